@@ -1,21 +1,37 @@
-use crate::models::splunk::{JobStatus, SearchJob};
+use crate::models::splunk::{
+    JobStatus, JobSummary, SearchJob, ServerSavedSearch, SplunkApp, SplunkError, TimeRange,
+    TimelineBucket,
+};
 use log::error;
 use reqwest::Client;
 use serde_json::Value;
 use std::error::Error;
+use std::time::Duration;
+
+/// How long `run_search` waits for a job before cancelling it.
+const RUN_SEARCH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The owner/app context knowledge objects are resolved in (`/servicesNS/{owner}/{app}`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Namespace {
+    pub owner: String,
+    pub app: String,
+}
 
 #[derive(Clone)]
 pub struct SplunkClient {
     base_url: String,
     token: String,
     client: Client,
+    namespace: Option<Namespace>,
+    read_only: bool,
 }
 
 impl SplunkClient {
     pub fn new(base_url: String, token: String, verify_ssl: bool) -> Self {
         let client = Client::builder()
             .danger_accept_invalid_certs(!verify_ssl)
-            .timeout(std::time::Duration::from_secs(10))
+            .timeout(Duration::from_secs(10))
             .build()
             .expect("Failed to build HTTP client");
 
@@ -30,19 +46,105 @@ impl SplunkClient {
             base_url,
             token,
             client,
+            namespace: None,
+            read_only: false,
+        }
+    }
+
+    /// Returns a copy of this client scoped to the given owner/app namespace.
+    pub fn with_namespace(&self, namespace: Option<Namespace>) -> Self {
+        Self {
+            namespace,
+            ..self.clone()
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&Namespace> {
+        self.namespace.as_ref()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns a copy of this client that refuses write-side SPL, job control
+    /// and knowledge-object changes.
+    pub fn with_read_only(&self, read_only: bool) -> Self {
+        Self {
+            read_only,
+            ..self.clone()
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn ensure_writable(&self, action: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.read_only {
+            return Err(format!("Read-only mode: refusing to {}", action).into());
+        }
+        Ok(())
+    }
+
+    fn ensure_read_only_spl(&self, query: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.read_only {
+            let writes = crate::spl::pipeline::write_commands(query);
+            if !writes.is_empty() {
+                return Err(format!(
+                    "Read-only mode: refusing to run SPL with write commands ({})",
+                    writes.join(", ")
+                )
+                .into());
+            }
+            let unchecked = crate::spl::pipeline::unchecked_parts(query);
+            if !unchecked.is_empty() {
+                return Err(format!(
+                    "Read-only mode: refusing to run SPL that can't be checked for writes ({})",
+                    unchecked.join(", ")
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds an endpoint URL, routing through `/servicesNS/{owner}/{app}` when a
+    /// namespace is set so app-scoped macros, lookups and eventtypes resolve.
+    fn endpoint(&self, path: &str) -> String {
+        match &self.namespace {
+            Some(ns) => format!(
+                "{}/servicesNS/{}/{}/{}",
+                self.base_url,
+                urlencoding::encode(&ns.owner),
+                urlencoding::encode(&ns.app),
+                path
+            ),
+            None => format!("{}/services/{}", self.base_url, path),
         }
     }
 
-    pub async fn create_search(&self, query: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-        let url = format!("{}/services/search/jobs", self.base_url);
+    pub async fn create_search(
+        &self,
+        query: &str,
+        range: &TimeRange,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.ensure_read_only_spl(query)?;
+        let url = self.endpoint("search/jobs");
 
         let formatted_query = format_query(query);
 
-        let params = [
+        let mut params = vec![
             ("search", formatted_query.as_str()),
             ("output_mode", "json"),
             ("exec_mode", "normal"),
         ];
+        if let Some(earliest) = &range.earliest {
+            params.push(("earliest_time", earliest));
+        }
+        if let Some(latest) = &range.latest {
+            params.push(("latest_time", latest));
+        }
 
         let response = self
             .client
@@ -68,7 +170,7 @@ impl SplunkClient {
         &self,
         sid: &str,
     ) -> Result<JobStatus, Box<dyn Error + Send + Sync>> {
-        let url = format!("{}/services/search/jobs/{}", self.base_url, sid);
+        let url = self.endpoint(&format!("search/jobs/{}", sid));
 
         let response = self
             .client
@@ -113,7 +215,7 @@ impl SplunkClient {
         count: u32,
         offset: u32,
     ) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
-        let url = format!("{}/services/search/jobs/{}/results", self.base_url, sid);
+        let url = self.endpoint(&format!("search/jobs/{}/results", sid));
 
         let response = self
             .client
@@ -145,38 +247,503 @@ impl SplunkClient {
         Ok(vec![])
     }
 
-    #[allow(dead_code)]
-    pub async fn delete_job(&self, sid: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let url = format!("{}/services/search/jobs/{}", self.base_url, sid);
+    /// Dispatches `query`, waits for the job to finish and returns up to
+    /// `count` results (0 for all).
+    pub async fn run_search(
+        &self,
+        query: &str,
+        range: &TimeRange,
+        count: u32,
+    ) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
+        let sid = self.create_search(query, range).await?;
+        self.wait_for_results(&sid, count).await
+    }
+
+    /// Polls a job until it is done and returns its first `count` results.
+    /// A job still running after `RUN_SEARCH_TIMEOUT` is cancelled.
+    pub async fn wait_for_results(
+        &self,
+        sid: &str,
+        count: u32,
+    ) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
+        let deadline = tokio::time::Instant::now() + RUN_SEARCH_TIMEOUT;
+        loop {
+            let status = self.get_job_status(sid).await?;
+            if status.dispatch_state == "FAILED" {
+                return Err(format!("Search job {} failed", sid).into());
+            }
+            if status.is_done {
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                if let Err(e) = self.cancel_job(sid).await {
+                    error!("Failed to cancel job {}: {}", sid, e);
+                }
+                return Err(format!(
+                    "Search job {} timed out after {}s",
+                    sid,
+                    RUN_SEARCH_TIMEOUT.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        self.get_results(sid, count, 0).await
+    }
+
+    /// Event counts over the job's whole time range, from `/timeline`.
+    pub async fn get_timeline(
+        &self,
+        sid: &str,
+    ) -> Result<Vec<TimelineBucket>, Box<dyn Error + Send + Sync>> {
+        let url = self.endpoint(&format!("search/jobs/{}/timeline", sid));
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let json: Value = serde_json::from_str(&text)?;
+        match json.get("buckets") {
+            Some(buckets) => Ok(serde_json::from_value(buckets.clone())?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Stops a job this client started. Allowed in read-only mode: it only
+    /// cancels our own search, so a runaway query can always be stopped.
+    pub async fn cancel_job(&self, sid: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.endpoint(&format!("search/jobs/{}/control", sid));
 
         let response = self
             .client
-            .delete(&url)
+            .post(&url)
             .header("Authorization", format!("Bearer {}", self.token))
+            .form(&[("action", "cancel"), ("output_mode", "json")])
             .send()
             .await?;
 
         if !response.status().is_success() {
-            return Err(format!("Failed to delete job: {}", response.status()).into());
+            return Err(format!("Failed to cancel job: {}", response.status()).into());
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_apps(&self) -> Result<Vec<SplunkApp>, Box<dyn Error + Send + Sync>> {
+        // Apps are a global collection, so this intentionally ignores the namespace.
+        let url = format!("{}/services/apps/local", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json"), ("count", "0")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let json: Value = serde_json::from_str(&text)?;
+        let mut apps: Vec<SplunkApp> = json
+            .get("entry")
+            .and_then(|e| e.as_array())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| {
+                        let name = entry.get("name")?.as_str()?.to_string();
+                        let content = entry.get("content");
+                        let flag = |key: &str| {
+                            content
+                                .and_then(|c| c.get(key))
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false)
+                        };
+                        Some(SplunkApp {
+                            label: content
+                                .and_then(|c| c.get("label"))
+                                .and_then(|v| v.as_str())
+                                .unwrap_or(&name)
+                                .to_string(),
+                            visible: flag("visible"),
+                            disabled: flag("disabled"),
+                            name,
+                        })
+                    })
+                    .filter(|app| !app.disabled)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(apps)
+    }
+
+    /// Lists saved searches visible to the user across all apps and owners.
+    pub async fn list_saved_searches(
+        &self,
+    ) -> Result<Vec<ServerSavedSearch>, Box<dyn Error + Send + Sync>> {
+        let url = format!("{}/servicesNS/-/-/saved/searches", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json"), ("count", "0")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let json: Value = serde_json::from_str(&text)?;
+        let mut searches: Vec<ServerSavedSearch> = json
+            .get("entry")
+            .and_then(|e| e.as_array())
+            .map(|entries| entries.iter().filter_map(parse_saved_search).collect())
+            .unwrap_or_default();
+
+        searches.sort_by_key(|s| s.name.to_lowercase());
+        Ok(searches)
+    }
+
+    /// Source (Simple XML, or Dashboard Studio wrapped in XML) of a dashboard
+    /// in any app.
+    pub async fn get_dashboard(&self, name: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let url = format!(
+            "{}/servicesNS/-/-/data/ui/views/{}",
+            self.base_url,
+            urlencoding::encode(name)
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let json: Value = serde_json::from_str(&text)?;
+        json.get("entry")
+            .and_then(|e| e.get(0))
+            .and_then(|e| e.get("content"))
+            .and_then(|c| c.get("eai:data"))
+            .and_then(|d| d.as_str())
+            .map(String::from)
+            .ok_or_else(|| format!("Dashboard '{}' has no definition", name).into())
+    }
+
+    /// Lists the jobs currently held in the dispatch directory.
+    pub async fn list_jobs(&self) -> Result<Vec<JobSummary>, Box<dyn Error + Send + Sync>> {
+        let url = self.endpoint("search/jobs");
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json"), ("count", "0")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let json: Value = serde_json::from_str(&text)?;
+        Ok(parse_job_entries(&json))
+    }
+
+    /// Dispatches a saved search and returns the sid of the new job.
+    pub async fn dispatch_saved_search(
+        &self,
+        saved: &ServerSavedSearch,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.ensure_read_only_spl(&saved.search)?;
+        let url = saved_search_url(&self.base_url, saved, "/dispatch");
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .form(&[("output_mode", "json")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            error!("Splunk API Error {}: {}", status, text);
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
+
+        let job: SearchJob = serde_json::from_str(&text)?;
+        Ok(job.sid)
+    }
+
+    /// Returns the sid of the most recent scheduled run of a saved search,
+    /// if any.
+    pub async fn latest_saved_search_run(
+        &self,
+        saved: &ServerSavedSearch,
+    ) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        let url = saved_search_url(&self.base_url, saved, "/history");
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .query(&[("output_mode", "json"), ("count", "0")])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(format!("API Error {}: {}", status, text).into());
         }
 
+        let json: Value = serde_json::from_str(&text)?;
+        Ok(latest_scheduled_run(&json))
+    }
+
+    /// Enables or disables the schedule of a saved search.
+    pub async fn set_saved_search_scheduled(
+        &self,
+        saved: &ServerSavedSearch,
+        scheduled: bool,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.ensure_writable("change saved search schedules")?;
+        let url = saved_search_url(&self.base_url, saved, "");
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .form(&[
+                ("output_mode", "json"),
+                ("is_scheduled", if scheduled { "1" } else { "0" }),
+            ])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await?;
+            return Err(format!("API Error {}: {}", status, text).into());
+        }
         Ok(())
     }
 
-    pub fn get_shareable_url(&self, sid: &str) -> String {
-        // Assume base_url is like https://splunk.example.com:8089 or https://splunk.example.com
-        // We need the web interface URL.
-        // Usually, the management port is 8089. The web port is 8000 (HTTP) or 443 (HTTPS/Cloud).
-        // Let's assume the user configures SPLUNK_BASE_URL as the management URL.
-        // If it contains :8089, we remove it, assuming the web interface is on the default port (443).
-        // This fixes links for Splunk Cloud and standard HTTPS deployments.
+    /// Creates a saved search in the given owner/app namespace.
+    pub async fn create_saved_search(
+        &self,
+        namespace: &Namespace,
+        name: &str,
+        search: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.ensure_writable("create saved searches")?;
+        let url = format!(
+            "{}/servicesNS/{}/{}/saved/searches",
+            self.base_url,
+            urlencoding::encode(&namespace.owner),
+            urlencoding::encode(&namespace.app)
+        );
 
-        // A robust way is to ask the user for WEB_URL, but we didn't add that to config.
-        // We will try to replace :8089 with empty string if present, otherwise append path.
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .form(&[("output_mode", "json"), ("name", name), ("search", search)])
+            .send()
+            .await?;
+
+        check_write_response(response).await
+    }
+
+    /// Replaces the SPL of an existing saved search.
+    pub async fn update_saved_search(
+        &self,
+        saved: &ServerSavedSearch,
+        search: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.ensure_writable("update saved searches")?;
+        let url = saved_search_url(&self.base_url, saved, "");
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .form(&[("output_mode", "json"), ("search", search)])
+            .send()
+            .await?;
+
+        check_write_response(response).await
+    }
+}
+
+/// Splunk Web URL of the job `sid` on the server at `base_url`.
+pub fn shareable_url(base_url: &str, namespace: Option<&Namespace>, sid: &str) -> String {
+    // Assume base_url is like https://splunk.example.com:8089 or https://splunk.example.com
+    // We need the web interface URL.
+    // Usually, the management port is 8089. The web port is 8000 (HTTP) or 443 (HTTPS/Cloud).
+    // Let's assume the user configures SPLUNK_BASE_URL as the management URL.
+    // If it contains :8089, we remove it, assuming the web interface is on the default port (443).
+    // This fixes links for Splunk Cloud and standard HTTPS deployments.
+
+    // A robust way is to ask the user for WEB_URL, but we didn't add that to config.
+    // We will try to replace :8089 with empty string if present, otherwise append path.
+
+    let web_url = base_url.replace(":8089", "");
+    let app = namespace.map(|ns| ns.app.as_str()).unwrap_or("search");
+    format!("{}/en-US/app/{}/search?sid={}", web_url, app, sid)
+}
 
-        let web_url = self.base_url.replace(":8089", "");
-        format!("{}/en-US/app/search/search?sid={}", web_url, sid)
+/// Maps a knowledge-object write response to an error, calling out ACL denials.
+async fn check_write_response(
+    response: reqwest::Response,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
     }
+
+    let text = response.text().await?;
+    let message = serde_json::from_str::<SplunkError>(&text)
+        .ok()
+        .and_then(|e| e.messages.into_iter().next())
+        .map(|m| m.text)
+        .unwrap_or(text);
+
+    if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(format!("Permission denied by ACL: {}", message).into());
+    }
+    error!("Splunk API Error {}: {}", status, message);
+    Err(format!("API Error {}: {}", status, message).into())
+}
+
+/// Entity URL of a saved search in its own owner/app namespace.
+fn saved_search_url(base_url: &str, saved: &ServerSavedSearch, suffix: &str) -> String {
+    format!(
+        "{}/servicesNS/{}/{}/saved/searches/{}{}",
+        base_url,
+        urlencoding::encode(&saved.owner),
+        urlencoding::encode(&saved.app),
+        urlencoding::encode(&saved.name),
+        suffix
+    )
+}
+
+/// Splunk reports booleans either as JSON bools or as "0"/"1" strings.
+fn flag(content: &Value, key: &str) -> bool {
+    match content.get(key) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => s == "1" || s.eq_ignore_ascii_case("true"),
+        Some(Value::Number(n)) => n.as_i64() == Some(1),
+        _ => false,
+    }
+}
+
+/// The newest scheduled run in a saved search's `/history`. History entries
+/// carry the sid as their name and also list ad-hoc dispatches, which are
+/// skipped.
+fn latest_scheduled_run(json: &Value) -> Option<String> {
+    json.get("entry")?
+        .as_array()?
+        .iter()
+        .filter(|entry| entry.get("content").is_some_and(|c| flag(c, "isScheduled")))
+        .filter_map(|entry| {
+            let sid = entry.get("name")?.as_str()?;
+            let published = entry
+                .get("published")
+                .and_then(|p| p.as_str())
+                .unwrap_or("");
+            Some((published, sid))
+        })
+        .max()
+        .map(|(_, sid)| sid.to_string())
+}
+
+fn parse_saved_search(entry: &Value) -> Option<ServerSavedSearch> {
+    let name = entry.get("name")?.as_str()?.to_string();
+    let content = entry.get("content")?;
+    let acl = entry.get("acl");
+    let text = |v: Option<&Value>, key: &str| {
+        v.and_then(|c| c.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let flag = |key: &str| flag(content, key);
+
+    Some(ServerSavedSearch {
+        name,
+        app: text(acl, "app"),
+        owner: text(acl, "owner"),
+        search: text(Some(content), "search"),
+        description: text(Some(content), "description"),
+        cron_schedule: text(Some(content), "cron_schedule"),
+        is_scheduled: flag("is_scheduled"),
+        disabled: flag("disabled"),
+        earliest_time: text(Some(content), "dispatch.earliest_time"),
+        latest_time: text(Some(content), "dispatch.latest_time"),
+        can_write: acl
+            .and_then(|a| a.get("can_write"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true),
+        last_run: None,
+    })
+}
+
+fn parse_job_entries(json: &Value) -> Vec<JobSummary> {
+    json.get("entry")
+        .and_then(|e| e.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let mut content = entry.get("content")?.clone();
+                    if let Some(obj) = content.as_object_mut() {
+                        obj.entry("published")
+                            .or_insert_with(|| entry.get("published").cloned().unwrap_or_default());
+                    }
+                    serde_json::from_value(content).ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 fn format_query(query: &str) -> String {
@@ -211,30 +778,145 @@ mod tests {
 
         // Leading whitespace
         assert_eq!(format_query("  index=main  "), "| search index=main");
+
+        // Formatter output passes through unchanged
+        let formatted = crate::spl::format::format("| tstats count by host | sort - count");
+        assert_eq!(format_query(&formatted), formatted);
+        let formatted = crate::spl::format::format("index=main | head 5");
+        assert_eq!(format_query(&formatted), format!("| search {}", formatted));
     }
 
     #[test]
-    fn test_get_shareable_url() {
-        let client = SplunkClient::new(
-            "https://splunk.example.com:8089".to_string(),
-            "token".to_string(),
-            false,
+    fn test_read_only_refuses_writes() {
+        let client = SplunkClient::new("https://splunk:8089".to_string(), "t".to_string(), false);
+        assert!(client
+            .ensure_read_only_spl("index=x | outputlookup y")
+            .is_ok());
+
+        let client = client.with_read_only(true);
+        assert!(client.ensure_read_only_spl("index=x | stats count").is_ok());
+        let err = client
+            .ensure_read_only_spl("index=x [| inputlookup a | sendalert notify] | delete")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Read-only mode: refusing to run SPL with write commands (sendalert, delete)"
+        );
+        let err = client
+            .ensure_read_only_spl(r#"| makeresults | map search="search index=x | delete""#)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Read-only mode: refusing to run SPL that can't be checked for writes (map)"
         );
-        let url = client.get_shareable_url("12345");
+        let err = client
+            .ensure_read_only_spl("`mymacro` | stats count")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Read-only mode: refusing to run SPL that can't be checked for writes (`mymacro`)"
+        );
+        assert!(client.ensure_writable("create saved searches").is_err());
+    }
+
+    #[test]
+    fn test_shareable_url() {
+        let url = shareable_url("https://splunk.example.com:8089", None, "12345");
         assert_eq!(
             url,
             "https://splunk.example.com/en-US/app/search/search?sid=12345"
         );
 
-        let client2 = SplunkClient::new(
-            "https://splunk.example.com".to_string(),
+        let namespace = Namespace {
+            owner: "nobody".to_string(),
+            app: "detections".to_string(),
+        };
+        let url2 = shareable_url("https://splunk.example.com", Some(&namespace), "67890");
+        assert_eq!(
+            url2,
+            "https://splunk.example.com/en-US/app/detections/search?sid=67890"
+        );
+    }
+
+    #[test]
+    fn test_parse_saved_search() {
+        let entry = serde_json::json!({
+            "name": "Failed Logins",
+            "acl": { "app": "detections", "owner": "admin" },
+            "content": {
+                "search": "index=auth action=failure",
+                "cron_schedule": "*/15 * * * *",
+                "is_scheduled": "1",
+                "disabled": false,
+                "dispatch.earliest_time": "-15m"
+            }
+        });
+        let saved = parse_saved_search(&entry).unwrap();
+        assert_eq!(saved.name, "Failed Logins");
+        assert_eq!(saved.app, "detections");
+        assert!(saved.is_scheduled);
+        assert!(!saved.disabled);
+        assert_eq!(saved.earliest_time, "-15m");
+        assert_eq!(
+            saved_search_url("https://h:8089", &saved, "/dispatch"),
+            "https://h:8089/servicesNS/admin/detections/saved/searches/Failed%20Logins/dispatch"
+        );
+    }
+
+    #[test]
+    fn test_latest_scheduled_run() {
+        let history = serde_json::json!({
+            "entry": [
+                {
+                    "name": "scheduler__admin__search__RMD5_at_1700000000_1",
+                    "published": "2023-11-14T22:13:20+00:00",
+                    "content": { "isScheduled": true }
+                },
+                {
+                    "name": "admin__admin__search__RMD5_1700003600.2",
+                    "published": "2023-11-14T23:13:20+00:00",
+                    "content": { "isScheduled": false }
+                },
+                {
+                    "name": "scheduler__admin__search__RMD5_at_1699996400_0",
+                    "published": "2023-11-14T21:13:20+00:00",
+                    "content": { "isScheduled": "1" }
+                }
+            ]
+        });
+        assert_eq!(
+            latest_scheduled_run(&history).as_deref(),
+            Some("scheduler__admin__search__RMD5_at_1700000000_1")
+        );
+        assert_eq!(
+            latest_scheduled_run(&serde_json::json!({ "entry": [] })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_namespaced_endpoints() {
+        let client = SplunkClient::new(
+            "https://splunk.example.com:8089/".to_string(),
             "token".to_string(),
             false,
         );
-        let url2 = client2.get_shareable_url("67890");
         assert_eq!(
-            url2,
-            "https://splunk.example.com/en-US/app/search/search?sid=67890"
+            client.endpoint("search/jobs"),
+            "https://splunk.example.com:8089/services/search/jobs"
+        );
+
+        let scoped = client.with_namespace(Some(Namespace {
+            owner: "nobody".to_string(),
+            app: "detections".to_string(),
+        }));
+        assert_eq!(
+            scoped.endpoint("search/jobs"),
+            "https://splunk.example.com:8089/servicesNS/nobody/detections/search/jobs"
+        );
+        assert_eq!(
+            shareable_url(scoped.base_url(), scoped.namespace(), "1"),
+            "https://splunk.example.com/en-US/app/detections/search?sid=1"
         );
     }
 }
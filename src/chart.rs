@@ -0,0 +1,211 @@
+//! Series extraction for the chart view of transforming results
+//! (`timechart`, `chart`, `stats`).
+
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChartKind {
+    Line,
+    Bar,
+    StackedArea,
+}
+
+impl ChartKind {
+    pub fn next(self) -> Self {
+        match self {
+            ChartKind::Line => ChartKind::Bar,
+            ChartKind::Bar => ChartKind::StackedArea,
+            ChartKind::StackedArea => ChartKind::Line,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChartKind::Line => "Line",
+            ChartKind::Bar => "Bar",
+            ChartKind::StackedArea => "Stacked Area",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Series {
+    pub name: String,
+    /// One value per x position; `None` where the row has no number.
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug)]
+pub struct ChartData {
+    pub x_field: String,
+    pub x_labels: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_blank(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// `_time` as `MM-DD HH:MM`, or the raw value for anything else.
+fn x_label(field: &str, value: Option<&Value>) -> String {
+    let raw = match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    };
+    if field == "_time" {
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&raw) {
+            return dt.format("%m-%d %H:%M").to_string();
+        }
+    }
+    raw
+}
+
+impl ChartData {
+    /// Uses `_time` (or else the first column) as the x-axis and every other
+    /// column whose values are all numeric as a series. Internal fields like
+    /// `_span` are skipped.
+    pub fn from_results(results: &[Value]) -> Option<ChartData> {
+        let mut columns: Vec<&str> = Vec::new();
+        for row in results {
+            for key in row.as_object()?.keys() {
+                if !columns.contains(&key.as_str()) {
+                    columns.push(key);
+                }
+            }
+        }
+
+        let x_field = if columns.contains(&"_time") {
+            "_time"
+        } else {
+            *columns.iter().find(|c| !c.starts_with('_'))?
+        };
+
+        let series: Vec<Series> = columns
+            .iter()
+            .filter(|c| **c != x_field && !c.starts_with('_'))
+            .filter_map(|column| {
+                let cells: Vec<Option<&Value>> = results.iter().map(|r| r.get(column)).collect();
+                let numeric = cells
+                    .iter()
+                    .all(|cell| is_blank(*cell) || cell.and_then(number).is_some());
+                if !numeric || cells.iter().all(|cell| is_blank(*cell)) {
+                    return None;
+                }
+                Some(Series {
+                    name: column.to_string(),
+                    values: cells.iter().map(|cell| cell.and_then(number)).collect(),
+                })
+            })
+            .collect();
+        if series.is_empty() {
+            return None;
+        }
+
+        Some(ChartData {
+            x_field: x_field.to_string(),
+            x_labels: results
+                .iter()
+                .map(|r| x_label(x_field, r.get(x_field)))
+                .collect(),
+            series,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.x_labels.len()
+    }
+
+    /// Running totals of the visible series at each x, bottom series first.
+    pub fn stacked(&self, visible: &[bool]) -> Vec<Vec<f64>> {
+        let mut totals = vec![0.0; self.len()];
+        self.series
+            .iter()
+            .zip(visible)
+            .filter(|(_, shown)| **shown)
+            .map(|(series, _)| {
+                for (total, value) in totals.iter_mut().zip(&series.values) {
+                    *total += value.unwrap_or(0.0);
+                }
+                totals.clone()
+            })
+            .collect()
+    }
+
+    /// Y-axis bounds over the visible series, always including zero.
+    pub fn bounds(&self, visible: &[bool], stacked: bool) -> (f64, f64) {
+        let values: Vec<f64> = if stacked {
+            self.stacked(visible).into_iter().flatten().collect()
+        } else {
+            self.series
+                .iter()
+                .zip(visible)
+                .filter(|(_, shown)| **shown)
+                .flat_map(|(series, _)| series.values.iter().flatten().copied())
+                .collect()
+        };
+        let min = values.iter().copied().fold(0.0, f64::min);
+        let max = values.iter().copied().fold(0.0, f64::max);
+        if max > min {
+            (min, max)
+        } else {
+            (min, min + 1.0)
+        }
+    }
+}
+
+/// Whole numbers without decimals, everything else to two places.
+pub fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_results() {
+        let results = vec![
+            json!({"_time": "2024-03-01T10:00:00.000+00:00", "web": "3", "db": "1", "_span": "60"}),
+            json!({"_time": "2024-03-01T10:01:00.000+00:00", "web": "5", "db": ""}),
+        ];
+        let data = ChartData::from_results(&results).unwrap();
+        assert_eq!(data.x_field, "_time");
+        assert_eq!(data.x_labels, vec!["03-01 10:00", "03-01 10:01"]);
+        let names: Vec<&str> = data.series.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["web", "db"]);
+        assert_eq!(data.series[1].values, vec![Some(1.0), None]);
+        assert_eq!(
+            data.stacked(&[true, true]),
+            vec![vec![3.0, 5.0], vec![4.0, 5.0]]
+        );
+        assert_eq!(data.bounds(&[false, true], false), (0.0, 1.0));
+
+        // `stats count by host`: the first column is the x-axis.
+        let results = vec![
+            json!({"host": "a", "count": 4, "owner": "ops"}),
+            json!({"host": "b", "count": 7, "owner": "dev"}),
+        ];
+        let data = ChartData::from_results(&results).unwrap();
+        assert_eq!(data.x_field, "host");
+        assert_eq!(data.series.len(), 1);
+        assert!(ChartData::from_results(&[json!({"host": "a"})]).is_none());
+    }
+}
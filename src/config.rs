@@ -1,9 +1,14 @@
+use crate::api::Namespace;
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use keyring::Entry;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
+use toml_edit::{value, DocumentMut, Item, Table, TableLike};
+
+pub const DEFAULT_PROFILE: &str = "default";
 
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct Config {
@@ -11,11 +16,38 @@ pub struct Config {
     pub splunk_token: String,
     pub splunk_verify_ssl: bool,
     pub theme: Option<String>,
+    /// Name of the active profile (`[profiles.<name>]` in config.toml).
+    pub profile: String,
+    /// App namespace searches and knowledge objects are resolved in.
+    pub app: Option<String>,
+    /// Owner namespace used together with `app` (defaults to `nobody`).
+    pub owner: Option<String>,
+    /// Refuse write-side SPL and server-side changes (per profile).
+    pub read_only: bool,
+    /// Lint rule ids (e.g. `all-time`) that should not warn before a search.
+    pub lint_disable: Vec<String>,
+    /// Command that receives copied text on stdin, e.g. `wl-copy`; OSC 52
+    /// is used when unset or when it fails.
+    pub clipboard_command: Option<String>,
+    /// `[keys.<mode>]` overrides: action name to key names.
+    pub keys: BTreeMap<String, BTreeMap<String, Vec<String>>>,
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let mut config = Config::default();
+        Self::load_profile(None)
+    }
+
+    /// Loads the config with `profile` instead of the default one.
+    pub fn load_profile(profile: Option<&str>) -> Result<Self> {
+        let mut config = Config {
+            profile: profile.unwrap_or_default().to_string(),
+            ..Config::default()
+        };
+
+        // The chosen profile's own settings, which win over the environment
+        // and the keyring when the profile was picked explicitly.
+        let mut chosen = None;
 
         // 1. Load from Config File (Global)
         if let Some(proj_dirs) = ProjectDirs::from("", "", "spelunktui") {
@@ -29,12 +61,24 @@ impl Config {
 
                 // Handle parsing errors gracefully
                 match toml::from_str::<FileConfig>(&content) {
-                    Ok(file_config) => config.merge(file_config),
+                    Ok(file_config) => {
+                        if let Some(name) = profile {
+                            if name != DEFAULT_PROFILE && !file_config.profiles.contains_key(name) {
+                                anyhow::bail!("No profile '{}' in config.toml", name);
+                            }
+                        }
+                        chosen = config.merge(file_config);
+                    }
                     Err(e) => warn!("Failed to parse config.toml: {}", e),
                 }
             }
         }
 
+        if config.profile.is_empty() {
+            config.profile =
+                env::var("SPLUNK_PROFILE").unwrap_or_else(|_| DEFAULT_PROFILE.to_string());
+        }
+
         // 2. Load from Keyring (if token is missing)
         if config.splunk_token.is_empty() {
             let service = "spelunktui";
@@ -58,6 +102,20 @@ impl Config {
             config.splunk_verify_ssl = val.parse().unwrap_or(false);
         }
 
+        if let (Some(name), Some(chosen)) = (profile, chosen) {
+            let overridden = [
+                ("SPLUNK_BASE_URL", chosen.splunk_base_url.is_some()),
+                ("SPLUNK_TOKEN", chosen.splunk_token.is_some()),
+                ("SPLUNK_VERIFY_SSL", chosen.splunk_verify_ssl.is_some()),
+            ];
+            for (var, set) in overridden {
+                if set && env::var_os(var).is_some() {
+                    warn!("Profile '{}' sets its own value; ignoring {}", name, var);
+                }
+            }
+            config.merge_profile(chosen);
+        }
+
         Ok(config)
     }
 
@@ -78,10 +136,39 @@ pub struct FileConfig {
     pub splunk_token: Option<String>,
     pub splunk_verify_ssl: Option<bool>,
     pub theme: Option<String>,
+    /// Profile used when `SPLUNK_PROFILE` is not set.
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lint_disable: Vec<String>,
+    pub clipboard_command: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, BTreeMap<String, KeyList>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// One key name or several, e.g. `quit = "q"` or `help = ["ctrl+/", "f1"]`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Per-profile overrides, stored as `[profiles.<name>]` tables.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct ProfileConfig {
+    pub splunk_base_url: Option<String>,
+    pub splunk_token: Option<String>,
+    pub splunk_verify_ssl: Option<bool>,
+    pub app: Option<String>,
+    pub owner: Option<String>,
+    pub read_only: Option<bool>,
 }
 
 impl Config {
-    fn merge(&mut self, other: FileConfig) {
+    /// Applies the file's settings and returns the profile section used.
+    fn merge(&mut self, mut other: FileConfig) -> Option<ProfileConfig> {
         if let Some(v) = other.splunk_base_url {
             self.splunk_base_url = v;
         }
@@ -94,26 +181,189 @@ impl Config {
         if let Some(v) = other.theme {
             self.theme = Some(v);
         }
+        self.lint_disable = other.lint_disable;
+        self.clipboard_command = other.clipboard_command;
+        self.keys = other
+            .keys
+            .into_iter()
+            .map(|(mode, actions)| {
+                let actions = actions
+                    .into_iter()
+                    .map(|(action, keys)| match keys {
+                        KeyList::One(key) => (action, vec![key]),
+                        KeyList::Many(keys) => (action, keys),
+                    })
+                    .collect();
+                (mode, actions)
+            })
+            .collect();
+
+        // Profile selection: an explicit one, then the environment, then the
+        // file's default.
+        if self.profile.is_empty() {
+            self.profile = env::var("SPLUNK_PROFILE")
+                .ok()
+                .or(other.profile)
+                .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        }
+
+        let profile = other.profiles.remove(&self.profile)?;
+        self.merge_profile(profile.clone());
+        Some(profile)
     }
 
-    pub fn save_theme(theme_name: &str) -> Result<()> {
+    fn merge_profile(&mut self, profile: ProfileConfig) {
+        if let Some(v) = profile.splunk_base_url {
+            self.splunk_base_url = v;
+        }
+        if let Some(v) = profile.splunk_token {
+            self.splunk_token = v;
+        }
+        if let Some(v) = profile.splunk_verify_ssl {
+            self.splunk_verify_ssl = v;
+        }
+        if profile.app.is_some() {
+            self.app = profile.app;
+        }
+        if profile.owner.is_some() {
+            self.owner = profile.owner;
+        }
+        if let Some(v) = profile.read_only {
+            self.read_only = v;
+        }
+    }
+
+    /// Reads config.toml, applies `update` and writes it back. Only the keys
+    /// `update` touches change; comments, unknown keys and formatting are
+    /// kept. A file that doesn't parse is left alone rather than replaced.
+    fn update_file_config(update: impl FnOnce(&mut DocumentMut)) -> Result<()> {
         if let Some(proj_dirs) = ProjectDirs::from("", "", "spelunktui") {
             let config_dir = proj_dirs.config_dir();
             std::fs::create_dir_all(config_dir)?;
             let config_path = config_dir.join("config.toml");
 
-            // Read existing or create new
-            let mut file_config: FileConfig = if config_path.exists() {
-                let content = std::fs::read_to_string(&config_path)?;
-                toml::from_str(&content).unwrap_or_default()
+            let content = if config_path.exists() {
+                std::fs::read_to_string(&config_path)?
             } else {
-                FileConfig::default()
+                String::new()
             };
-
-            file_config.theme = Some(theme_name.to_string());
-            let toml_string = toml::to_string(&file_config)?;
-            std::fs::write(config_path, toml_string)?;
+            std::fs::write(config_path, edit_file_config(&content, update)?)?;
         }
         Ok(())
     }
+
+    pub fn save_theme(theme_name: &str) -> Result<()> {
+        Self::update_file_config(|doc| set_str(doc.as_table_mut(), "theme", theme_name))
+    }
+
+    /// Owner/app namespace for `SplunkClient`, if an app is configured.
+    pub fn namespace(&self) -> Option<Namespace> {
+        self.app.as_ref().map(|app| Namespace {
+            owner: self.owner.clone().unwrap_or_else(|| "nobody".to_string()),
+            app: app.clone(),
+        })
+    }
+
+    /// Persists the selected app namespace for `profile`.
+    pub fn save_app(profile: &str, app: Option<&str>) -> Result<()> {
+        Self::update_file_config(|doc| set_profile_app(doc, profile, app))
+    }
+}
+
+/// Applies `update` to the TOML in `content`, refusing a file that isn't a
+/// valid config.
+fn edit_file_config(content: &str, update: impl FnOnce(&mut DocumentMut)) -> Result<String> {
+    toml::from_str::<FileConfig>(content)
+        .map_err(|e| anyhow::anyhow!("config.toml is invalid, not changing it: {}", e.message()))?;
+    let mut doc: DocumentMut = content.parse()?;
+    update(&mut doc);
+    Ok(doc.to_string())
+}
+
+/// Sets `key` to `text`, keeping the comments around an existing value.
+fn set_str(table: &mut dyn TableLike, key: &str, text: &str) {
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = text.into();
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, value(text));
+        }
+    }
+}
+
+fn set_profile_app(doc: &mut DocumentMut, profile: &str, app: Option<&str>) {
+    let profiles = doc.entry("profiles").or_insert_with(|| {
+        let mut profiles = Table::new();
+        profiles.set_implicit(true);
+        Item::Table(profiles)
+    });
+    let Some(profiles) = profiles.as_table_like_mut() else {
+        return;
+    };
+    if app.is_none() && !profiles.contains_key(profile) {
+        return;
+    }
+    let Some(table) = profiles
+        .entry(profile)
+        .or_insert(Item::Table(Table::new()))
+        .as_table_like_mut()
+    else {
+        return;
+    };
+    match app {
+        Some(app) => set_str(table, "app", app),
+        None => {
+            table.remove("app");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_keeps_comments_and_unknown_keys() {
+        let content = r#"# my setup
+theme = "Neon"   # dark
+future_option = 1
+
+[profiles.prod]
+# production search head
+splunk_base_url = "https://prod:8089"
+"#;
+        let edited = edit_file_config(content, |doc| {
+            set_str(doc.as_table_mut(), "theme", "Light");
+            set_profile_app(doc, "prod", Some("detections"));
+            set_profile_app(doc, "dev", Some("search"));
+        })
+        .unwrap();
+        assert_eq!(
+            edited,
+            r#"# my setup
+theme = "Light"   # dark
+future_option = 1
+
+[profiles.prod]
+# production search head
+splunk_base_url = "https://prod:8089"
+app = "detections"
+
+[profiles.dev]
+app = "search"
+"#
+        );
+
+        let cleared = edit_file_config(&edited, |doc| set_profile_app(doc, "prod", None)).unwrap();
+        assert!(!cleared.contains("detections"));
+        assert!(cleared.contains("# production search head"));
+
+        let fresh =
+            edit_file_config("", |doc| set_str(doc.as_table_mut(), "theme", "Neon")).unwrap();
+        assert_eq!(fresh, "theme = \"Neon\"\n");
+        assert!(edit_file_config("theme = ", |_| {}).is_err());
+    }
 }
@@ -0,0 +1,471 @@
+//! Dashboards from Simple XML or Dashboard Studio definitions: their inputs
+//! (tokens) and the search, time range and visualization of each panel.
+
+use crate::chart::ChartKind;
+use crate::models::splunk::TimeRange;
+use crate::spl::template;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PanelKind {
+    Table,
+    Chart(ChartKind),
+    Single,
+    Events,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Input {
+    pub token: String,
+    pub label: String,
+    pub default: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Panel {
+    pub title: String,
+    /// SPL with `$token$` placeholders still in place.
+    pub query: String,
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+    pub kind: PanelKind,
+    pub refresh: Option<Duration>,
+    /// Panels sharing a row are laid out side by side.
+    pub row: usize,
+    /// Relative width within the row.
+    pub width: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dashboard {
+    pub title: String,
+    pub inputs: Vec<Input>,
+    pub panels: Vec<Panel>,
+}
+
+impl Panel {
+    /// The panel's query and time range with `values` substituted.
+    pub fn resolve(
+        &self,
+        values: &BTreeMap<String, String>,
+    ) -> Result<(String, TimeRange), String> {
+        let query = template::substitute(&self.query, values)?;
+        let time = |bound: &Option<String>| -> Result<Option<String>, String> {
+            match bound {
+                Some(text) => {
+                    let mut out = text.clone();
+                    for name in template::tokens(text) {
+                        let value = values
+                            .get(&name)
+                            .ok_or_else(|| format!("No value for token ${}$", name))?;
+                        out = out.replace(&format!("${}$", name), value);
+                    }
+                    Ok(Some(out).filter(|s| !s.is_empty()))
+                }
+                None => Ok(None),
+            }
+        };
+        Ok((
+            query,
+            TimeRange {
+                earliest: time(&self.earliest)?,
+                latest: time(&self.latest)?,
+            },
+        ))
+    }
+}
+
+/// `30s`, `5m`, `1h`, `1d` or plain seconds.
+pub fn parse_interval(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let amount: u64 = text[..split].parse().ok()?;
+    let seconds = match text[split..].trim() {
+        "" | "s" | "sec" => amount,
+        "m" | "min" => amount * 60,
+        "h" => amount * 3600,
+        "d" => amount * 86400,
+        _ => return None,
+    };
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+/// Parses a dashboard definition: Simple XML, a Dashboard Studio JSON
+/// document, or Studio JSON wrapped in `<dashboard version="2">`.
+pub fn parse(source: &str) -> Result<Dashboard, String> {
+    let trimmed = source.trim_start();
+    if trimmed.starts_with('{') {
+        return parse_studio(trimmed);
+    }
+    let doc = roxmltree::Document::parse(source).map_err(|e| format!("Invalid XML: {}", e))?;
+    let root = doc.root_element();
+    if let Some(definition) = child(root, "definition") {
+        return parse_studio(definition.text().unwrap_or_default());
+    }
+    Ok(parse_simple_xml(root))
+}
+
+fn child<'a, 'i>(node: roxmltree::Node<'a, 'i>, name: &str) -> Option<roxmltree::Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+}
+
+/// Closest chart view type for a Splunk chart type.
+fn chart_kind(name: &str) -> ChartKind {
+    match name.trim() {
+        "column" | "bar" => ChartKind::Bar,
+        "area" => ChartKind::StackedArea,
+        _ => ChartKind::Line,
+    }
+}
+
+fn parse_simple_xml(root: roxmltree::Node) -> Dashboard {
+    let mut dashboard = Dashboard {
+        title: child_text(root, "label").unwrap_or_default(),
+        ..Dashboard::default()
+    };
+    let form_refresh = root.attribute("refresh").and_then(parse_interval);
+
+    // A time input without a token sets the time range of every panel that
+    // doesn't have its own.
+    let mut global_time = (None, None);
+    for input in root.descendants().filter(|n| n.has_tag_name("input")) {
+        let label = child_text(input, "label");
+        let default = child(input, "default");
+        match (input.attribute("type"), input.attribute("token")) {
+            (Some("time"), token) => {
+                let earliest = default.and_then(|d| child_text(d, "earliest"));
+                let latest = default.and_then(|d| child_text(d, "latest"));
+                let Some(token) = token else {
+                    global_time = (earliest, latest);
+                    continue;
+                };
+                for (bound, value) in [("earliest", earliest), ("latest", latest)] {
+                    dashboard.inputs.push(Input {
+                        token: format!("{}.{}", token, bound),
+                        label: format!(
+                            "{} ({})",
+                            label.clone().unwrap_or(token.to_string()),
+                            bound
+                        ),
+                        default: value.unwrap_or_default(),
+                    });
+                }
+            }
+            (_, Some(token)) => dashboard.inputs.push(Input {
+                token: token.to_string(),
+                label: label.unwrap_or(token.to_string()),
+                default: default
+                    .and_then(|d| d.text())
+                    .map(|t| t.trim().to_string())
+                    .unwrap_or_default(),
+            }),
+            _ => {}
+        }
+    }
+
+    let visualizations = ["table", "chart", "single", "event", "viz"];
+    for (row_index, row) in root
+        .children()
+        .filter(|n| n.has_tag_name("row"))
+        .enumerate()
+    {
+        for panel in row.children().filter(|n| n.has_tag_name("panel")) {
+            for viz in panel
+                .children()
+                .filter(|n| visualizations.contains(&n.tag_name().name()))
+            {
+                let Some(search) = child(viz, "search") else {
+                    continue;
+                };
+                let Some(query) = child_text(search, "query") else {
+                    continue;
+                };
+                let earliest = child_text(search, "earliest");
+                let latest = child_text(search, "latest");
+                let uses_global = earliest.is_none() && latest.is_none();
+                dashboard.panels.push(Panel {
+                    title: child_text(viz, "title")
+                        .or_else(|| child_text(panel, "title"))
+                        .unwrap_or_default(),
+                    query,
+                    earliest: if uses_global {
+                        global_time.0.clone()
+                    } else {
+                        earliest
+                    },
+                    latest: if uses_global {
+                        global_time.1.clone()
+                    } else {
+                        latest
+                    },
+                    kind: match viz.tag_name().name() {
+                        "chart" | "viz" => PanelKind::Chart(chart_kind(
+                            viz.children()
+                                .find(|n| {
+                                    n.has_tag_name("option")
+                                        && n.attribute("name") == Some("charting.chart")
+                                })
+                                .and_then(|n| n.text())
+                                .unwrap_or("line"),
+                        )),
+                        "single" => PanelKind::Single,
+                        "event" => PanelKind::Events,
+                        _ => PanelKind::Table,
+                    },
+                    refresh: child_text(search, "refresh")
+                        .as_deref()
+                        .and_then(parse_interval)
+                        .or(form_refresh),
+                    row: row_index,
+                    width: 1,
+                });
+            }
+        }
+    }
+    dashboard
+}
+
+fn str_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a str> {
+    path.iter()
+        .try_fold(value, |v, key| v.get(key))
+        .and_then(|v| v.as_str())
+}
+
+fn parse_studio(json: &str) -> Result<Dashboard, String> {
+    let def: Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid dashboard JSON: {}", e))?;
+    let mut dashboard = Dashboard {
+        title: str_at(&def, &["title"]).unwrap_or_default().to_string(),
+        ..Dashboard::default()
+    };
+
+    if let Some(inputs) = def.get("inputs").and_then(|i| i.as_object()) {
+        for input in inputs.values() {
+            let Some(token) = str_at(input, &["options", "token"]) else {
+                continue;
+            };
+            let label = str_at(input, &["title"]).unwrap_or(token).to_string();
+            let default = input.get("options").and_then(|o| o.get("defaultValue"));
+            if str_at(input, &["type"]) == Some("input.timerange") {
+                // Default is "-24h,now" or split into earliest/latest.
+                let (earliest, latest) = match default {
+                    Some(Value::String(s)) => {
+                        let (e, l) = s.split_once(',').unwrap_or((s, ""));
+                        (e.to_string(), l.to_string())
+                    }
+                    _ => Default::default(),
+                };
+                for (bound, value) in [("earliest", earliest), ("latest", latest)] {
+                    dashboard.inputs.push(Input {
+                        token: format!("{}.{}", token, bound),
+                        label: format!("{} ({})", label, bound),
+                        default: value,
+                    });
+                }
+            } else {
+                dashboard.inputs.push(Input {
+                    token: token.to_string(),
+                    label,
+                    default: match default {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(Value::Array(items)) => items
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        Some(Value::Null) | None => String::new(),
+                        Some(v) => v.to_string(),
+                    },
+                });
+            }
+        }
+    }
+
+    // Time range and refresh every search data source inherits.
+    let defaults = def
+        .get("defaults")
+        .and_then(|d| d.get("dataSources"))
+        .and_then(|d| d.get("ds.search"))
+        .and_then(|d| d.get("options"));
+    let default_param =
+        |name: &str| defaults.and_then(|o| str_at(o, &["queryParameters", name]).map(String::from));
+
+    let empty = serde_json::Map::new();
+    let data_sources = def
+        .get("dataSources")
+        .and_then(|d| d.as_object())
+        .unwrap_or(&empty);
+    let visualizations = def
+        .get("visualizations")
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+
+    // Layout items ordered top to bottom, left to right; panels starting at
+    // the same y share a row.
+    let mut items: Vec<(i64, i64, i64, &str)> = def
+        .get("layout")
+        .and_then(|l| l.get("structure"))
+        .and_then(|s| s.as_array())
+        .map(|structure| {
+            structure
+                .iter()
+                .filter_map(|item| {
+                    let position = item.get("position");
+                    let coord = |k: &str| position.and_then(|p| p.get(k)).and_then(|v| v.as_i64());
+                    Some((
+                        coord("y").unwrap_or(0),
+                        coord("x").unwrap_or(0),
+                        coord("w").unwrap_or(1),
+                        str_at(item, &["item"])?,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| {
+            visualizations
+                .keys()
+                .map(|k| (0, 0, 1, k.as_str()))
+                .collect()
+        });
+    items.sort();
+
+    let mut row = 0;
+    let mut last_y = None;
+    for (y, _, w, id) in items {
+        let Some(viz) = visualizations.get(id) else {
+            continue;
+        };
+        let Some(source) =
+            str_at(viz, &["dataSources", "primary"]).and_then(|d| data_sources.get(d))
+        else {
+            continue;
+        };
+        let Some(query) = str_at(source, &["options", "query"]) else {
+            continue;
+        };
+        if last_y.is_some_and(|last| last != y) {
+            row += 1;
+        }
+        last_y = Some(y);
+
+        let param = |name: &str| {
+            str_at(source, &["options", "queryParameters", name])
+                .map(String::from)
+                .or_else(|| default_param(name))
+        };
+        let viz_type = str_at(viz, &["type"]).unwrap_or_default();
+        dashboard.panels.push(Panel {
+            title: str_at(viz, &["title"]).unwrap_or_default().to_string(),
+            query: query.to_string(),
+            earliest: param("earliest"),
+            latest: param("latest"),
+            kind: match viz_type {
+                "splunk.singlevalue"
+                | "splunk.singlevalueicon"
+                | "splunk.singlevalueradial"
+                | "splunk.markergauge"
+                | "splunk.fillergauge" => PanelKind::Single,
+                "splunk.line" | "splunk.area" | "splunk.column" | "splunk.bar" => {
+                    PanelKind::Chart(chart_kind(viz_type.trim_start_matches("splunk.")))
+                }
+                "splunk.events" => PanelKind::Events,
+                _ => PanelKind::Table,
+            },
+            refresh: str_at(source, &["options", "refresh"])
+                .map(String::from)
+                .or_else(|| defaults.and_then(|o| str_at(o, &["refresh"]).map(String::from)))
+                .as_deref()
+                .and_then(parse_interval),
+            row,
+            width: w.max(1) as u32,
+        });
+    }
+    Ok(dashboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_xml() {
+        let xml = r#"<form refresh="60">
+  <label>Web Overview</label>
+  <fieldset>
+    <input type="time" token="tr"><default><earliest>-24h</earliest><latest>now</latest></default></input>
+    <input type="text" token="host"><label>Host</label><default>web*</default></input>
+  </fieldset>
+  <row>
+    <panel><single><title>Errors</title><search><query>index=web host=$host$ status&gt;=500 | stats count</query><earliest>$tr.earliest$</earliest><latest>$tr.latest$</latest></search></single></panel>
+    <panel><title>Traffic</title><chart><option name="charting.chart">column</option><search><query><![CDATA[index=web | timechart count]]></query><refresh>30s</refresh></search></chart></panel>
+  </row>
+</form>"#;
+        let dashboard = parse(xml).unwrap();
+        assert_eq!(dashboard.title, "Web Overview");
+        let tokens: Vec<&str> = dashboard.inputs.iter().map(|i| i.token.as_str()).collect();
+        assert_eq!(tokens, vec!["tr.earliest", "tr.latest", "host"]);
+        assert_eq!(dashboard.panels.len(), 2);
+        assert_eq!(dashboard.panels[0].kind, PanelKind::Single);
+        assert_eq!(dashboard.panels[0].refresh, Some(Duration::from_secs(60)));
+        assert_eq!(dashboard.panels[1].title, "Traffic");
+        assert_eq!(dashboard.panels[1].kind, PanelKind::Chart(ChartKind::Bar));
+        assert_eq!(dashboard.panels[1].refresh, Some(Duration::from_secs(30)));
+
+        let values: BTreeMap<String, String> = dashboard
+            .inputs
+            .iter()
+            .map(|i| (i.token.clone(), i.default.clone()))
+            .collect();
+        let (query, range) = dashboard.panels[0].resolve(&values).unwrap();
+        assert_eq!(query, "index=web host=web* status>=500 | stats count");
+        assert_eq!(range.earliest.as_deref(), Some("-24h"));
+        assert_eq!(range.latest.as_deref(), Some("now"));
+    }
+
+    #[test]
+    fn test_parse_studio() {
+        let json = r#"{
+  "title": "Auth",
+  "inputs": {"input_global_trp": {"type": "input.timerange", "title": "Time", "options": {"token": "global_time", "defaultValue": "-4h,now"}}},
+  "defaults": {"dataSources": {"ds.search": {"options": {"queryParameters": {"earliest": "$global_time.earliest$", "latest": "$global_time.latest$"}}}}},
+  "dataSources": {
+    "ds_a": {"type": "ds.search", "options": {"query": "index=auth | stats count"}},
+    "ds_b": {"type": "ds.search", "options": {"query": "index=auth | timechart count", "refresh": "5m"}}
+  },
+  "visualizations": {
+    "viz_a": {"type": "splunk.singlevalue", "title": "Logins", "dataSources": {"primary": "ds_a"}},
+    "viz_b": {"type": "splunk.line", "dataSources": {"primary": "ds_b"}}
+  },
+  "layout": {"structure": [
+    {"item": "viz_b", "position": {"x": 0, "y": 200, "w": 1200, "h": 300}},
+    {"item": "viz_a", "position": {"x": 0, "y": 0, "w": 400, "h": 200}}
+  ]}
+}"#;
+        let wrapped = format!(
+            "<dashboard version=\"2\"><definition><![CDATA[{}]]></definition></dashboard>",
+            json
+        );
+        let dashboard = parse(&wrapped).unwrap();
+        assert_eq!(dashboard, parse(json).unwrap());
+        assert_eq!(dashboard.inputs[0].default, "-4h");
+        assert_eq!(dashboard.panels[0].title, "Logins");
+        assert_eq!(dashboard.panels[0].kind, PanelKind::Single);
+        assert_eq!(dashboard.panels[1].kind, PanelKind::Chart(ChartKind::Line));
+        assert_eq!(dashboard.panels[1].row, 1);
+        assert_eq!(dashboard.panels[1].refresh, Some(Duration::from_secs(300)));
+        assert_eq!(
+            dashboard.panels[1].earliest.as_deref(),
+            Some("$global_time.earliest$")
+        );
+    }
+}
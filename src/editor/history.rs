@@ -0,0 +1,63 @@
+/// Maximum number of undo steps kept.
+const MAX_UNDO: usize = 200;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    text: String,
+    cursor: usize,
+}
+
+/// Snapshot-based undo/redo stack shared by all editor modes.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl EditHistory {
+    /// Records the state before a change. Consecutive identical snapshots are
+    /// collapsed so no-op edits don't create empty undo steps.
+    pub fn record(&mut self, text: &str, cursor: usize) {
+        if self.undo.last().map(|s| s.text.as_str()) == Some(text) {
+            return;
+        }
+        self.undo.push(Snapshot {
+            text: text.to_string(),
+            cursor,
+        });
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, text: &mut String, cursor: &mut usize) -> bool {
+        Self::swap(&mut self.undo, &mut self.redo, text, cursor)
+    }
+
+    pub fn redo(&mut self, text: &mut String, cursor: &mut usize) -> bool {
+        Self::swap(&mut self.redo, &mut self.undo, text, cursor)
+    }
+
+    fn swap(
+        from: &mut Vec<Snapshot>,
+        to: &mut Vec<Snapshot>,
+        text: &mut String,
+        cursor: &mut usize,
+    ) -> bool {
+        // Skip snapshots identical to the current text (e.g. a checkpoint
+        // taken right before a change that turned out to do nothing).
+        while let Some(snapshot) = from.pop() {
+            if snapshot.text == *text {
+                continue;
+            }
+            to.push(Snapshot {
+                text: std::mem::replace(text, snapshot.text),
+                cursor: *cursor,
+            });
+            *cursor = snapshot.cursor.min(text.len());
+            return true;
+        }
+        false
+    }
+}
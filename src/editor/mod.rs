@@ -0,0 +1,31 @@
+//! Text editing primitives for the SPL editor, independent of the TUI.
+
+pub mod history;
+pub mod standard;
+pub mod text;
+pub mod vim;
+
+use history::EditHistory;
+
+/// Mutable view of the editor contents handed to the editing engines.
+pub struct Buffer<'a> {
+    pub text: &'a mut String,
+    /// Byte index into `text`, always on a char boundary.
+    pub cursor: &'a mut usize,
+    pub history: &'a mut EditHistory,
+}
+
+impl Buffer<'_> {
+    /// Snapshots the current state so the next change can be undone.
+    pub fn checkpoint(&mut self) {
+        self.history.record(self.text, *self.cursor);
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.history.undo(self.text, self.cursor)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.history.redo(self.text, self.cursor)
+    }
+}
@@ -0,0 +1,307 @@
+//! Non-modal editing with readline/emacs bindings and shift-arrow selection.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::text::{
+    at_column, column, line_end, line_start, next_boundary, prev_boundary, word_backward,
+    word_forward,
+};
+use super::Buffer;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum EditKind {
+    Insert,
+    Delete,
+    /// Kills, cuts and pastes always get their own undo step.
+    Block,
+}
+
+#[derive(Default)]
+pub struct Standard {
+    /// Fixed end of the selection; the other end is the cursor.
+    anchor: Option<usize>,
+    kill_buffer: String,
+    /// Kind of the previous edit, used to group typing into one undo step.
+    last_edit: Option<EditKind>,
+}
+
+impl Standard {
+    pub fn reset(&mut self) {
+        self.anchor = None;
+        self.last_edit = None;
+    }
+
+    /// Replaces what `Ctrl+y` pastes, e.g. with text copied outside the editor.
+    pub fn set_kill_buffer(&mut self, text: String) {
+        self.kill_buffer = text;
+    }
+
+    /// The selected byte range, if any.
+    pub fn selection(&self, cursor: usize) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        (anchor != cursor).then(|| (anchor.min(cursor), anchor.max(cursor)))
+    }
+
+    /// Handles an editing key. Returns `false` for keys it doesn't use
+    /// (Enter, Up/Down without Shift, Esc, ...) so the caller can act on them.
+    pub fn handle_key(&mut self, key: KeyEvent, buf: &mut Buffer) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let cursor = *buf.cursor;
+        let text = &*buf.text;
+
+        match key.code {
+            // Undo / redo
+            KeyCode::Char('z') if ctrl && !shift => {
+                self.reset();
+                buf.undo();
+            }
+            KeyCode::Char('Z') | KeyCode::Char('z') if ctrl || alt => {
+                self.reset();
+                buf.redo();
+            }
+
+            // Clipboard
+            KeyCode::Char('c') if ctrl => self.copy(buf),
+            KeyCode::Insert if ctrl => self.copy(buf),
+            KeyCode::Char('w') if alt => self.copy(buf),
+            KeyCode::Delete if shift => self.cut(buf),
+            KeyCode::Char('w') if ctrl => {
+                if self.selection(cursor).is_some() {
+                    self.cut(buf);
+                } else {
+                    let start = word_backward(text, cursor, true);
+                    self.kill(buf, start, cursor);
+                }
+            }
+            KeyCode::Char('y') if ctrl => self.paste(buf),
+            KeyCode::Insert if shift => self.paste(buf),
+
+            // Kills
+            KeyCode::Char('k') if ctrl => {
+                let end = line_end(text, cursor);
+                let end = if end == cursor {
+                    next_boundary(text, end)
+                } else {
+                    end
+                };
+                self.kill(buf, cursor, end);
+            }
+            KeyCode::Char('u') if ctrl => {
+                let start = line_start(text, cursor);
+                self.kill(buf, start, cursor);
+            }
+            KeyCode::Backspace if alt || ctrl => {
+                let start = word_backward(text, cursor, false);
+                self.kill(buf, start, cursor);
+            }
+            KeyCode::Char('d') if alt => {
+                let end = word_forward(text, cursor, false);
+                self.kill(buf, cursor, end);
+            }
+            KeyCode::Backspace => match self.selection(cursor) {
+                Some((start, end)) => self.replace(buf, start, end, "", EditKind::Block),
+                None if cursor > 0 => self.replace(
+                    buf,
+                    prev_boundary(text, cursor),
+                    cursor,
+                    "",
+                    EditKind::Delete,
+                ),
+                None => {}
+            },
+            KeyCode::Delete => self.delete_forward(buf),
+            KeyCode::Char('d') if ctrl => self.delete_forward(buf),
+
+            // Movement (Shift extends the selection)
+            KeyCode::Left if ctrl => self.move_to(buf, word_backward(text, cursor, false), shift),
+            KeyCode::Right if ctrl => self.move_to(buf, word_forward(text, cursor, false), shift),
+            KeyCode::Char('b') if alt => {
+                self.move_to(buf, word_backward(text, cursor, false), false)
+            }
+            KeyCode::Char('f') if alt => {
+                self.move_to(buf, word_forward(text, cursor, false), false)
+            }
+            KeyCode::Left => self.move_to(buf, prev_boundary(text, cursor), shift),
+            KeyCode::Right => self.move_to(buf, next_boundary(text, cursor), shift),
+            KeyCode::Char('b') if ctrl => self.move_to(buf, prev_boundary(text, cursor), false),
+            KeyCode::Char('f') if ctrl => self.move_to(buf, next_boundary(text, cursor), false),
+            KeyCode::Home => self.move_to(buf, line_start(text, cursor), shift),
+            KeyCode::End => self.move_to(buf, line_end(text, cursor), shift),
+            KeyCode::Char('a') if ctrl => self.move_to(buf, line_start(text, cursor), false),
+            KeyCode::Char('e') if ctrl => self.move_to(buf, line_end(text, cursor), false),
+            KeyCode::Up | KeyCode::Down if shift => {
+                let target = vertical(text, cursor, key.code == KeyCode::Up);
+                self.move_to(buf, target, true);
+            }
+
+            // Insertion
+            KeyCode::Enter if shift => self.insert(buf, "\n"),
+            KeyCode::Char('j') if ctrl => self.insert(buf, "\n"),
+            KeyCode::Char(c) if !ctrl && !alt => {
+                let mut tmp = [0u8; 4];
+                self.insert(buf, c.encode_utf8(&mut tmp));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn move_to(&mut self, buf: &mut Buffer, pos: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(*buf.cursor);
+        } else {
+            self.anchor = None;
+        }
+        *buf.cursor = pos;
+        self.last_edit = None;
+    }
+
+    /// Replaces `start..end` and records an undo step unless the edit
+    /// continues a run of typing (or deleting). Typing starts a new step at
+    /// each word.
+    fn replace(&mut self, buf: &mut Buffer, start: usize, end: usize, with: &str, kind: EditKind) {
+        let word_break = kind == EditKind::Insert
+            && !with.starts_with(char::is_whitespace)
+            && buf.text[..start].ends_with(char::is_whitespace);
+        if kind == EditKind::Block || self.last_edit != Some(kind) || word_break {
+            buf.checkpoint();
+        }
+        buf.text.replace_range(start..end, with);
+        *buf.cursor = start + with.len();
+        self.anchor = None;
+        self.last_edit = Some(kind);
+    }
+
+    fn insert(&mut self, buf: &mut Buffer, s: &str) {
+        match self.selection(*buf.cursor) {
+            Some((start, end)) => self.replace(buf, start, end, s, EditKind::Block),
+            None => {
+                let cursor = *buf.cursor;
+                self.replace(buf, cursor, cursor, s, EditKind::Insert);
+            }
+        }
+    }
+
+    fn delete_forward(&mut self, buf: &mut Buffer) {
+        let cursor = *buf.cursor;
+        match self.selection(cursor) {
+            Some((start, end)) => self.replace(buf, start, end, "", EditKind::Block),
+            None if cursor < buf.text.len() => {
+                let end = next_boundary(buf.text, cursor);
+                self.replace(buf, cursor, end, "", EditKind::Delete);
+            }
+            None => {}
+        }
+    }
+
+    fn kill(&mut self, buf: &mut Buffer, start: usize, end: usize) {
+        if end > start {
+            self.kill_buffer = buf.text[start..end].to_string();
+            self.replace(buf, start, end, "", EditKind::Block);
+        }
+    }
+
+    fn copy(&mut self, buf: &mut Buffer) {
+        if let Some((start, end)) = self.selection(*buf.cursor) {
+            self.kill_buffer = buf.text[start..end].to_string();
+        }
+        self.anchor = None;
+    }
+
+    fn cut(&mut self, buf: &mut Buffer) {
+        if let Some((start, end)) = self.selection(*buf.cursor) {
+            self.kill(buf, start, end);
+        }
+    }
+
+    fn paste(&mut self, buf: &mut Buffer) {
+        if self.kill_buffer.is_empty() {
+            return;
+        }
+        let (start, end) = self
+            .selection(*buf.cursor)
+            .unwrap_or((*buf.cursor, *buf.cursor));
+        let content = std::mem::take(&mut self.kill_buffer);
+        self.replace(buf, start, end, &content, EditKind::Block);
+        self.kill_buffer = content;
+    }
+}
+
+/// Same column on the previous/next line, or the buffer start/end at the edges.
+fn vertical(text: &str, cursor: usize, up: bool) -> usize {
+    let col = column(text, cursor);
+    let start = line_start(text, cursor);
+    if up {
+        if start == 0 {
+            return 0;
+        }
+        at_column(text, line_start(text, start - 1), col)
+    } else {
+        let end = line_end(text, cursor);
+        if end >= text.len() {
+            return text.len();
+        }
+        at_column(text, end + 1, col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::history::EditHistory;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_typing_kills_and_undo() {
+        let mut editor = Standard::default();
+        let (mut text, mut cursor, mut history) = (String::new(), 0, EditHistory::default());
+        let mut buf = Buffer {
+            text: &mut text,
+            cursor: &mut cursor,
+            history: &mut history,
+        };
+        for c in "index=main stats".chars() {
+            editor.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE), &mut buf);
+        }
+        editor.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL), &mut buf);
+        assert_eq!(buf.text.as_str(), "index=main ");
+        editor.handle_key(key(KeyCode::Char('a'), KeyModifiers::CONTROL), &mut buf);
+        editor.handle_key(key(KeyCode::Char('y'), KeyModifiers::CONTROL), &mut buf);
+        assert_eq!(buf.text.as_str(), "statsindex=main ");
+
+        let ctrl_z = key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        editor.handle_key(ctrl_z, &mut buf);
+        editor.handle_key(ctrl_z, &mut buf);
+        assert_eq!(buf.text.as_str(), "index=main stats");
+        // Typing is grouped per word.
+        editor.handle_key(ctrl_z, &mut buf);
+        assert_eq!(buf.text.as_str(), "index=main ");
+    }
+
+    #[test]
+    fn test_selection_cut_and_replace() {
+        let mut editor = Standard::default();
+        let (mut text, mut cursor, mut history) =
+            ("a | head 5".to_string(), 0, EditHistory::default());
+        let mut buf = Buffer {
+            text: &mut text,
+            cursor: &mut cursor,
+            history: &mut history,
+        };
+        editor.handle_key(key(KeyCode::End, KeyModifiers::NONE), &mut buf);
+        for _ in 0..8 {
+            editor.handle_key(key(KeyCode::Left, KeyModifiers::SHIFT), &mut buf);
+        }
+        assert_eq!(editor.selection(*buf.cursor), Some((2, 10)));
+        editor.handle_key(key(KeyCode::Char('w'), KeyModifiers::CONTROL), &mut buf);
+        assert_eq!(buf.text.as_str(), "a ");
+        editor.handle_key(key(KeyCode::Home, KeyModifiers::SHIFT), &mut buf);
+        editor.handle_key(key(KeyCode::Char('b'), KeyModifiers::NONE), &mut buf);
+        assert_eq!(buf.text.as_str(), "b");
+    }
+}
@@ -0,0 +1,152 @@
+//! Byte-index helpers over UTF-8 text. Every function takes and returns byte
+//! offsets that lie on char boundaries.
+
+pub fn char_at(s: &str, i: usize) -> Option<char> {
+    s.get(i..).and_then(|rest| rest.chars().next())
+}
+
+pub fn next_boundary(s: &str, i: usize) -> usize {
+    char_at(s, i).map(|c| i + c.len_utf8()).unwrap_or(s.len())
+}
+
+pub fn prev_boundary(s: &str, i: usize) -> usize {
+    s[..i.min(s.len())]
+        .chars()
+        .next_back()
+        .map(|c| i - c.len_utf8())
+        .unwrap_or(0)
+}
+
+pub fn line_start(s: &str, i: usize) -> usize {
+    s[..i.min(s.len())].rfind('\n').map(|n| n + 1).unwrap_or(0)
+}
+
+/// Index of the line's terminating `\n`, or `s.len()` on the last line.
+pub fn line_end(s: &str, i: usize) -> usize {
+    s[i.min(s.len())..]
+        .find('\n')
+        .map(|n| i + n)
+        .unwrap_or(s.len())
+}
+
+pub fn first_non_blank(s: &str, i: usize) -> usize {
+    let start = line_start(s, i);
+    let end = line_end(s, i);
+    s[start..end]
+        .find(|c: char| !c.is_whitespace())
+        .map(|n| start + n)
+        .unwrap_or(end)
+}
+
+/// Column of `i` within its line, counted in chars.
+pub fn column(s: &str, i: usize) -> usize {
+    s[line_start(s, i)..i].chars().count()
+}
+
+/// Byte index of char column `col` on the line starting at `start`, clamped to the line end.
+pub fn at_column(s: &str, start: usize, col: usize) -> usize {
+    let end = line_end(s, start);
+    s[start..end]
+        .char_indices()
+        .nth(col)
+        .map(|(n, _)| start + n)
+        .unwrap_or(end)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CharClass {
+    Space,
+    Word,
+    Punct,
+}
+
+/// Vim's character classes; with `big` (WORD motions) only whitespace separates.
+pub fn class(c: char, big: bool) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if big || c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punct
+    }
+}
+
+fn class_at(s: &str, i: usize, big: bool) -> Option<CharClass> {
+    char_at(s, i).map(|c| class(c, big))
+}
+
+/// Start of the next word (`w` / `W`).
+pub fn word_forward(s: &str, i: usize, big: bool) -> usize {
+    let mut i = i;
+    if let Some(start_class) = class_at(s, i, big) {
+        if start_class != CharClass::Space {
+            while class_at(s, i, big) == Some(start_class) {
+                i = next_boundary(s, i);
+            }
+        }
+    }
+    while class_at(s, i, big) == Some(CharClass::Space) {
+        i = next_boundary(s, i);
+    }
+    i
+}
+
+/// Start of the current or previous word (`b` / `B`).
+pub fn word_backward(s: &str, i: usize, big: bool) -> usize {
+    let mut i = i;
+    while i > 0 && class_at(s, prev_boundary(s, i), big) == Some(CharClass::Space) {
+        i = prev_boundary(s, i);
+    }
+    if i == 0 {
+        return 0;
+    }
+    let run = class_at(s, prev_boundary(s, i), big);
+    while i > 0 && class_at(s, prev_boundary(s, i), big) == run {
+        i = prev_boundary(s, i);
+    }
+    i
+}
+
+/// Last char of the current or next word (`e` / `E`).
+pub fn word_end(s: &str, i: usize, big: bool) -> usize {
+    let mut i = next_boundary(s, i);
+    while class_at(s, i, big) == Some(CharClass::Space) {
+        i = next_boundary(s, i);
+    }
+    let Some(run) = class_at(s, i, big) else {
+        return prev_boundary(s, s.len());
+    };
+    while class_at(s, next_boundary(s, i), big) == Some(run) {
+        i = next_boundary(s, i);
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_motions() {
+        let s = "index=main | stats count";
+        assert_eq!(word_forward(s, 0, false), 5); // "="
+        assert_eq!(word_forward(s, 0, true), 11); // "|"
+        assert_eq!(word_end(s, 0, false), 4);
+        assert_eq!(word_end(s, 0, true), 9);
+        assert_eq!(word_backward(s, 15, false), 13);
+        assert_eq!(word_backward(s, 13, true), 11);
+        assert_eq!(word_backward(s, 11, false), 6);
+    }
+
+    #[test]
+    fn test_lines_and_columns() {
+        let s = "ab\n  cé\nx";
+        assert_eq!(line_start(s, 5), 3);
+        assert_eq!(line_end(s, 3), 8);
+        assert_eq!(first_non_blank(s, 3), 5);
+        assert_eq!(column(s, 6), 3);
+        assert_eq!(at_column(s, 3, 3), 6);
+        assert_eq!(at_column(s, 9, 4), 10);
+        assert_eq!(prev_boundary(s, 8), 6);
+    }
+}
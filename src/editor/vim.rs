@@ -0,0 +1,1243 @@
+//! Modal (Vim-style) editing engine: motions, counts, operators, text
+//! objects, visual modes, `.` repeat and undo/redo.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::text::{
+    at_column, char_at, class, column, first_non_blank, line_end, line_start, next_boundary,
+    prev_boundary, word_backward, word_end, word_forward, CharClass,
+};
+use super::Buffer;
+use crate::spl::lexer::{tokenize, TokenKind};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VimState {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+impl VimState {
+    pub fn label(self) -> &'static str {
+        match self {
+            VimState::Normal => "-- NORMAL --",
+            VimState::Insert => "-- INSERT --",
+            VimState::Visual => "-- VISUAL --",
+            VimState::VisualLine => "-- VISUAL LINE --",
+        }
+    }
+
+    fn is_visual(self) -> bool {
+        matches!(self, VimState::Visual | VimState::VisualLine)
+    }
+}
+
+/// What the editor should do after a key has been handled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Handled,
+    /// Run the search.
+    Submit,
+    /// Leave the editor.
+    Cancel,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Find {
+    ch: char,
+    forward: bool,
+    till: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward(bool),
+    WordBackward(bool),
+    WordEnd(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FileStart,
+    FileEnd,
+    Find(Find),
+    RepeatFind { reverse: bool },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MotionKind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Object {
+    Word(bool),
+    Quote(char),
+    Pair(char, char),
+    /// One stage of an SPL pipeline.
+    Pipe,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Target {
+    Motion(Motion),
+    /// Doubled operator (`dd`, `cc`, `yy`).
+    Lines,
+    Object {
+        inner: bool,
+        object: Object,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// Visual-mode text object selection (`viw`, `vi|`).
+    Select {
+        inner: bool,
+        object: Object,
+    },
+    Replace(char),
+    Simple(char),
+}
+
+#[derive(PartialEq, Eq, Debug)]
+enum Parse<T> {
+    Pending,
+    Invalid,
+    Done(T),
+}
+
+#[derive(Clone, Default)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+#[derive(Default)]
+pub struct Vim {
+    /// Keys of the command being typed, e.g. `2d` while waiting for a motion.
+    pending: Vec<char>,
+    /// Raw keys of the pending command, kept for `.` repeat.
+    keys: Vec<KeyEvent>,
+    /// Keys of a change that entered insert mode, completed on Esc.
+    insert_keys: Option<Vec<KeyEvent>>,
+    last_change: Vec<KeyEvent>,
+    register: Register,
+    /// The fixed end of a visual selection.
+    anchor: usize,
+    last_find: Option<Find>,
+}
+
+impl Vim {
+    /// Drops any half-typed command, e.g. when the editor is left.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.keys.clear();
+        self.insert_keys = None;
+    }
+
+    /// Replaces what `p` puts, e.g. with text copied outside the editor.
+    pub fn set_register(&mut self, text: String) {
+        self.register = Register {
+            text,
+            linewise: false,
+        };
+    }
+
+    /// The partially typed command, for display.
+    pub fn pending(&self) -> String {
+        self.pending.iter().collect()
+    }
+
+    /// Byte range currently selected in a visual mode.
+    pub fn selection(&self, text: &str, cursor: usize, state: VimState) -> Option<(usize, usize)> {
+        let (a, b) = (self.anchor.min(cursor), self.anchor.max(cursor));
+        match state {
+            VimState::Visual => Some((a, next_boundary(text, b))),
+            VimState::VisualLine => Some((line_start(text, a), line_end(text, b))),
+            _ => None,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, state: &mut VimState, buf: &mut Buffer) -> Outcome {
+        if *state == VimState::Insert {
+            return self.handle_insert(key, state, buf);
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let ch = match key.code {
+            KeyCode::Esc => {
+                if !self.pending.is_empty() {
+                    self.reset();
+                } else if state.is_visual() {
+                    *state = VimState::Normal;
+                } else {
+                    self.reset();
+                    return Outcome::Cancel;
+                }
+                return Outcome::Handled;
+            }
+            KeyCode::Enter => {
+                self.reset();
+                *state = VimState::Normal;
+                return Outcome::Submit;
+            }
+            KeyCode::Char('r') if ctrl => {
+                self.reset();
+                buf.redo();
+                clamp(buf);
+                return Outcome::Handled;
+            }
+            KeyCode::Char(c) if !ctrl => c,
+            KeyCode::Left | KeyCode::Backspace => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Up => 'k',
+            KeyCode::Down => 'j',
+            KeyCode::Home => '0',
+            KeyCode::End => '$',
+            _ => return Outcome::Handled,
+        };
+
+        self.keys.push(key);
+        self.pending.push(ch);
+        let visual = state.is_visual();
+        match parse(&self.pending, visual) {
+            Parse::Pending => {}
+            Parse::Invalid => self.reset(),
+            Parse::Done((count, command)) => {
+                let keys = std::mem::take(&mut self.keys);
+                self.pending.clear();
+                let repeatable = self.execute(count, command, state, buf);
+                if *state == VimState::Insert {
+                    self.insert_keys = Some(keys);
+                } else {
+                    if repeatable && !visual {
+                        self.last_change = keys;
+                    }
+                    clamp(buf);
+                }
+            }
+        }
+        Outcome::Handled
+    }
+
+    fn handle_insert(&mut self, key: KeyEvent, state: &mut VimState, buf: &mut Buffer) -> Outcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if let Some(keys) = self.insert_keys.as_mut() {
+            keys.push(key);
+        }
+        match key.code {
+            KeyCode::Esc => {
+                *state = VimState::Normal;
+                if *buf.cursor > line_start(buf.text, *buf.cursor) {
+                    *buf.cursor = prev_boundary(buf.text, *buf.cursor);
+                }
+                if let Some(keys) = self.insert_keys.take() {
+                    self.last_change = keys;
+                }
+            }
+            KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => insert(buf, "\n"),
+            KeyCode::Char('j') if ctrl => insert(buf, "\n"),
+            KeyCode::Enter => {
+                self.reset();
+                *state = VimState::Normal;
+                return Outcome::Submit;
+            }
+            KeyCode::Char('w') if ctrl => {
+                let start = word_backward(buf.text, *buf.cursor, false);
+                buf.text.replace_range(start..*buf.cursor, "");
+                *buf.cursor = start;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                let mut tmp = [0u8; 4];
+                insert(buf, c.encode_utf8(&mut tmp));
+            }
+            KeyCode::Backspace if *buf.cursor > 0 => {
+                let start = prev_boundary(buf.text, *buf.cursor);
+                buf.text.replace_range(start..*buf.cursor, "");
+                *buf.cursor = start;
+            }
+            KeyCode::Left if *buf.cursor > line_start(buf.text, *buf.cursor) => {
+                *buf.cursor = prev_boundary(buf.text, *buf.cursor);
+            }
+            KeyCode::Right if *buf.cursor < line_end(buf.text, *buf.cursor) => {
+                *buf.cursor = next_boundary(buf.text, *buf.cursor);
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let motion = if key.code == KeyCode::Up {
+                    Motion::Up
+                } else {
+                    Motion::Down
+                };
+                if let Some((pos, _)) = self.motion_target(buf.text, *buf.cursor, motion, None) {
+                    *buf.cursor = pos;
+                }
+            }
+            KeyCode::Home => *buf.cursor = line_start(buf.text, *buf.cursor),
+            KeyCode::End => *buf.cursor = line_end(buf.text, *buf.cursor),
+            _ => {}
+        }
+        Outcome::Handled
+    }
+
+    /// Runs a parsed command. Returns whether it can be repeated with `.`.
+    fn execute(
+        &mut self,
+        count: Option<usize>,
+        command: Command,
+        state: &mut VimState,
+        buf: &mut Buffer,
+    ) -> bool {
+        match command {
+            Command::Move(motion) => {
+                if let Some((pos, _)) = self.motion_target(buf.text, *buf.cursor, motion, count) {
+                    *buf.cursor = pos;
+                }
+                false
+            }
+            Command::Operate(op, target) => {
+                match self.target_range(buf.text, *buf.cursor, op, target, count) {
+                    Some((start, end, linewise)) => {
+                        self.apply(op, start, end, linewise, state, buf);
+                        op != Operator::Yank
+                    }
+                    None => false,
+                }
+            }
+            Command::Select { inner, object } => {
+                if let Some((start, end)) = object_range(buf.text, *buf.cursor, inner, object) {
+                    if end > start {
+                        self.anchor = start;
+                        *buf.cursor = prev_boundary(buf.text, end);
+                        *state = VimState::Visual;
+                    }
+                }
+                false
+            }
+            Command::Replace(c) if state.is_visual() => {
+                // Every char of the selection, keeping the line breaks.
+                let (start, end) = self.visual_range(*state, false, buf);
+                let replacement: String = buf.text[start..end]
+                    .chars()
+                    .map(|old| if old == '\n' { old } else { c })
+                    .collect();
+                buf.checkpoint();
+                buf.text.replace_range(start..end, &replacement);
+                *buf.cursor = start.min(prev_boundary(buf.text, buf.text.len()));
+                self.anchor = *buf.cursor;
+                *state = VimState::Normal;
+                true
+            }
+            Command::Replace(c) => {
+                let n = count.unwrap_or(1);
+                let start = *buf.cursor;
+                let end = line_end(buf.text, start);
+                // The end of the n chars from the cursor, not n columns.
+                let Some(stop) = buf.text[start..end]
+                    .char_indices()
+                    .map(|(i, ch)| start + i + ch.len_utf8())
+                    .nth(n - 1)
+                else {
+                    return false;
+                };
+                buf.checkpoint();
+                let replacement: String = std::iter::repeat_n(c, n).collect();
+                buf.text.replace_range(start..stop, &replacement);
+                *buf.cursor = prev_boundary(buf.text, start + replacement.len());
+                true
+            }
+            Command::Simple(c) if state.is_visual() => {
+                self.visual_command(c, state, buf);
+                false
+            }
+            Command::Simple(c) => self.simple(c, count, state, buf),
+        }
+    }
+
+    fn simple(
+        &mut self,
+        c: char,
+        count: Option<usize>,
+        state: &mut VimState,
+        buf: &mut Buffer,
+    ) -> bool {
+        let n = count.unwrap_or(1);
+        let cursor = *buf.cursor;
+        match c {
+            'x' | 'X' | 's' | 'D' | 'C' => {
+                let (op, motion) = match c {
+                    'x' => (Operator::Delete, Motion::Right),
+                    'X' => (Operator::Delete, Motion::Left),
+                    's' => (Operator::Change, Motion::Right),
+                    'D' => (Operator::Delete, Motion::LineEnd),
+                    _ => (Operator::Change, Motion::LineEnd),
+                };
+                if let Some((start, end, linewise)) =
+                    self.target_range(buf.text, cursor, op, Target::Motion(motion), count)
+                {
+                    if end > start || op == Operator::Change {
+                        self.apply(op, start, end, linewise, state, buf);
+                    }
+                }
+                true
+            }
+            'S' => self.execute(
+                count,
+                Command::Operate(Operator::Change, Target::Lines),
+                state,
+                buf,
+            ),
+            'Y' => self.execute(
+                count,
+                Command::Operate(Operator::Yank, Target::Lines),
+                state,
+                buf,
+            ),
+            'p' | 'P' => {
+                if self.register.text.is_empty() && !self.register.linewise {
+                    return false;
+                }
+                buf.checkpoint();
+                self.put(c == 'P', n, buf);
+                true
+            }
+            'u' => {
+                for _ in 0..n {
+                    if !buf.undo() {
+                        break;
+                    }
+                }
+                false
+            }
+            '.' => {
+                let keys = self.last_change.clone();
+                for _ in 0..n {
+                    for key in &keys {
+                        self.handle_key(*key, state, buf);
+                    }
+                }
+                self.last_change = keys;
+                false
+            }
+            'i' | 'a' | 'I' | 'A' | 'o' | 'O' => {
+                buf.checkpoint();
+                let text = &*buf.text;
+                match c {
+                    'a' if cursor < line_end(text, cursor) => {
+                        *buf.cursor = next_boundary(text, cursor)
+                    }
+                    'I' => *buf.cursor = first_non_blank(text, cursor),
+                    'A' => *buf.cursor = line_end(text, cursor),
+                    'o' => {
+                        let end = line_end(text, cursor);
+                        buf.text.insert(end, '\n');
+                        *buf.cursor = end + 1;
+                    }
+                    'O' => {
+                        let start = line_start(text, cursor);
+                        buf.text.insert(start, '\n');
+                        *buf.cursor = start;
+                    }
+                    _ => {}
+                }
+                *state = VimState::Insert;
+                true
+            }
+            'v' => {
+                self.anchor = cursor;
+                *state = VimState::Visual;
+                false
+            }
+            'V' => {
+                self.anchor = cursor;
+                *state = VimState::VisualLine;
+                false
+            }
+            'J' => {
+                buf.checkpoint();
+                for _ in 0..n.saturating_sub(1).max(1) {
+                    let end = line_end(buf.text, *buf.cursor);
+                    if end >= buf.text.len() {
+                        break;
+                    }
+                    let next = first_non_blank(buf.text, end + 1);
+                    let joiner = if next == line_end(buf.text, end + 1) {
+                        ""
+                    } else {
+                        " "
+                    };
+                    let trimmed = buf.text[..end].trim_end_matches([' ', '\t']).len();
+                    let trimmed = trimmed.max(line_start(buf.text, end));
+                    buf.text.replace_range(trimmed..next, joiner);
+                    *buf.cursor = trimmed;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The selected range; `linewise` also widens a charwise selection to
+    /// whole lines.
+    fn visual_range(&self, state: VimState, linewise: bool, buf: &Buffer) -> (usize, usize) {
+        let (a, b) = (self.anchor.min(*buf.cursor), self.anchor.max(*buf.cursor));
+        if linewise || state == VimState::VisualLine {
+            (line_start(buf.text, a), line_end(buf.text, b))
+        } else {
+            (a, next_boundary(buf.text, b))
+        }
+    }
+
+    fn visual_command(&mut self, c: char, state: &mut VimState, buf: &mut Buffer) {
+        let linewise =
+            *state == VimState::VisualLine || matches!(c, 'D' | 'X' | 'Y' | 'C' | 'S' | 'R');
+        let (start, end) = self.visual_range(*state, linewise, buf);
+        match c {
+            'd' | 'x' | 'D' | 'X' => self.apply(Operator::Delete, start, end, linewise, state, buf),
+            'c' | 's' | 'C' | 'S' | 'R' => {
+                self.apply(Operator::Change, start, end, linewise, state, buf)
+            }
+            'y' | 'Y' => self.apply(Operator::Yank, start, end, linewise, state, buf),
+            'p' | 'P' => {
+                let register = self.register.clone();
+                let at_end = end >= buf.text.len();
+                self.apply(Operator::Delete, start, end, linewise, state, buf);
+                self.register = register;
+                self.put(!(linewise && at_end && start > 0), 1, buf);
+            }
+            'o' => {
+                std::mem::swap(&mut self.anchor, buf.cursor);
+            }
+            'v' | 'V' => {
+                let target = if c == 'v' {
+                    VimState::Visual
+                } else {
+                    VimState::VisualLine
+                };
+                *state = if *state == target {
+                    VimState::Normal
+                } else {
+                    target
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies an operator to `start..end`. Linewise ranges cover whole lines
+    /// without their trailing newline.
+    fn apply(
+        &mut self,
+        op: Operator,
+        start: usize,
+        end: usize,
+        linewise: bool,
+        state: &mut VimState,
+        buf: &mut Buffer,
+    ) {
+        self.register = Register {
+            text: buf.text[start..end].to_string(),
+            linewise,
+        };
+        match op {
+            Operator::Yank => {
+                if !linewise || state.is_visual() {
+                    *buf.cursor = start;
+                }
+                *state = VimState::Normal;
+            }
+            Operator::Delete => {
+                buf.checkpoint();
+                let (start, end) = if !linewise {
+                    (start, end)
+                } else if end < buf.text.len() {
+                    (start, end + 1)
+                } else {
+                    (start.saturating_sub(1), end)
+                };
+                buf.text.replace_range(start..end, "");
+                *buf.cursor = if linewise {
+                    first_non_blank(buf.text, start.min(buf.text.len()))
+                } else {
+                    start
+                };
+                *state = VimState::Normal;
+            }
+            Operator::Change => {
+                buf.checkpoint();
+                buf.text.replace_range(start..end, "");
+                *buf.cursor = start;
+                *state = VimState::Insert;
+            }
+        }
+    }
+
+    fn put(&mut self, before: bool, count: usize, buf: &mut Buffer) {
+        let cursor = *buf.cursor;
+        if self.register.linewise {
+            let block = vec![self.register.text.as_str(); count].join("\n");
+            if before {
+                let start = line_start(buf.text, cursor);
+                buf.text.insert_str(start, &format!("{}\n", block));
+                *buf.cursor = first_non_blank(buf.text, start);
+            } else {
+                let end = line_end(buf.text, cursor);
+                buf.text.insert_str(end, &format!("\n{}", block));
+                *buf.cursor = first_non_blank(buf.text, end + 1);
+            }
+        } else {
+            let content = self.register.text.repeat(count);
+            let at = if before || cursor >= line_end(buf.text, cursor) {
+                cursor
+            } else {
+                next_boundary(buf.text, cursor)
+            };
+            buf.text.insert_str(at, &content);
+            *buf.cursor = prev_boundary(buf.text, at + content.len()).max(at);
+        }
+    }
+
+    /// Resolves an operator target to a byte range and whether it is linewise.
+    fn target_range(
+        &mut self,
+        text: &str,
+        cursor: usize,
+        op: Operator,
+        target: Target,
+        count: Option<usize>,
+    ) -> Option<(usize, usize, bool)> {
+        match target {
+            Target::Lines => {
+                let mut last = cursor;
+                for _ in 1..count.unwrap_or(1) {
+                    let end = line_end(text, last);
+                    if end >= text.len() {
+                        break;
+                    }
+                    last = end + 1;
+                }
+                Some((line_start(text, cursor), line_end(text, last), true))
+            }
+            Target::Object { inner, object } => {
+                object_range(text, cursor, inner, object).map(|(s, e)| (s, e, false))
+            }
+            Target::Motion(motion) => {
+                let (target, kind) = match motion {
+                    // `cw` on a word behaves like `ce`.
+                    Motion::WordForward(big)
+                        if op == Operator::Change
+                            && char_at(text, cursor).is_some_and(|c| !c.is_whitespace()) =>
+                    {
+                        let mut end = cursor;
+                        for n in 0..count.unwrap_or(1) {
+                            if n > 0 || !is_word_end(text, end, big) {
+                                end = word_end(text, end, big);
+                            }
+                        }
+                        (end, MotionKind::Inclusive)
+                    }
+                    _ => self.motion_target(text, cursor, motion, count)?,
+                };
+                let (a, b) = (cursor.min(target), cursor.max(target));
+                match kind {
+                    MotionKind::Linewise => Some((line_start(text, a), line_end(text, b), true)),
+                    MotionKind::Inclusive => Some((a, next_boundary(text, b), false)),
+                    MotionKind::Exclusive => {
+                        // An exclusive motion ending at the start of a later
+                        // line stops at the end of the previous one (`dw` on
+                        // the last word of a line keeps the newline).
+                        let b = if b > a && b == line_start(text, b) && line_start(text, a) < b {
+                            b - 1
+                        } else {
+                            b
+                        };
+                        Some((a, b, false))
+                    }
+                }
+            }
+        }
+    }
+
+    fn motion_target(
+        &mut self,
+        text: &str,
+        cursor: usize,
+        motion: Motion,
+        count: Option<usize>,
+    ) -> Option<(usize, MotionKind)> {
+        let n = count.unwrap_or(1);
+        let repeat = |f: &dyn Fn(usize) -> usize| (0..n).fold(cursor, |pos, _| f(pos));
+        let result = match motion {
+            Motion::Left => {
+                let start = line_start(text, cursor);
+                let pos = repeat(&|p| if p > start { prev_boundary(text, p) } else { p });
+                (pos, MotionKind::Exclusive)
+            }
+            Motion::Right => {
+                let end = line_end(text, cursor);
+                let pos = repeat(&|p| if p < end { next_boundary(text, p) } else { p });
+                (pos, MotionKind::Exclusive)
+            }
+            Motion::Up | Motion::Down => {
+                let col = column(text, cursor);
+                let mut start = line_start(text, cursor);
+                for _ in 0..n {
+                    if motion == Motion::Up {
+                        if start == 0 {
+                            break;
+                        }
+                        start = line_start(text, start - 1);
+                    } else {
+                        let end = line_end(text, start);
+                        if end >= text.len() {
+                            break;
+                        }
+                        start = end + 1;
+                    }
+                }
+                (at_column(text, start, col), MotionKind::Linewise)
+            }
+            Motion::WordForward(big) => (
+                repeat(&|p| word_forward(text, p, big)),
+                MotionKind::Exclusive,
+            ),
+            Motion::WordBackward(big) => (
+                repeat(&|p| word_backward(text, p, big)),
+                MotionKind::Exclusive,
+            ),
+            Motion::WordEnd(big) => (repeat(&|p| word_end(text, p, big)), MotionKind::Inclusive),
+            Motion::LineStart => (line_start(text, cursor), MotionKind::Exclusive),
+            Motion::FirstNonBlank => (first_non_blank(text, cursor), MotionKind::Exclusive),
+            Motion::LineEnd => {
+                let mut pos = cursor;
+                for _ in 1..n {
+                    let end = line_end(text, pos);
+                    if end >= text.len() {
+                        break;
+                    }
+                    pos = end + 1;
+                }
+                let (start, end) = (line_start(text, pos), line_end(text, pos));
+                if start == end {
+                    (start, MotionKind::Exclusive)
+                } else {
+                    (prev_boundary(text, end), MotionKind::Inclusive)
+                }
+            }
+            Motion::FileStart | Motion::FileEnd => {
+                let line = match (motion, count) {
+                    (_, Some(line)) => line.saturating_sub(1),
+                    (Motion::FileStart, None) => 0,
+                    _ => usize::MAX,
+                };
+                let mut start = 0;
+                for _ in 0..line {
+                    let end = line_end(text, start);
+                    if end >= text.len() {
+                        break;
+                    }
+                    start = end + 1;
+                }
+                (first_non_blank(text, start), MotionKind::Linewise)
+            }
+            Motion::Find(find) => {
+                self.last_find = Some(find);
+                (find_in_line(text, cursor, find, n, false)?, find_kind(find))
+            }
+            Motion::RepeatFind { reverse } => {
+                let mut find = self.last_find?;
+                find.forward ^= reverse;
+                (find_in_line(text, cursor, find, n, true)?, find_kind(find))
+            }
+        };
+        Some(result)
+    }
+}
+
+fn insert(buf: &mut Buffer, s: &str) {
+    buf.text.insert_str(*buf.cursor, s);
+    *buf.cursor += s.len();
+}
+
+/// Keeps the normal-mode cursor on a character rather than past the line end.
+fn clamp(buf: &mut Buffer) {
+    let cursor = (*buf.cursor).min(buf.text.len());
+    let (start, end) = (line_start(buf.text, cursor), line_end(buf.text, cursor));
+    *buf.cursor = if cursor >= end && end > start {
+        prev_boundary(buf.text, end)
+    } else {
+        cursor
+    };
+}
+
+fn is_word_end(text: &str, i: usize, big: bool) -> bool {
+    let here = char_at(text, i).map(|c| class(c, big));
+    let next = char_at(text, next_boundary(text, i)).map(|c| class(c, big));
+    here != next
+}
+
+fn find_kind(find: Find) -> MotionKind {
+    if find.forward {
+        MotionKind::Inclusive
+    } else {
+        MotionKind::Exclusive
+    }
+}
+
+/// `f`/`t`/`F`/`T` within the cursor's line. `repeat` makes a repeated `t`
+/// skip the match it is already sitting next to.
+fn find_in_line(
+    text: &str,
+    cursor: usize,
+    find: Find,
+    count: usize,
+    repeat: bool,
+) -> Option<usize> {
+    let (start, end) = (line_start(text, cursor), line_end(text, cursor));
+    let skip = usize::from(repeat && find.till);
+    let found = if find.forward {
+        let from = next_boundary(text, cursor).min(end);
+        text[from..end]
+            .char_indices()
+            .filter(|&(_, c)| c == find.ch)
+            .map(|(i, _)| from + i)
+            .filter(|&i| !(skip == 1 && i == next_boundary(text, cursor)))
+            .nth(count - 1)?
+    } else {
+        text[start..cursor]
+            .char_indices()
+            .rev()
+            .filter(|&(_, c)| c == find.ch)
+            .map(|(i, _)| start + i)
+            .filter(|&i| !(skip == 1 && next_boundary(text, i) == cursor))
+            .nth(count - 1)?
+    };
+    Some(match (find.till, find.forward) {
+        (false, _) => found,
+        (true, true) => prev_boundary(text, found),
+        (true, false) => next_boundary(text, found),
+    })
+}
+
+/// Byte range (end exclusive) of a text object around `cursor`.
+fn object_range(text: &str, cursor: usize, inner: bool, object: Object) -> Option<(usize, usize)> {
+    match object {
+        Object::Word(big) => {
+            let here = class(char_at(text, cursor)?, big);
+            let run_class = |i: usize| char_at(text, i).map(|c| class(c, big));
+            let mut start = cursor;
+            while start > 0 && run_class(prev_boundary(text, start)) == Some(here) {
+                start = prev_boundary(text, start);
+            }
+            let mut end = cursor;
+            while run_class(end) == Some(here) {
+                end = next_boundary(text, end);
+            }
+            if !inner {
+                let mut after = end;
+                while run_class(after) == Some(CharClass::Space) {
+                    after = next_boundary(text, after);
+                }
+                if after > end {
+                    end = after;
+                } else {
+                    while start > 0
+                        && run_class(prev_boundary(text, start)) == Some(CharClass::Space)
+                    {
+                        start = prev_boundary(text, start);
+                    }
+                }
+            }
+            Some((start, end))
+        }
+        Object::Quote(q) => {
+            let (line, end) = (line_start(text, cursor), line_end(text, cursor));
+            let mut quotes = Vec::new();
+            let mut escaped = false;
+            for (i, c) in text[line..end].char_indices() {
+                if c == q && !escaped {
+                    quotes.push(line + i);
+                }
+                escaped = c == '\\' && !escaped;
+            }
+            let (open, close) = quotes
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .find(|&(_, close)| close >= cursor)?;
+            Some(if inner {
+                (open + 1, close)
+            } else {
+                (open, close + 1)
+            })
+        }
+        Object::Pair(open_ch, close_ch) => {
+            let mut depth = 0;
+            let mut open = None;
+            for (i, c) in text[..next_boundary(text, cursor)].char_indices().rev() {
+                if c == close_ch && i != cursor {
+                    depth += 1;
+                } else if c == open_ch {
+                    if depth == 0 {
+                        open = Some(i);
+                        break;
+                    }
+                    depth -= 1;
+                }
+            }
+            let open = open?;
+            let mut depth = 0;
+            let mut close = None;
+            for (i, c) in text[open + 1..].char_indices() {
+                if c == open_ch {
+                    depth += 1;
+                } else if c == close_ch {
+                    if depth == 0 {
+                        close = Some(open + 1 + i);
+                        break;
+                    }
+                    depth -= 1;
+                }
+            }
+            let close = close?;
+            Some(if inner {
+                (open + 1, close)
+            } else {
+                (open, close + 1)
+            })
+        }
+        Object::Pipe => Some(pipe_stage(text, cursor, inner)),
+    }
+}
+
+/// The pipeline stage around `cursor`, bounded by pipes (or subsearch
+/// brackets) at the same nesting depth. The inner object excludes surrounding
+/// whitespace; the outer one also takes the pipe in front of the stage (or,
+/// for the first stage, the pipe after it) so `da|` removes a whole stage.
+fn pipe_stage(text: &str, cursor: usize, inner: bool) -> (usize, usize) {
+    let tokens = tokenize(text);
+    let split = tokens.partition_point(|t| t.start <= cursor);
+
+    let mut depth = 0;
+    let mut prev = None;
+    for token in tokens[..split].iter().rev() {
+        match token.kind {
+            TokenKind::RBracket => depth += 1,
+            TokenKind::LBracket if depth == 0 => {
+                prev = Some((token.start, false));
+                break;
+            }
+            TokenKind::LBracket => depth -= 1,
+            TokenKind::Pipe if depth == 0 => {
+                prev = Some((token.start, true));
+                break;
+            }
+            _ => {}
+        }
+    }
+    let mut depth = 0;
+    let mut next = None;
+    for token in &tokens[split..] {
+        match token.kind {
+            TokenKind::LBracket => depth += 1,
+            TokenKind::RBracket if depth == 0 => {
+                next = Some((token.start, false));
+                break;
+            }
+            TokenKind::RBracket => depth -= 1,
+            TokenKind::Pipe if depth == 0 => {
+                next = Some((token.start, true));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let start = prev.map(|(i, _)| i + 1).unwrap_or(0);
+    let end = next.map(|(i, _)| i).unwrap_or(text.len());
+    if inner {
+        let stage = &text[start..end];
+        let lead = stage.len() - stage.trim_start().len();
+        let trimmed = stage.trim();
+        return (start + lead, start + lead + trimmed.len());
+    }
+    match (prev, next) {
+        (Some((pipe, true)), _) => {
+            let stage_end = start + text[start..end].trim_end().len();
+            (pipe, if next.is_some() { end } else { stage_end })
+        }
+        (_, Some((pipe, true))) => {
+            let after = pipe + 1;
+            let ws = text[after..].len() - text[after..].trim_start().len();
+            (start, after + ws)
+        }
+        _ => (start, end),
+    }
+}
+
+fn take_count(keys: &[char]) -> (Option<usize>, &[char]) {
+    let digits = keys
+        .iter()
+        .enumerate()
+        .take_while(|&(i, c)| c.is_ascii_digit() && !(i == 0 && *c == '0'))
+        .count();
+    if digits == 0 {
+        return (None, keys);
+    }
+    let count = keys[..digits].iter().fold(0usize, |n, c| {
+        (n * 10 + c.to_digit(10).unwrap_or(0) as usize).min(9999)
+    });
+    (Some(count), &keys[digits..])
+}
+
+fn parse_motion(keys: &[char]) -> Parse<Motion> {
+    let Some(&first) = keys.first() else {
+        return Parse::Pending;
+    };
+    let motion = match first {
+        'h' => Motion::Left,
+        'l' => Motion::Right,
+        'k' => Motion::Up,
+        'j' => Motion::Down,
+        'w' | 'W' => Motion::WordForward(first == 'W'),
+        'b' | 'B' => Motion::WordBackward(first == 'B'),
+        'e' | 'E' => Motion::WordEnd(first == 'E'),
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        'G' => Motion::FileEnd,
+        ';' => Motion::RepeatFind { reverse: false },
+        ',' => Motion::RepeatFind { reverse: true },
+        'g' => {
+            return match keys.get(1) {
+                None => Parse::Pending,
+                Some('g') => Parse::Done(Motion::FileStart),
+                Some(_) => Parse::Invalid,
+            };
+        }
+        'f' | 't' | 'F' | 'T' => {
+            return match keys.get(1) {
+                None => Parse::Pending,
+                Some(&ch) => Parse::Done(Motion::Find(Find {
+                    ch,
+                    forward: first.is_lowercase(),
+                    till: first.eq_ignore_ascii_case(&'t'),
+                })),
+            };
+        }
+        _ => return Parse::Invalid,
+    };
+    Parse::Done(motion)
+}
+
+fn parse_object(c: char) -> Option<Object> {
+    Some(match c {
+        'w' | 'W' => Object::Word(c == 'W'),
+        '"' | '\'' | '`' => Object::Quote(c),
+        '(' | ')' | 'b' => Object::Pair('(', ')'),
+        '[' | ']' => Object::Pair('[', ']'),
+        '{' | '}' | 'B' => Object::Pair('{', '}'),
+        '|' => Object::Pipe,
+        _ => return None,
+    })
+}
+
+/// Parses `[count] command`, `[count] motion`, or
+/// `[count] operator [count] (motion | text object | operator)`.
+fn parse(keys: &[char], visual: bool) -> Parse<(Option<usize>, Command)> {
+    let (count, rest) = take_count(keys);
+    let Some(&first) = rest.first() else {
+        return Parse::Pending;
+    };
+    let operator = match first {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        _ => None,
+    };
+    if let (Some(op), false) = (operator, visual) {
+        let (count2, rest) = take_count(&rest[1..]);
+        let count = match (count, count2) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(1) * b.unwrap_or(1)),
+        };
+        let Some(&c) = rest.first() else {
+            return Parse::Pending;
+        };
+        if c == first {
+            return Parse::Done((count, Command::Operate(op, Target::Lines)));
+        }
+        if c == 'i' || c == 'a' {
+            return match rest.get(1) {
+                None => Parse::Pending,
+                Some(&o) => match parse_object(o) {
+                    Some(object) => Parse::Done((
+                        count,
+                        Command::Operate(
+                            op,
+                            Target::Object {
+                                inner: c == 'i',
+                                object,
+                            },
+                        ),
+                    )),
+                    None => Parse::Invalid,
+                },
+            };
+        }
+        return match parse_motion(rest) {
+            Parse::Pending => Parse::Pending,
+            Parse::Invalid => Parse::Invalid,
+            Parse::Done(motion) => {
+                Parse::Done((count, Command::Operate(op, Target::Motion(motion))))
+            }
+        };
+    }
+    if visual && (first == 'i' || first == 'a') {
+        return match rest.get(1) {
+            None => Parse::Pending,
+            Some(&o) => match parse_object(o) {
+                Some(object) => Parse::Done((
+                    count,
+                    Command::Select {
+                        inner: first == 'i',
+                        object,
+                    },
+                )),
+                None => Parse::Invalid,
+            },
+        };
+    }
+    match parse_motion(rest) {
+        Parse::Done(motion) => return Parse::Done((count, Command::Move(motion))),
+        Parse::Pending => return Parse::Pending,
+        Parse::Invalid => {}
+    }
+    match first {
+        'r' => match rest.get(1) {
+            None => Parse::Pending,
+            Some(&c) => Parse::Done((count, Command::Replace(c))),
+        },
+        'x' | 'X' | 's' | 'S' | 'D' | 'C' | 'Y' | 'R' | 'p' | 'P' | 'u' | '.' | 'i' | 'a' | 'I'
+        | 'A' | 'o' | 'O' | 'v' | 'V' | 'J' | 'd' | 'c' | 'y' => {
+            Parse::Done((count, Command::Simple(first)))
+        }
+        _ => Parse::Invalid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::history::EditHistory;
+
+    /// Feeds `keys` to a fresh editor in normal mode; `\x1b` is Esc.
+    fn run(text: &str, cursor: usize, keys: &str) -> (String, usize, VimState) {
+        let mut vim = Vim::default();
+        let mut state = VimState::Normal;
+        let mut text = text.to_string();
+        let mut cursor = cursor;
+        let mut history = EditHistory::default();
+        let mut buf = Buffer {
+            text: &mut text,
+            cursor: &mut cursor,
+            history: &mut history,
+        };
+        for c in keys.chars() {
+            let key = match c {
+                '\x1b' => KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+                '\x12' => KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+                c => KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            };
+            vim.handle_key(key, &mut state, &mut buf);
+        }
+        (text, cursor, state)
+    }
+
+    #[test]
+    fn test_operators_and_motions() {
+        assert_eq!(
+            run("index=main sourcetype=x", 0, "dw").0,
+            "=main sourcetype=x"
+        );
+        assert_eq!(run("index=main sourcetype=x", 0, "d2W").0, "");
+        assert_eq!(
+            run("index=main sourcetype=x", 6, "cwweb\x1b").0,
+            "index=web sourcetype=x"
+        );
+        assert_eq!(run("one two three", 0, "2dw").0, "three");
+        assert_eq!(run("a,b,c", 0, "dt,").0, ",b,c");
+        assert_eq!(run("a,b,c", 0, "f,;D").0, "a,b");
+        assert_eq!(run("a\nb\nc", 2, "ddp").0, "a\nc\nb");
+        assert_eq!(run("a\nb\nc", 0, "yyjjp").0, "a\nb\nc\na");
+        assert_eq!(run("a\nb\nc", 0, "Gx").0, "a\nb\n");
+        assert_eq!(run("abc", 0, "xu").0, "abc");
+        assert_eq!(run("abc", 0, "xu\x12").0, "bc");
+    }
+
+    #[test]
+    fn test_text_objects() {
+        assert_eq!(run("eval x=\"a b\"", 9, "ci\"z\x1b").0, "eval x=\"z\"");
+        assert_eq!(run("if(a, (b))", 4, "di(").0, "if()");
+        assert_eq!(run("x [search a] y", 5, "da[").0, "x  y");
+        assert_eq!(run("foo bar", 5, "diw").0, "foo ");
+        assert_eq!(
+            run("index=x | stats count | head 5", 12, "ci|top host\x1b").0,
+            "index=x | top host | head 5"
+        );
+        assert_eq!(
+            run("index=x | stats count | head 5", 12, "da|").0,
+            "index=x | head 5"
+        );
+        assert_eq!(
+            run("index=x | stats count | head 5", 1, "da|").0,
+            "stats count | head 5"
+        );
+        assert_eq!(
+            run("a | b [search c | d] | e", 16, "di|").0,
+            "a | b [search c | ] | e"
+        );
+    }
+
+    #[test]
+    fn test_visual_and_repeat() {
+        assert_eq!(run("one two three", 0, "vey$p").0, "one two threeone");
+        assert_eq!(run("a\nb\nc", 0, "Vjd").0, "c");
+        assert_eq!(run("a b c d", 0, "dw.").0, "c d");
+        assert_eq!(run("x\ny", 0, "A;\x1bj.").0, "x;\ny;");
+        let (text, _, state) = run("abc", 0, "viw");
+        assert_eq!((text.as_str(), state), ("abc", VimState::Visual));
+    }
+
+    #[test]
+    fn test_replace_wide_and_multibyte() {
+        assert_eq!(run("日本語", 3, "rX").0, "日X語");
+        assert_eq!(run("日本語", 0, "2rX").0, "XX語");
+        assert_eq!(run("日本語", 3, "3rX").0, "日本語");
+        assert_eq!(run("aüb", 1, "r日").0, "a日b");
+        assert_eq!(run("aü\nb", 1, "2rX").0, "aü\nb");
+
+        // Visual r replaces the whole selection and leaves a valid anchor.
+        let (text, cursor, state) = run("日本語 ü [x] \"q\"", 6, "vllrX");
+        assert_eq!(
+            (text.as_str(), cursor, state),
+            ("日本XXX [x] \"q\"", 6, VimState::Normal)
+        );
+        let (text, cursor, _) = run("日本語 ü [x] \"q\"", 6, "VbrXy");
+        assert_eq!((text.as_str(), cursor), ("XXXXXXXXXXXXX", 0));
+        assert_eq!(run("ab\ncd", 0, "vjrX").0, "XX\nXd");
+    }
+}
@@ -0,0 +1,725 @@
+//! The TUI's message loop. `App::update` takes an `Action` (terminal input, a
+//! tick, or the outcome of a request), changes the app's state and returns
+//! the `Effect`s to run. The `Executor` owns the `SplunkClient`, runs each
+//! request in its own task and sends the outcome back as another action.
+//! History, config and saved-search effects go to one worker thread in the
+//! order they were emitted, so a read-modify-write of a file never races
+//! another.
+
+use crate::api::{Namespace, SplunkClient};
+use crate::config::Config;
+use crate::models::splunk::{JobStatus, ServerSavedSearch, SplunkApp, TimeRange, TimelineBucket};
+use crate::sync::{self as search_sync, SyncEntry};
+use crate::utils::clipboard;
+use crate::utils::history::{HistoryEntry, HistoryManager};
+use crate::utils::saved_searches::{SavedSearch, SavedSearchManager};
+use crate::utils::token_values::TokenValues;
+use crossterm::event::{KeyEvent, MouseEvent};
+use log::{error, info};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Held for every read or write of the local files, so the worker thread
+/// and a sync pull never touch the same file at once.
+static LOCAL_FILES: Mutex<()> = Mutex::new(());
+
+#[derive(Debug)]
+pub enum Action {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    Tick,
+    /// The external editor exited after editing the query.
+    QueryEdited(String),
+    Copied {
+        what: String,
+        bytes: usize,
+        /// How the text was delivered.
+        result: Result<String, String>,
+    },
+    /// `clipboard_command` failed; the text still goes out over OSC 52.
+    CopyCommandFailed {
+        what: String,
+        text: String,
+        error: String,
+    },
+    SearchCreated {
+        query: String,
+        range: TimeRange,
+        result: Result<String, String>,
+    },
+    JobStatus {
+        sid: String,
+        result: Result<JobStatus, String>,
+    },
+    ResultsLoaded {
+        sid: String,
+        result: Result<Vec<Value>, String>,
+    },
+    TimelineLoaded {
+        sid: String,
+        buckets: Vec<TimelineBucket>,
+    },
+    ComparisonLoaded {
+        sid: String,
+        results: Vec<Value>,
+    },
+    PanelStarted {
+        index: usize,
+        generation: u64,
+        result: Result<String, String>,
+    },
+    PanelLoaded {
+        index: usize,
+        generation: u64,
+        result: Result<Vec<Value>, String>,
+    },
+    JobKilled {
+        sid: String,
+        result: Result<(), String>,
+    },
+    JobReattached {
+        entry: HistoryEntry,
+        result: Result<(), String>,
+    },
+    ServerSearchesLoaded(Result<Vec<ServerSavedSearch>, String>),
+    SavedSearchDispatched {
+        saved: ServerSavedSearch,
+        result: Result<String, String>,
+    },
+    LastRunFound {
+        saved: ServerSavedSearch,
+        result: Result<Option<String>, String>,
+    },
+    ScheduleSet {
+        name: String,
+        scheduled: bool,
+        result: Result<(), String>,
+    },
+    SyncLoaded {
+        app: String,
+        result: Result<Vec<SyncEntry>, String>,
+    },
+    SyncApplied {
+        push: bool,
+        done: usize,
+        errors: Vec<String>,
+        /// The diff after the changes.
+        refreshed: Result<Vec<SyncEntry>, String>,
+    },
+    AppsLoaded(Result<Vec<SplunkApp>, String>),
+    DashboardLoaded {
+        source: String,
+        result: Result<String, String>,
+    },
+    AppSaved {
+        app: Option<String>,
+        result: Result<(), String>,
+    },
+    ProfileLoaded {
+        name: String,
+        result: Result<Box<Config>, String>,
+    },
+    SearchSaved {
+        overwrite: bool,
+        result: Result<SavedSearch, String>,
+    },
+    SavedSearchesLoaded(Result<Vec<SavedSearch>, String>),
+    SavedSearchChanged {
+        name: String,
+        change: SearchChange,
+        result: Result<(), String>,
+    },
+    Exported {
+        path: PathBuf,
+        rows: usize,
+        bytes: usize,
+        result: Result<(), String>,
+    },
+}
+
+/// A change to a local saved search from the Load Search finder.
+#[derive(Debug)]
+pub enum SearchChange {
+    Rename(String),
+    Duplicate(String),
+    Delete,
+}
+
+pub enum Effect {
+    // Run by the event loop, which owns the terminal.
+    Quit,
+    /// Writes `contents` to `path` and suspends the TUI for `$EDITOR`;
+    /// `reload_query` sends the file back as `Action::QueryEdited`.
+    OpenEditor {
+        path: String,
+        contents: String,
+        reload_query: bool,
+    },
+    /// Copies to the clipboard. Without a command the loop writes an OSC 52
+    /// sequence to stdout; a command is run by the executor.
+    Copy {
+        what: String,
+        text: String,
+        command: Option<String>,
+    },
+
+    // Run by the executor.
+    /// Sends later requests through another client (profile switch).
+    Connect(Arc<SplunkClient>),
+    /// Sends later requests in another app namespace; `None` for the token
+    /// user's default app context.
+    SetNamespace(Option<Namespace>),
+    OpenUrl(String),
+    CreateSearch {
+        query: String,
+        range: TimeRange,
+    },
+    PollJob {
+        sid: String,
+    },
+    FetchResults {
+        sid: String,
+    },
+    FetchTimeline {
+        sid: String,
+    },
+    /// The comparison search of the single-value panels.
+    RunComparison {
+        sid: String,
+        query: String,
+        range: TimeRange,
+    },
+    /// Starts a dashboard panel's search; `WaitForPanel` then waits for its
+    /// results.
+    RunPanel {
+        index: usize,
+        generation: u64,
+        query: String,
+        range: TimeRange,
+    },
+    WaitForPanel {
+        index: usize,
+        generation: u64,
+        sid: String,
+    },
+    KillJob {
+        sid: String,
+    },
+    /// Stops a job whose results are no longer wanted, e.g. a panel search
+    /// from before a dashboard refresh.
+    CancelJob {
+        sid: String,
+    },
+    ReattachJob {
+        entry: HistoryEntry,
+    },
+    ListServerSearches,
+    DispatchSavedSearch(ServerSavedSearch),
+    FindLastRun(ServerSavedSearch),
+    SetScheduled {
+        saved: ServerSavedSearch,
+        scheduled: bool,
+    },
+    LoadSync {
+        app: String,
+    },
+    ApplySync {
+        namespace: Namespace,
+        entries: Vec<SyncEntry>,
+        push: bool,
+    },
+    ListApps,
+    /// A dashboard from a local file or, failing that, the server.
+    LoadDashboard {
+        source: String,
+    },
+
+    // Local files, also run by the executor so `update` never blocks on
+    // disk. Failures without a reply are only logged.
+    AppendHistory(HistoryEntry),
+    RecordCompletion {
+        sid: String,
+        result_count: u64,
+        duration: f64,
+    },
+    SaveTokenValues(TokenValues),
+    SaveTheme(String),
+    /// Persists the app namespace of `profile`; `None` clears it.
+    SaveApp {
+        profile: String,
+        app: Option<String>,
+    },
+    LoadProfile {
+        name: String,
+    },
+    /// Saves the query and view of `search`, keeping the details already
+    /// stored under its name.
+    SaveSearch {
+        search: SavedSearch,
+        overwrite: bool,
+    },
+    LoadSavedSearches,
+    ChangeSavedSearch {
+        name: String,
+        change: SearchChange,
+    },
+    Export {
+        path: PathBuf,
+        text: String,
+        rows: usize,
+    },
+}
+
+pub struct Executor {
+    client: Arc<SplunkClient>,
+    tx: UnboundedSender<Action>,
+    local: mpsc::Sender<Effect>,
+}
+
+impl Executor {
+    pub fn new(client: Arc<SplunkClient>, tx: UnboundedSender<Action>) -> Self {
+        let (local, effects) = mpsc::channel();
+        let replies = tx.clone();
+        std::thread::spawn(move || {
+            // Ends when the executor, and with it the sender, is dropped.
+            for effect in effects {
+                if let Some(action) = with_local_files(|| store(effect)) {
+                    let _ = replies.send(action);
+                }
+            }
+        });
+        Executor { client, tx, local }
+    }
+
+    /// Starts `effect` in the background. The loop's own effects (`Quit`,
+    /// `OpenEditor`, OSC 52 `Copy`) are ignored.
+    pub fn run(&mut self, effect: Effect) {
+        match effect {
+            Effect::Connect(client) => {
+                self.client = client;
+                return;
+            }
+            Effect::SetNamespace(namespace) => {
+                self.client = Arc::new(self.client.with_namespace(namespace));
+                return;
+            }
+            effect if effect.is_local() => {
+                let _ = self.local.send(effect);
+                return;
+            }
+            _ => {}
+        }
+        let client = self.client.clone();
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            if let Some(action) = perform(&client, effect).await {
+                // The loop has exited if the receiver is gone.
+                let _ = tx.send(action);
+            }
+        });
+    }
+}
+
+/// Runs one request and returns its outcome.
+pub async fn perform(client: &SplunkClient, effect: Effect) -> Option<Action> {
+    let action = match effect {
+        Effect::Quit
+        | Effect::OpenEditor { .. }
+        | Effect::Copy { command: None, .. }
+        | Effect::Connect(_)
+        | Effect::SetNamespace(_) => return None,
+        Effect::OpenUrl(url) => {
+            let _ = open::that(url);
+            return None;
+        }
+        Effect::Copy {
+            what,
+            text,
+            command: Some(command),
+        } => {
+            let run = {
+                let (command, text) = (command.clone(), text.clone());
+                move || clipboard::run_command(&command, &text, clipboard::COMMAND_TIMEOUT)
+            };
+            let result = tokio::task::spawn_blocking(run)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r.map_err(|e| e.to_string()));
+            match result {
+                Ok(()) => Action::Copied {
+                    what,
+                    bytes: text.len(),
+                    result: Ok(command),
+                },
+                Err(error) => Action::CopyCommandFailed { what, text, error },
+            }
+        }
+        Effect::CreateSearch { query, range } => {
+            let result = client
+                .create_search(&query, &range)
+                .await
+                .map_err(|e| e.to_string());
+            match &result {
+                Ok(sid) => info!("Job created successfully: {}", sid),
+                Err(e) => error!("Search creation failed: {}", e),
+            }
+            Action::SearchCreated {
+                query,
+                range,
+                result,
+            }
+        }
+        Effect::PollJob { sid } => {
+            let result = client.get_job_status(&sid).await.map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                error!("Failed to check status for job {}: {}", sid, e);
+            }
+            Action::JobStatus { sid, result }
+        }
+        Effect::FetchResults { sid } => {
+            let result = client
+                .get_results(&sid, 100, 0)
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                error!("Failed to fetch results for job {}: {}", sid, e);
+            }
+            Action::ResultsLoaded { sid, result }
+        }
+        Effect::FetchTimeline { sid } => match client.get_timeline(&sid).await {
+            Ok(buckets) => Action::TimelineLoaded { sid, buckets },
+            Err(e) => {
+                error!("Failed to fetch timeline for job {}: {}", sid, e);
+                return None;
+            }
+        },
+        Effect::RunComparison { sid, query, range } => {
+            match client.run_search(&query, &range, 1).await {
+                Ok(results) => Action::ComparisonLoaded { sid, results },
+                Err(e) => {
+                    error!("Comparison search failed: {}", e);
+                    return None;
+                }
+            }
+        }
+        Effect::RunPanel {
+            index,
+            generation,
+            query,
+            range,
+        } => {
+            let result = client
+                .create_search(&query, &range)
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                error!("Dashboard panel search failed: {}", e);
+            }
+            Action::PanelStarted {
+                index,
+                generation,
+                result,
+            }
+        }
+        Effect::WaitForPanel {
+            index,
+            generation,
+            sid,
+        } => {
+            let result = client
+                .wait_for_results(&sid, 100)
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                error!("Dashboard panel search failed: {}", e);
+            }
+            Action::PanelLoaded {
+                index,
+                generation,
+                result,
+            }
+        }
+        Effect::KillJob { sid } => {
+            let result = client.cancel_job(&sid).await.map_err(|e| e.to_string());
+            Action::JobKilled { sid, result }
+        }
+        Effect::CancelJob { sid } => {
+            if let Err(e) = client.cancel_job(&sid).await {
+                error!("Failed to cancel job {}: {}", sid, e);
+            }
+            return None;
+        }
+        Effect::ReattachJob { entry } => {
+            let result = client
+                .get_job_status(&entry.sid)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string());
+            if let Err(e) = &result {
+                info!("Job {} no longer available: {}", entry.sid, e);
+            }
+            Action::JobReattached { entry, result }
+        }
+        Effect::ListServerSearches => {
+            let result = match client.list_saved_searches().await {
+                Ok(mut searches) => {
+                    // Last run times come from the job list; missing jobs just leave it blank.
+                    if let Ok(jobs) = client.list_jobs().await {
+                        for saved in searches.iter_mut() {
+                            saved.last_run = jobs
+                                .iter()
+                                .filter(|j| {
+                                    j.is_saved_search && j.label.as_deref() == Some(&saved.name)
+                                })
+                                .map(|j| j.published.clone())
+                                .max();
+                        }
+                    }
+                    Ok(searches)
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            Action::ServerSearchesLoaded(result)
+        }
+        Effect::DispatchSavedSearch(saved) => {
+            let result = client
+                .dispatch_saved_search(&saved)
+                .await
+                .map_err(|e| e.to_string());
+            if let Ok(sid) = &result {
+                info!("Dispatched saved search '{}': {}", saved.name, sid);
+            }
+            Action::SavedSearchDispatched { saved, result }
+        }
+        Effect::FindLastRun(saved) => {
+            let result = client
+                .latest_saved_search_run(&saved)
+                .await
+                .map_err(|e| e.to_string());
+            Action::LastRunFound { saved, result }
+        }
+        Effect::SetScheduled { saved, scheduled } => {
+            let result = client
+                .set_saved_search_scheduled(&saved, scheduled)
+                .await
+                .map_err(|e| e.to_string());
+            Action::ScheduleSet {
+                name: saved.name,
+                scheduled,
+                result,
+            }
+        }
+        Effect::LoadSync { app } => {
+            let result = load_sync(client, &app).await;
+            Action::SyncLoaded { app, result }
+        }
+        Effect::ApplySync {
+            namespace,
+            entries,
+            push,
+        } => {
+            let mut done = 0;
+            let mut errors = Vec::new();
+            for entry in entries {
+                let result = if push {
+                    search_sync::push(client, &namespace, &entry).await
+                } else {
+                    let entry = entry.clone();
+                    blocking(move || search_sync::pull(&entry)).await
+                };
+                match result {
+                    Ok(msg) => {
+                        info!("{}", msg);
+                        done += 1;
+                    }
+                    Err(e) => {
+                        error!("Sync of '{}' failed: {}", entry.name, e);
+                        errors.push(format!("{}: {}", entry.name, e));
+                    }
+                }
+            }
+            Action::SyncApplied {
+                push,
+                done,
+                errors,
+                refreshed: load_sync(client, &namespace.app).await,
+            }
+        }
+        Effect::ListApps => Action::AppsLoaded(client.list_apps().await.map_err(|e| e.to_string())),
+        Effect::LoadDashboard { source } => {
+            let result = if std::path::Path::new(&source).is_file() {
+                tokio::fs::read_to_string(&source)
+                    .await
+                    .map_err(|e| e.to_string())
+            } else {
+                client
+                    .get_dashboard(&source)
+                    .await
+                    .map_err(|e| e.to_string())
+            };
+            Action::DashboardLoaded { source, result }
+        }
+        effect => return blocking(move || store(effect)).await,
+    };
+    Some(action)
+}
+
+impl Effect {
+    /// Whether `store` runs this effect, on the executor's file worker.
+    fn is_local(&self) -> bool {
+        matches!(
+            self,
+            Effect::AppendHistory(_)
+                | Effect::RecordCompletion { .. }
+                | Effect::SaveTokenValues(_)
+                | Effect::SaveTheme(_)
+                | Effect::SaveApp { .. }
+                | Effect::LoadProfile { .. }
+                | Effect::SaveSearch { .. }
+                | Effect::LoadSavedSearches
+                | Effect::ChangeSavedSearch { .. }
+                | Effect::Export { .. }
+        )
+    }
+}
+
+fn with_local_files<T>(f: impl FnOnce() -> T) -> T {
+    let _guard = LOCAL_FILES.lock().unwrap_or_else(PoisonError::into_inner);
+    f()
+}
+
+/// Runs `f` on tokio's blocking pool while holding the local-files lock.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(move || with_local_files(f))
+        .await
+        .expect("local file task panicked")
+}
+
+/// Runs an effect on the local files.
+pub fn store(effect: Effect) -> Option<Action> {
+    let action = match effect {
+        Effect::AppendHistory(entry) => {
+            if let Err(e) = HistoryManager::append(&entry) {
+                error!("Failed to write history: {}", e);
+            }
+            return None;
+        }
+        Effect::RecordCompletion {
+            sid,
+            result_count,
+            duration,
+        } => {
+            if let Err(e) = HistoryManager::record_completion(&sid, result_count, duration) {
+                error!("Failed to update history: {}", e);
+            }
+            return None;
+        }
+        Effect::SaveTokenValues(values) => {
+            if let Err(e) = values.save() {
+                error!("Failed to save token values: {}", e);
+            }
+            return None;
+        }
+        Effect::SaveTheme(theme) => {
+            if let Err(e) = Config::save_theme(&theme) {
+                error!("Failed to save theme: {}", e);
+            }
+            return None;
+        }
+        Effect::SaveApp { profile, app } => {
+            let result = Config::save_app(&profile, app.as_deref()).map_err(|e| e.to_string());
+            Action::AppSaved { app, result }
+        }
+        Effect::LoadProfile { name } => {
+            let result = Config::load_profile(Some(&name))
+                .and_then(|config| {
+                    config.validate()?;
+                    Ok(Box::new(config))
+                })
+                .map_err(|e| e.to_string());
+            Action::ProfileLoaded { name, result }
+        }
+        Effect::SaveSearch { search, overwrite } => {
+            let result = SavedSearchManager::save_view(search).map_err(|e| e.to_string());
+            Action::SearchSaved { overwrite, result }
+        }
+        Effect::LoadSavedSearches => {
+            Action::SavedSearchesLoaded(SavedSearchManager::load_all().map_err(|e| e.to_string()))
+        }
+        Effect::ChangeSavedSearch { name, change } => {
+            let result = match &change {
+                SearchChange::Rename(new) => SavedSearchManager::rename_search(&name, new),
+                SearchChange::Duplicate(new) => SavedSearchManager::duplicate_search(&name, new),
+                SearchChange::Delete => SavedSearchManager::delete_search(&name),
+            }
+            .map_err(|e| e.to_string());
+            Action::SavedSearchChanged {
+                name,
+                change,
+                result,
+            }
+        }
+        Effect::Export { path, text, rows } => {
+            let result = std::fs::write(&path, &text).map_err(|e| e.to_string());
+            Action::Exported {
+                path,
+                rows,
+                bytes: text.len(),
+                result,
+            }
+        }
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// The diff between the local saved searches and those of `app`.
+async fn load_sync(client: &SplunkClient, app: &str) -> Result<Vec<SyncEntry>, String> {
+    let local = blocking(search_sync::load_local)
+        .await
+        .map_err(|e| e.to_string())?;
+    let server = search_sync::load_server(client, app)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(search_sync::diff(&local, &server))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_kill_job_works_in_read_only_mode() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let n = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        let client = SplunkClient::new(base_url, "t".to_string(), true).with_read_only(true);
+        let action = perform(
+            &client,
+            Effect::KillJob {
+                sid: "42".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(
+            action,
+            Some(Action::JobKilled { sid, result: Ok(()) }) if sid == "42"
+        ));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /services/search/jobs/42/control "));
+    }
+}
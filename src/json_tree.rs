@@ -0,0 +1,154 @@
+//! Foldable tree of a (recursively parsed) event for the detail pane. Folds
+//! are keyed by JSONPath so they carry over between events of the same shape.
+
+use serde_json::Value;
+use std::collections::HashSet;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Seg {
+    Key(String),
+    Index(usize),
+}
+
+/// One visible line of the tree.
+#[derive(Clone, Debug)]
+pub struct Row {
+    pub path: Vec<Seg>,
+    pub depth: usize,
+    /// Child count for objects and arrays, `None` for scalars.
+    pub children: Option<usize>,
+    pub folded: bool,
+}
+
+impl Row {
+    pub fn label(&self) -> String {
+        match self.path.last() {
+            Some(Seg::Key(k)) => k.clone(),
+            Some(Seg::Index(i)) => format!("[{}]", i),
+            None => "$".to_string(),
+        }
+    }
+}
+
+/// `$.a.b[0]`, with bracket notation for keys that aren't identifiers.
+pub fn json_path(path: &[Seg]) -> String {
+    let mut out = String::from("$");
+    for seg in path {
+        match seg {
+            Seg::Key(k)
+                if !k.is_empty()
+                    && !k.starts_with(|c: char| c.is_ascii_digit())
+                    && k.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                out.push('.');
+                out.push_str(k);
+            }
+            Seg::Key(k) => out.push_str(&format!("['{}']", k.replace('\'', "\\'"))),
+            Seg::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+pub fn get<'a>(value: &'a Value, path: &[Seg]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, seg| match seg {
+        Seg::Key(k) => v.get(k),
+        Seg::Index(i) => v.get(i),
+    })
+}
+
+fn children(value: &Value) -> Vec<(Seg, &Value)> {
+    match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (Seg::Key(k.clone()), v)).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (Seg::Index(i), v))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Visible rows below the root, skipping the contents of folded paths.
+pub fn rows(value: &Value, folded: &HashSet<String>) -> Vec<Row> {
+    fn walk(value: &Value, path: &mut Vec<Seg>, folded: &HashSet<String>, out: &mut Vec<Row>) {
+        for (seg, child) in children(value) {
+            path.push(seg);
+            let count =
+                matches!(child, Value::Object(_) | Value::Array(_)).then(|| children(child).len());
+            let is_folded = count.is_some() && folded.contains(&json_path(path));
+            out.push(Row {
+                path: path.clone(),
+                depth: path.len() - 1,
+                children: count,
+                folded: is_folded,
+            });
+            if count.is_some() && !is_folded {
+                walk(child, path, folded, out);
+            }
+            path.pop();
+        }
+    }
+    let mut out = Vec::new();
+    walk(value, &mut Vec::new(), folded, &mut out);
+    out
+}
+
+/// Folds every non-empty container nested deeper than `depth`, like vim's
+/// `foldlevel`: 0 folds everything, 1 opens the top-level containers.
+pub fn fold_to_depth(value: &Value, depth: usize) -> HashSet<String> {
+    fn walk(value: &Value, path: &mut Vec<Seg>, depth: usize, out: &mut HashSet<String>) {
+        for (seg, child) in children(value) {
+            path.push(seg);
+            if !children(child).is_empty() {
+                if path.len() > depth {
+                    out.insert(json_path(path));
+                }
+                walk(child, path, depth, out);
+            }
+            path.pop();
+        }
+    }
+    let mut out = HashSet::new();
+    walk(value, &mut Vec::new(), depth, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_folds_and_paths() {
+        let event = json!({
+            "user": {"name": "alice", "groups": ["admin", "ops"]},
+            "source ip": "10.0.0.1"
+        });
+        let all = rows(&event, &HashSet::new());
+        let paths: Vec<String> = all.iter().map(|r| json_path(&r.path)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "$.user",
+                "$.user.name",
+                "$.user.groups",
+                "$.user.groups[0]",
+                "$.user.groups[1]",
+                "$['source ip']",
+            ]
+        );
+        assert_eq!(all[2].children, Some(2));
+        assert_eq!(all[2].depth, 1);
+        assert_eq!(all[3].label(), "[0]");
+        assert_eq!(get(&event, &all[4].path), Some(&json!("ops")));
+
+        let folded = fold_to_depth(&event, 1);
+        assert_eq!(folded, HashSet::from(["$.user.groups".to_string()]));
+        let shown = rows(&event, &folded);
+        assert_eq!(shown.len(), 4);
+        assert!(shown[2].folded);
+
+        assert_eq!(rows(&event, &fold_to_depth(&event, 0)).len(), 2);
+    }
+}
@@ -0,0 +1,579 @@
+//! Named actions and the keys bound to them in each mode. The defaults can be
+//! changed per mode with `[keys.<mode>]` tables in config.toml, e.g.
+//!
+//! ```toml
+//! [keys.normal]
+//! scroll_down_fast = ["ctrl+d", "pagedown"]
+//! quit = "Q"
+//! ```
+//!
+//! Only these modes are bindable. The keys inside modal views (finders,
+//! pickers, the timeline, dashboards, ...) are fixed and listed in the help.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+
+/// A set of bindings. `Detail` and `Chart` are looked up before `Normal`
+/// while the detail pane or a chart has focus, and `Global` in every mode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Global,
+    Normal,
+    Detail,
+    Chart,
+    Editing,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 5] = [
+        Mode::Global,
+        Mode::Normal,
+        Mode::Detail,
+        Mode::Chart,
+        Mode::Editing,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Global => "global",
+            Mode::Normal => "normal",
+            Mode::Detail => "detail",
+            Mode::Chart => "chart",
+            Mode::Editing => "editing",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Mode::Global => "General",
+            Mode::Normal => "Results & Navigation",
+            Mode::Detail => "Event Detail (pane focused)",
+            Mode::Chart => "Chart View",
+            Mode::Editing => "Search Input",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Help,
+    Palette,
+    Quit,
+    EditQuery,
+    RunSearch,
+    KillJob,
+    CycleTheme,
+    SelectApp,
+    LoadSearch,
+    SaveSearch,
+    ServerSearches,
+    Sync,
+    History,
+    Timeline,
+    Dashboard,
+    CycleView,
+    OpenInEditor,
+    OpenJobUrl,
+    Copy,
+    ClearResults,
+    LocalSearch,
+    NextMatch,
+    PrevMatch,
+    LocalPipeline,
+    CycleFocus,
+    MoveDown,
+    MoveUp,
+    ScrollDownFast,
+    ScrollUpFast,
+    FocusList,
+    FocusDetail,
+    CycleDetailFormat,
+    ToggleFold,
+    Fold,
+    CopyValue,
+    CopyPath,
+    ChartLeft,
+    ChartRight,
+    CycleChartKind,
+    FormatQuery,
+    ToggleVim,
+    HistorySearch,
+    EditExternally,
+}
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::Palette => "command_palette",
+            Action::Quit => "quit",
+            Action::EditQuery => "edit_query",
+            Action::RunSearch => "run_search",
+            Action::KillJob => "kill_job",
+            Action::CycleTheme => "cycle_theme",
+            Action::SelectApp => "select_app",
+            Action::LoadSearch => "load_search",
+            Action::SaveSearch => "save_search",
+            Action::ServerSearches => "server_searches",
+            Action::Sync => "sync",
+            Action::History => "history",
+            Action::Timeline => "timeline",
+            Action::Dashboard => "dashboard",
+            Action::CycleView => "cycle_view",
+            Action::OpenInEditor => "open_in_editor",
+            Action::OpenJobUrl => "open_job_url",
+            Action::Copy => "copy",
+            Action::ClearResults => "clear_results",
+            Action::LocalSearch => "local_search",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::LocalPipeline => "local_pipeline",
+            Action::CycleFocus => "cycle_focus",
+            Action::MoveDown => "move_down",
+            Action::MoveUp => "move_up",
+            Action::ScrollDownFast => "scroll_down_fast",
+            Action::ScrollUpFast => "scroll_up_fast",
+            Action::FocusList => "focus_list",
+            Action::FocusDetail => "focus_detail",
+            Action::CycleDetailFormat => "cycle_detail_format",
+            Action::ToggleFold => "toggle_fold",
+            Action::Fold => "fold",
+            Action::CopyValue => "copy_value",
+            Action::CopyPath => "copy_path",
+            Action::ChartLeft => "chart_left",
+            Action::ChartRight => "chart_right",
+            Action::CycleChartKind => "cycle_chart_kind",
+            Action::FormatQuery => "format_query",
+            Action::ToggleVim => "toggle_vim",
+            Action::HistorySearch => "history_search",
+            Action::EditExternally => "edit_externally",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Help => "Show this Help",
+            Action::Palette => "Command Palette (actions and :commands)",
+            Action::Quit => "Quit",
+            Action::EditQuery => "Enter Search Input Mode",
+            Action::RunSearch => "Run Search",
+            Action::KillJob => "Kill the Running Job",
+            Action::CycleTheme => "Cycle Themes",
+            Action::SelectApp => "Select App Namespace",
+            Action::LoadSearch => "Load Saved Search (type to fuzzy find)",
+            Action::SaveSearch => "Save Current Search",
+            Action::ServerSearches => "Browse Server Saved Searches",
+            Action::Sync => "Sync Local <-> Server Searches",
+            Action::History => "Search History Browser",
+            Action::Timeline => "Focus Timeline (Enter filter, z zoom)",
+            Action::Dashboard => "Open Dashboard (i inputs, r refresh)",
+            Action::CycleView => "Cycle Table/Chart/Single Value/Raw View",
+            Action::OpenInEditor => "Open Results/Query in External Editor",
+            Action::OpenJobUrl => "Open Job in Browser",
+            Action::Copy => "Copy Query/Row/_raw/Field/Table/Job URL",
+            Action::ClearResults => "Clear Results",
+            Action::LocalSearch => "Local Regex Search (field:regex)",
+            Action::NextMatch => "Next Local Search Match",
+            Action::PrevMatch => "Previous Local Search Match",
+            Action::LocalPipeline => "Local Pipeline (Ctrl+P adds to query)",
+            Action::CycleFocus => "Cycle Focus (Search > List > Detail)",
+            Action::MoveDown => "Scroll / Navigate Down",
+            Action::MoveUp => "Scroll / Navigate Up",
+            Action::ScrollDownFast => "Fast Scroll Down",
+            Action::ScrollUpFast => "Fast Scroll Up",
+            Action::FocusList => "Focus the List",
+            Action::FocusDetail => "Focus the Detail Pane",
+            Action::CycleDetailFormat => "Cycle Tree/YAML/JSON/_raw",
+            Action::ToggleFold => "Toggle Node (Tree)",
+            Action::Fold => "Fold Prefix (a/o/c, M/R, 0-9 depth)",
+            Action::CopyValue => "Copy Value (Tree)",
+            Action::CopyPath => "Copy JSONPath (Tree)",
+            Action::ChartLeft => "Move Chart Cursor Left",
+            Action::ChartRight => "Move Chart Cursor Right",
+            Action::CycleChartKind => "Cycle Line/Bar/Stacked Area",
+            Action::FormatQuery => "Format Query",
+            Action::ToggleVim => "Toggle Vim/Standard Mode",
+            Action::HistorySearch => "Reverse Search History",
+            Action::EditExternally => "Edit Query in External Editor",
+        }
+    }
+}
+
+/// Default bindings, in help order.
+fn defaults(mode: Mode) -> &'static [(Action, &'static [&'static str])] {
+    match mode {
+        Mode::Global => &[(Action::Help, &["ctrl+/", "f1"])],
+        Mode::Normal => &[
+            (Action::Quit, &["q"]),
+            (Action::Palette, &[":", "ctrl+p"]),
+            (Action::EditQuery, &["e"]),
+            (Action::RunSearch, &["enter"]),
+            (Action::KillJob, &["x"]),
+            (Action::CycleTheme, &["t", "ctrl+t"]),
+            (Action::SelectApp, &["a"]),
+            (Action::MoveDown, &["j", "down"]),
+            (Action::MoveUp, &["k", "up"]),
+            (Action::ScrollDownFast, &["ctrl+j"]),
+            (Action::ScrollUpFast, &["ctrl+k"]),
+            (Action::FocusList, &["h", "left"]),
+            (Action::FocusDetail, &["l", "right"]),
+            (Action::CycleFocus, &["tab"]),
+            (Action::ClearResults, &["ctrl+r"]),
+            (Action::LoadSearch, &["ctrl+l"]),
+            (Action::SaveSearch, &["ctrl+s"]),
+            (Action::ServerSearches, &["r"]),
+            (Action::Sync, &["S"]),
+            (Action::History, &["H"]),
+            (Action::Timeline, &["T"]),
+            (Action::Dashboard, &["D"]),
+            (Action::OpenJobUrl, &["E"]),
+            (Action::CycleView, &["ctrl+v"]),
+            (Action::OpenInEditor, &["ctrl+x"]),
+            (Action::Copy, &["y"]),
+            (Action::LocalSearch, &["/"]),
+            (Action::NextMatch, &["n"]),
+            (Action::PrevMatch, &["N"]),
+            (Action::LocalPipeline, &["|"]),
+        ],
+        Mode::Detail => &[
+            (Action::CycleDetailFormat, &["v"]),
+            (Action::ToggleFold, &["enter"]),
+            (Action::Fold, &["z"]),
+            (Action::CopyValue, &["y"]),
+            (Action::CopyPath, &["Y"]),
+        ],
+        Mode::Chart => &[
+            (Action::ChartLeft, &["h", "left"]),
+            (Action::ChartRight, &["l", "right"]),
+            (Action::CycleChartKind, &["c"]),
+        ],
+        Mode::Editing => &[
+            (Action::RunSearch, &["enter"]),
+            (Action::FormatQuery, &["alt+shift+f"]),
+            (Action::ToggleVim, &["ctrl+v"]),
+            (Action::HistorySearch, &["ctrl+r"]),
+            (Action::EditExternally, &["ctrl+x"]),
+        ],
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Folds the ways terminals report the same key: Shift is part of the
+    /// character (`E`, `?`), and Ctrl+/ arrives as Ctrl+_, Ctrl+7 or Ctrl+?.
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) => {
+                let shifted = modifiers.contains(KeyModifiers::SHIFT);
+                modifiers.remove(KeyModifiers::SHIFT);
+                match c {
+                    '_' | '7' | '?' if modifiers.contains(KeyModifiers::CONTROL) => {
+                        KeyCode::Char('/')
+                    }
+                    c if shifted => KeyCode::Char(c.to_ascii_uppercase()),
+                    c => KeyCode::Char(c),
+                }
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Key { code, modifiers }
+    }
+
+    pub fn from_event(event: &KeyEvent) -> Key {
+        Key::new(event.code, event.modifiers)
+    }
+
+    /// Parses `ctrl+j`, `alt+shift+f`, `E`, `enter`, `pagedown`, `f1`, ...
+    pub fn parse(text: &str) -> Result<Key, String> {
+        let (mods, name) = if text == "+" || text.ends_with("++") {
+            (&text[..text.len() - 1], "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", m, text)),
+            };
+        }
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                f => match f.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", text)),
+                },
+            },
+        };
+        Ok(Key::new(code, modifiers))
+    }
+
+    pub fn label(&self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Insert => "Insert".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::Up => "Up".to_string(),
+            KeyCode::Down => "Down".to_string(),
+            KeyCode::Left => "Left".to_string(),
+            KeyCode::Right => "Right".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => format!("{:?}", code),
+        };
+        let mut label = String::new();
+        for (modifier, text) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(text);
+            }
+        }
+        label + &name
+    }
+}
+
+impl From<Key> for KeyEvent {
+    fn from(key: Key) -> KeyEvent {
+        KeyEvent::new(key.code, key.modifiers)
+    }
+}
+
+/// An action and its keys, in help order.
+type Bindings = Vec<(Action, Vec<Key>)>;
+
+pub struct Keymap {
+    bindings: Vec<(Mode, Bindings)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Mode::ALL
+            .iter()
+            .map(|&mode| {
+                let actions = defaults(mode)
+                    .iter()
+                    .map(|(action, keys)| {
+                        let keys = keys.iter().filter_map(|k| Key::parse(k).ok()).collect();
+                        (*action, keys)
+                    })
+                    .collect();
+                (mode, actions)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// The defaults with `overrides` (mode name -> action name -> keys)
+    /// applied. Keys given to an action are taken from the others in the
+    /// same mode. Returns the problems found alongside.
+    pub fn load(
+        overrides: &BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    ) -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut errors = Vec::new();
+        for (mode_name, actions) in overrides {
+            let Some((_, bindings)) = keymap
+                .bindings
+                .iter_mut()
+                .find(|(mode, _)| mode.name() == mode_name)
+            else {
+                errors.push(format!("keys.{}: unknown mode", mode_name));
+                continue;
+            };
+            for (action_name, keys) in actions {
+                let Some(index) = bindings.iter().position(|(a, _)| a.name() == action_name) else {
+                    errors.push(format!(
+                        "keys.{}: no action '{}' in this mode",
+                        mode_name, action_name
+                    ));
+                    continue;
+                };
+                let keys: Vec<Key> = keys
+                    .iter()
+                    .filter_map(|k| {
+                        Key::parse(k)
+                            .map_err(|e| errors.push(format!("keys.{}: {}", mode_name, e)))
+                            .ok()
+                    })
+                    .collect();
+                for (_, other) in bindings.iter_mut() {
+                    other.retain(|k| !keys.contains(k));
+                }
+                bindings[index].1 = keys;
+            }
+        }
+        (keymap, errors)
+    }
+
+    fn mode(&self, mode: Mode) -> &[(Action, Vec<Key>)] {
+        self.bindings
+            .iter()
+            .find(|(m, _)| *m == mode)
+            .map(|(_, b)| b.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The action bound to `event` in the first of `modes` that has one.
+    pub fn action(&self, modes: &[Mode], event: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        modes.iter().find_map(|&mode| {
+            self.mode(mode)
+                .iter()
+                .find(|(_, keys)| keys.contains(&key))
+                .map(|(action, _)| *action)
+        })
+    }
+
+    /// Bound actions of `mode` in help order.
+    pub fn bound(&self, mode: Mode) -> impl Iterator<Item = Action> + '_ {
+        self.mode(mode)
+            .iter()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(action, _)| *action)
+    }
+
+    /// Label of the first key bound to `action`, for hints.
+    pub fn first(&self, mode: Mode, action: Action) -> Option<String> {
+        self.mode(mode)
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, keys)| keys.first())
+            .map(Key::label)
+    }
+
+    /// Every action once, with the first mode that has it.
+    pub fn actions(&self) -> Vec<(Mode, Action)> {
+        let mut all: Vec<(Mode, Action)> = Vec::new();
+        for (mode, bindings) in &self.bindings {
+            for (action, _) in bindings {
+                if !all.iter().any(|(_, a)| a == action) {
+                    all.push((*mode, *action));
+                }
+            }
+        }
+        all
+    }
+
+    /// `Ctrl+j / PgDn`, or an empty string if unbound.
+    pub fn label(&self, mode: Mode, action: Action) -> String {
+        self.mode(mode)
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.iter().map(Key::label).collect::<Vec<_>>().join(" / "))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_lookup_and_overrides() {
+        assert_eq!(Key::parse("alt+shift+f").unwrap().label(), "Alt+F");
+        assert_eq!(Key::parse("ctrl++").unwrap().label(), "Ctrl++");
+        assert_eq!(Key::parse("pgdn").unwrap().label(), "PgDn");
+        assert!(Key::parse("hyper+x").is_err());
+        assert!(Key::parse("f13").is_err());
+
+        let keymap = Keymap::default();
+        let normal = [Mode::Normal];
+        assert_eq!(
+            keymap.action(&normal, &event(KeyCode::Char('E'), KeyModifiers::SHIFT)),
+            Some(Action::OpenJobUrl)
+        );
+        // Terminals that send Ctrl+/ as Ctrl+_ or Ctrl+7.
+        for c in ['/', '_', '7'] {
+            assert_eq!(
+                keymap.action(
+                    &[Mode::Global],
+                    &event(KeyCode::Char(c), KeyModifiers::CONTROL)
+                ),
+                Some(Action::Help)
+            );
+        }
+        // Per-mode maps: Enter toggles a tree node before it runs the search.
+        let enter = event(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(keymap.action(&normal, &enter), Some(Action::RunSearch));
+        assert_eq!(
+            keymap.action(&[Mode::Detail, Mode::Normal], &enter),
+            Some(Action::ToggleFold)
+        );
+
+        let overrides = BTreeMap::from([
+            (
+                "normal".to_string(),
+                BTreeMap::from([
+                    ("scroll_down_fast".to_string(), vec!["ctrl+d".to_string()]),
+                    // Takes `q` away from quit.
+                    ("kill_job".to_string(), vec!["q".to_string()]),
+                    ("bogus".to_string(), vec!["b".to_string()]),
+                ]),
+            ),
+            ("nope".to_string(), BTreeMap::new()),
+        ]);
+        let (keymap, errors) = Keymap::load(&overrides);
+        assert_eq!(errors.len(), 2);
+        let ctrl = |c| event(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(
+            keymap.action(&normal, &ctrl('d')),
+            Some(Action::ScrollDownFast)
+        );
+        assert_eq!(keymap.action(&normal, &ctrl('j')), None);
+        assert_eq!(
+            keymap.action(&normal, &event(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Some(Action::KillJob)
+        );
+        assert_eq!(keymap.label(Mode::Normal, Action::Quit), "");
+        assert_eq!(keymap.label(Mode::Normal, Action::CycleView), "Ctrl+v");
+    }
+}
@@ -1,7 +1,20 @@
 mod api;
+mod chart;
 mod config;
 mod config_wizard;
+mod dashboard;
+mod editor;
+mod effect;
+mod json_tree;
+mod keymap;
 mod models;
+mod palette;
+mod search;
+mod spl;
+mod stat;
+mod sync;
+mod theme;
+mod timeline;
 mod tui;
 mod utils;
 
@@ -21,15 +34,115 @@ struct Cli {
 enum Commands {
     /// Run the configuration wizard
     Config,
+    /// Format SPL read from stdin and print it
+    Fmt,
+    /// Run a search and print its results as JSON lines
+    Search {
+        /// SPL to run; may contain `$token$` placeholders
+        #[arg(required_unless_present = "saved", conflicts_with = "saved")]
+        query: Option<String>,
+        /// Run a local saved search instead of a query
+        #[arg(long, value_name = "NAME")]
+        saved: Option<String>,
+        /// Value for a `$token$` placeholder (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+        /// Earliest time, e.g. `-24h` (overrides the saved search's)
+        #[arg(long)]
+        earliest: Option<String>,
+        /// Latest time, e.g. `now` (overrides the saved search's)
+        #[arg(long)]
+        latest: Option<String>,
+        /// Maximum number of results to print (0 for all)
+        #[arg(long, default_value_t = 100)]
+        count: u32,
+    },
+    /// Open the TUI on a dashboard
+    Dashboard {
+        /// Dashboard name on the server, or a path to a Simple XML/Studio JSON file
+        source: String,
+    },
+    /// Diff local saved searches against an app's server saved searches and sync them
+    Sync {
+        /// App to sync with (defaults to the active profile's app)
+        #[arg(long)]
+        app: Option<String>,
+        /// Owner namespace for newly created reports (defaults to `nobody`)
+        #[arg(long)]
+        owner: Option<String>,
+        /// Push the named local search to the server (repeatable)
+        #[arg(long, value_name = "NAME")]
+        push: Vec<String>,
+        /// Pull the named server search into a local file (repeatable)
+        #[arg(long, value_name = "NAME")]
+        pull: Vec<String>,
+        /// Push every new or changed local search
+        #[arg(long)]
+        push_all: bool,
+        /// Pull every new or changed server search
+        #[arg(long, conflicts_with = "push_all")]
+        pull_all: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Cli::parse();
 
-    if let Some(Commands::Config) = args.command {
-        config_wizard::run()?;
-        return Ok(());
+    let mut dashboard = None;
+    match args.command {
+        Some(Commands::Config) => {
+            config_wizard::run()?;
+            return Ok(());
+        }
+        Some(Commands::Fmt) => {
+            let mut query = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut query)?;
+            println!("{}", spl::format::format(&query));
+            return Ok(());
+        }
+        Some(Commands::Search {
+            query,
+            saved,
+            vars,
+            earliest,
+            latest,
+            count,
+        }) => {
+            search::run(search::SearchArgs {
+                query,
+                saved,
+                vars,
+                earliest,
+                latest,
+                count,
+            })
+            .await
+            .map_err(|e| e as Box<dyn std::error::Error>)?;
+            return Ok(());
+        }
+        Some(Commands::Sync {
+            app,
+            owner,
+            push,
+            pull,
+            push_all,
+            pull_all,
+        }) => {
+            sync::run(sync::SyncArgs {
+                app,
+                owner,
+                push,
+                pull,
+                push_all,
+                pull_all,
+            })
+            .await
+            .map_err(|e| e as Box<dyn std::error::Error>)?;
+            return Ok(());
+        }
+        Some(Commands::Dashboard { source }) => dashboard = Some(source),
+        None => {}
     }
 
     let _ = WriteLogger::init(
@@ -39,7 +152,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     log::info!("Application started");
 
-    tui::run_app().await?;
+    tui::run_app(dashboard).await?;
     Ok(())
 }
 
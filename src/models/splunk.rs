@@ -1,5 +1,24 @@
 use serde::{Deserialize, Serialize};
 
+/// Search time bounds in Splunk time modifier syntax (e.g. `-24h@h`, `now`).
+/// `None` leaves the bound to the server default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeRange {
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+}
+
+impl TimeRange {
+    pub fn label(&self) -> String {
+        match (&self.earliest, &self.latest) {
+            (None, None) => String::from("All time"),
+            (Some(e), None) => format!("{} to now", e),
+            (None, Some(l)) => format!("All time to {}", l),
+            (Some(e), Some(l)) => format!("{} to {}", e, l),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchJob {
     pub sid: String,
@@ -21,8 +40,56 @@ pub struct JobStatus {
     pub event_count: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One bucket of a job's `/timeline`: event count over `duration` seconds
+/// from `earliest_time` (epoch seconds).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TimelineBucket {
+    pub earliest_time: f64,
+    pub duration: f64,
+    pub total_count: u64,
+}
+
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
+pub struct SplunkApp {
+    pub name: String,
+    pub label: String,
+    pub visible: bool,
+    pub disabled: bool,
+}
+
+/// A saved search (report or alert) stored on the Splunk server.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSavedSearch {
+    pub name: String,
+    pub app: String,
+    pub owner: String,
+    pub search: String,
+    pub description: String,
+    pub cron_schedule: String,
+    pub is_scheduled: bool,
+    pub disabled: bool,
+    pub earliest_time: String,
+    pub latest_time: String,
+    /// Whether the ACL lets the current user modify this object.
+    pub can_write: bool,
+    /// Publish time of the most recent run found in the job list, if any.
+    pub last_run: Option<String>,
+}
+
+/// Summary of a job from `GET search/jobs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobSummary {
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub published: String,
+    #[serde(rename = "isSavedSearch", default)]
+    pub is_saved_search: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SplunkError {
     pub messages: Vec<SplunkErrorMessage>,
 }
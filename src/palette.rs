@@ -0,0 +1,164 @@
+//! The command palette: fuzzy-find any action, or type an ex-style command
+//! such as `earliest -24h`, `export csv out.csv` or `set nowrap`.
+
+use crate::utils::clipboard;
+use crate::utils::fuzzy::fuzzy_match;
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "csv" => ExportFormat::Csv,
+            "tsv" => ExportFormat::Tsv,
+            "json" => ExportFormat::Json,
+            "md" | "markdown" => ExportFormat::Markdown,
+            _ => return None,
+        })
+    }
+
+    /// The rows as text; JSON keeps every field, the others `columns`.
+    pub fn render(self, columns: &[String], rows: &[Value]) -> String {
+        match self {
+            ExportFormat::Csv => clipboard::csv(columns, rows),
+            ExportFormat::Tsv => clipboard::tsv(columns, rows),
+            ExportFormat::Markdown => clipboard::markdown(columns, rows),
+            ExportFormat::Json => serde_json::to_string_pretty(rows).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Setting {
+    Wrap(bool),
+    Vim(bool),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    /// `None` clears the bound.
+    Earliest(Option<String>),
+    Latest(Option<String>),
+    Export {
+        format: ExportFormat,
+        path: String,
+    },
+    Set(Vec<Setting>),
+    Theme(String),
+    Profile(String),
+}
+
+/// Names shown as hints while typing.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("earliest <time>", "Set the earliest time, e.g. -24h or @d"),
+    ("latest <time>", "Set the latest time"),
+    (
+        "export csv|tsv|json|md <file>",
+        "Write the visible results to a file",
+    ),
+    ("set wrap|nowrap|vim|novim", "Change an option"),
+    ("theme <name>", "Apply a theme"),
+    ("profile <name>", "Switch to a profile from config.toml"),
+];
+
+/// Parses an ex-style command. `None` means the first word isn't one, so the
+/// input is a filter for the action list instead.
+pub fn parse(input: &str) -> Option<Result<Command, String>> {
+    let input = input.trim().trim_start_matches(':');
+    let (name, rest) = input.split_once(' ').unwrap_or((input, ""));
+    let rest = rest.trim();
+    let arg = (!rest.is_empty()).then(|| rest.to_string());
+    let command = match name {
+        "earliest" => Ok(Command::Earliest(arg)),
+        "latest" => Ok(Command::Latest(arg)),
+        "export" => {
+            let (format, path) = rest.split_once(' ').unwrap_or((rest, ""));
+            let path = path.trim().trim_matches('"');
+            match ExportFormat::parse(format) {
+                _ if path.is_empty() => Err("usage: export csv|tsv|json|md <file>".to_string()),
+                Some(format) => Ok(Command::Export {
+                    format,
+                    path: path.to_string(),
+                }),
+                None => Err(format!("unknown export format '{}'", format)),
+            }
+        }
+        "set" => rest
+            .split_whitespace()
+            .map(|option| match option {
+                "wrap" => Ok(Setting::Wrap(true)),
+                "nowrap" => Ok(Setting::Wrap(false)),
+                "vim" => Ok(Setting::Vim(true)),
+                "novim" => Ok(Setting::Vim(false)),
+                _ => Err(format!("unknown option '{}'", option)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|settings| {
+                if settings.is_empty() {
+                    Err("usage: set wrap|nowrap|vim|novim".to_string())
+                } else {
+                    Ok(Command::Set(settings))
+                }
+            }),
+        "theme" | "profile" => match arg {
+            Some(arg) if name == "theme" => Ok(Command::Theme(arg)),
+            Some(arg) => Ok(Command::Profile(arg)),
+            None => Err(format!("usage: {} <name>", name)),
+        },
+        _ => return None,
+    };
+    Some(command)
+}
+
+/// Indices of `candidates` matching `query`, best first.
+pub fn rank<'a>(query: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = candidates
+        .enumerate()
+        .filter_map(|(i, text)| fuzzy_match(query, text).map(|(score, _)| (score, i)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands_and_rank() {
+        assert_eq!(
+            parse(":earliest -24h"),
+            Some(Ok(Command::Earliest(Some("-24h".to_string()))))
+        );
+        assert_eq!(parse("latest"), Some(Ok(Command::Latest(None))));
+        assert_eq!(
+            parse("export csv \"my results.csv\""),
+            Some(Ok(Command::Export {
+                format: ExportFormat::Csv,
+                path: "my results.csv".to_string()
+            }))
+        );
+        assert!(matches!(parse("export xls out.xls"), Some(Err(_))));
+        assert!(matches!(parse("export csv"), Some(Err(_))));
+        assert_eq!(
+            parse("set nowrap vim"),
+            Some(Ok(Command::Set(vec![
+                Setting::Wrap(false),
+                Setting::Vim(true)
+            ])))
+        );
+        assert!(matches!(parse("set bogus"), Some(Err(_))));
+        assert!(parse("kill").is_none());
+
+        let names = ["Run Search", "Kill the Running Job", "Cycle Themes"];
+        assert_eq!(rank("kill", names.into_iter()), vec![1]);
+        assert_eq!(rank("", names.into_iter()), vec![0, 1, 2]);
+    }
+}
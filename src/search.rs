@@ -0,0 +1,124 @@
+use crate::api::SplunkClient;
+use crate::config::Config;
+use crate::models::splunk::TimeRange;
+use crate::spl::template;
+use crate::utils::saved_searches::{SavedSearchManager, TokenSpec};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Options for `spelunktui search`.
+pub struct SearchArgs {
+    pub query: Option<String>,
+    pub saved: Option<String>,
+    pub vars: Vec<String>,
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+    pub count: u32,
+}
+
+/// Values for the query's tokens: `--var` first, then the saved search's
+/// defaults. Every value is checked against its token's pattern.
+fn resolve_tokens(
+    query: &str,
+    vars: &[String],
+    specs: &BTreeMap<String, TokenSpec>,
+) -> Result<BTreeMap<String, String>, String> {
+    let mut given = BTreeMap::new();
+    for var in vars {
+        let (name, value) = var
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid --var '{}', expected name=value", var))?;
+        given.insert(name.trim().to_string(), value.to_string());
+    }
+
+    let mut values = BTreeMap::new();
+    for name in template::tokens(query) {
+        let spec = specs.get(&name).cloned().unwrap_or_default();
+        let value = given
+            .remove(&name)
+            .or(spec.default)
+            .ok_or_else(|| format!("Missing value for ${}$; pass --var {}=...", name, name))?;
+        template::validate(&name, &value, spec.pattern.as_deref())?;
+        values.insert(name, value);
+    }
+    if let Some(name) = given.keys().next() {
+        return Err(format!("Query has no ${}$ token", name));
+    }
+    Ok(values)
+}
+
+/// CLI entry point: runs the search and prints each result as a JSON line.
+pub async fn run(args: SearchArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = Config::load()?;
+    config.validate()?;
+
+    let (query, specs, mut range) = match (&args.query, &args.saved) {
+        (Some(query), None) => (query.clone(), BTreeMap::new(), TimeRange::default()),
+        (None, Some(name)) => {
+            let saved = SavedSearchManager::load(name)
+                .map_err(|e| format!("Failed to load saved search '{}': {}", name, e))?;
+            let range = TimeRange {
+                earliest: saved.meta.earliest,
+                latest: saved.meta.latest,
+            };
+            (saved.query, saved.meta.tokens, range)
+        }
+        _ => return Err("Pass either a query or --saved NAME.".into()),
+    };
+    if args.earliest.is_some() {
+        range.earliest = args.earliest;
+    }
+    if args.latest.is_some() {
+        range.latest = args.latest;
+    }
+
+    let values = resolve_tokens(&query, &args.vars, &specs)?;
+    let query = template::substitute(&query, &values)?;
+
+    let client = SplunkClient::new(
+        config.splunk_base_url.clone(),
+        config.splunk_token.clone(),
+        config.splunk_verify_ssl,
+    )
+    .with_namespace(config.namespace())
+    .with_read_only(config.read_only);
+
+    for result in client.run_search(&query, &range, args.count).await? {
+        println!("{}", result);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tokens() {
+        let specs: BTreeMap<String, TokenSpec> = [(
+            "user".to_string(),
+            TokenSpec {
+                default: Some("root".to_string()),
+                pattern: Some(r"\w+".to_string()),
+            },
+        )]
+        .into_iter()
+        .collect();
+        let query = "index=edr host=$host$ user=$user$";
+
+        let values = resolve_tokens(query, &["host=web01".to_string()], &specs).unwrap();
+        assert_eq!(values["host"], "web01");
+        assert_eq!(values["user"], "root");
+
+        assert!(resolve_tokens(query, &[], &specs).is_err());
+        assert!(resolve_tokens(
+            query,
+            &["host=a".to_string(), "user=bad user".to_string()],
+            &specs
+        )
+        .is_err());
+        assert!(
+            resolve_tokens(query, &["host=a".to_string(), "typo=b".to_string()], &specs).is_err()
+        );
+    }
+}
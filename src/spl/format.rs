@@ -0,0 +1,119 @@
+//! SPL pretty-printer. Puts each pipeline stage on its own line, indents
+//! subsearches and normalizes spacing, while copying strings, macros and
+//! comments through untouched.
+
+use super::lexer::{tokenize, TokenKind};
+
+const INDENT: &str = "    ";
+
+pub fn format(src: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    // Whitespace seen in the source since the previous token.
+    let mut space = false;
+    let mut prev: Option<(TokenKind, &str)> = None;
+
+    for token in tokenize(src) {
+        match token.kind {
+            TokenKind::Whitespace => {
+                space = true;
+                continue;
+            }
+            TokenKind::Pipe => {
+                if !at_line_start(&out) {
+                    newline(&mut out, depth);
+                }
+                out.push('|');
+            }
+            TokenKind::LBracket => {
+                if !at_line_start(&out) {
+                    out.push(' ');
+                }
+                out.push('[');
+                depth += 1;
+                newline(&mut out, depth);
+            }
+            TokenKind::RBracket => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(']');
+            }
+            kind => {
+                if !at_line_start(&out) && needs_space(prev, (kind, token.text), space) {
+                    out.push(' ');
+                }
+                if kind == TokenKind::Boolean {
+                    out.push_str(&token.text.to_ascii_uppercase());
+                } else {
+                    out.push_str(token.text);
+                }
+            }
+        }
+        prev = Some((token.kind, token.text));
+        space = false;
+    }
+
+    out.trim_end().to_string()
+}
+
+fn at_line_start(out: &str) -> bool {
+    let trimmed = out.trim_end_matches(' ');
+    trimmed.is_empty() || trimmed.ends_with('\n')
+}
+
+/// Starts a new line at `depth`, replacing any trailing spaces or an empty
+/// indented line already in `out`.
+fn newline(out: &mut String, depth: usize) {
+    out.truncate(out.trim_end_matches(' ').len());
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&INDENT.repeat(depth));
+}
+
+fn is_assignment(kind: TokenKind, text: &str) -> bool {
+    kind == TokenKind::Operator && matches!(text, "=" | "==" | "!=")
+}
+
+fn needs_space(prev: Option<(TokenKind, &str)>, next: (TokenKind, &str), space: bool) -> bool {
+    let Some((prev_kind, prev_text)) = prev else {
+        return false;
+    };
+    if matches!(prev_kind, TokenKind::Pipe | TokenKind::Comma) {
+        return true;
+    }
+    if next.0 == TokenKind::Comma
+        || is_assignment(prev_kind, prev_text)
+        || is_assignment(next.0, next.1)
+    {
+        return false;
+    }
+    space
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_pipeline() {
+        let src = r#"index = main  sourcetype="a | b"  error or warn | join host [search index=cmdb | fields host ,owner] ```keep  this``` | stats count by host,owner | where count > 5"#;
+        let expected = r#"index=main sourcetype="a | b" error OR warn
+| join host [
+    search index=cmdb
+    | fields host, owner
+] ```keep  this```
+| stats count by host, owner
+| where count > 5"#;
+        assert_eq!(format(src), expected);
+        assert_eq!(format(expected), expected);
+    }
+
+    #[test]
+    fn test_format_leading_pipe() {
+        assert_eq!(
+            format("  | tstats count where index=* by sourcetype|sort - count "),
+            "| tstats count where index=* by sourcetype\n| sort - count"
+        );
+    }
+}
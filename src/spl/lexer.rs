@@ -0,0 +1,231 @@
+//! A forgiving SPL tokenizer. It never fails: anything it doesn't recognise
+//! becomes a `Word`, so it is safe to run on half-typed queries.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    Pipe,
+    /// The command name of a pipeline stage (first word after `|` or `[`).
+    Command,
+    /// Clause keywords such as `by`, `as`, `over`.
+    Keyword,
+    /// `AND`, `OR`, `NOT` (in any case).
+    Boolean,
+    /// A word directly followed by a comparison operator.
+    Field,
+    Operator,
+    String,
+    Number,
+    /// A single-backtick macro call, e.g. `` `get_index(edr)` ``.
+    Macro,
+    /// A triple-backtick comment.
+    Comment,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Comma,
+    Word,
+    Whitespace,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the token in the source.
+    pub start: usize,
+}
+
+const KEYWORDS: &[&str] = &["by", "as", "over", "output", "outputnew", "in", "like"];
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace()
+        && !matches!(
+            c,
+            '|' | '[' | ']' | '(' | ')' | '=' | ',' | '"' | '`' | '<' | '>' | '!'
+        )
+}
+
+pub fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let bytes = src.as_bytes();
+    let mut i = 0;
+    // True when the next word starts a new pipeline stage.
+    let mut expect_command = false;
+
+    while i < src.len() {
+        let rest = &src[i..];
+        let c = rest.chars().next().unwrap();
+        let start = i;
+
+        let kind = if c.is_whitespace() {
+            i += rest
+                .find(|ch: char| !ch.is_whitespace())
+                .unwrap_or(rest.len());
+            TokenKind::Whitespace
+        } else if let Some(body) = rest.strip_prefix("```") {
+            i += body.find("```").map(|end| end + 6).unwrap_or(rest.len());
+            TokenKind::Comment
+        } else if c == '`' {
+            i += rest[1..].find('`').map(|end| end + 2).unwrap_or(rest.len());
+            TokenKind::Macro
+        } else if c == '"' {
+            let mut j = 1;
+            let mut escaped = false;
+            while j < rest.len() {
+                let b = bytes[i + j];
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    j += 1;
+                    break;
+                }
+                j += 1;
+            }
+            // Never split a multi-byte character at an unterminated string's end.
+            while !src.is_char_boundary(i + j.min(rest.len())) {
+                j += 1;
+            }
+            i += j.min(rest.len());
+            TokenKind::String
+        } else if c == '|' {
+            i += 1;
+            expect_command = true;
+            tokens.push(Token {
+                kind: TokenKind::Pipe,
+                text: &src[start..i],
+                start,
+            });
+            continue;
+        } else if c == '[' {
+            i += 1;
+            expect_command = true;
+            tokens.push(Token {
+                kind: TokenKind::LBracket,
+                text: &src[start..i],
+                start,
+            });
+            continue;
+        } else if c == ']' {
+            i += 1;
+            TokenKind::RBracket
+        } else if c == '(' {
+            i += 1;
+            TokenKind::LParen
+        } else if c == ')' {
+            i += 1;
+            TokenKind::RParen
+        } else if c == ',' {
+            i += 1;
+            TokenKind::Comma
+        } else if matches!(c, '=' | '<' | '>' | '!') {
+            i += if rest.starts_with("==")
+                || rest.starts_with("!=")
+                || rest.starts_with("<=")
+                || rest.starts_with(">=")
+            {
+                2
+            } else {
+                1
+            };
+            TokenKind::Operator
+        } else {
+            let len = rest
+                .find(|ch: char| !is_word_char(ch))
+                .unwrap_or(rest.len());
+            i += len;
+            let word = &src[start..i];
+            let next = &src[i..];
+            if expect_command {
+                TokenKind::Command
+            } else if matches!(word.to_ascii_uppercase().as_str(), "AND" | "OR" | "NOT") {
+                TokenKind::Boolean
+            } else if KEYWORDS.contains(&word.to_ascii_lowercase().as_str()) {
+                TokenKind::Keyword
+            } else if next.starts_with('=')
+                || next.starts_with("!=")
+                || next.starts_with('<')
+                || next.starts_with('>')
+            {
+                TokenKind::Field
+            } else if word.parse::<f64>().is_ok() {
+                TokenKind::Number
+            } else {
+                TokenKind::Word
+            }
+        };
+
+        if kind != TokenKind::Whitespace && kind != TokenKind::Comment {
+            expect_command = false;
+        }
+        tokens.push(Token {
+            kind,
+            text: &src[start..i],
+            start,
+        });
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(src)
+            .into_iter()
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_pipeline() {
+        assert_eq!(
+            kinds(r#"index=main "a \"b\"" | stats count by host"#),
+            vec![
+                (TokenKind::Field, "index"),
+                (TokenKind::Operator, "="),
+                (TokenKind::Word, "main"),
+                (TokenKind::String, r#""a \"b\"""#),
+                (TokenKind::Pipe, "|"),
+                (TokenKind::Command, "stats"),
+                (TokenKind::Word, "count"),
+                (TokenKind::Keyword, "by"),
+                (TokenKind::Word, "host"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_subsearch_macro_and_comment() {
+        assert_eq!(
+            kinds("`edr` NOT [search x=1] ```note``` | head 5"),
+            vec![
+                (TokenKind::Macro, "`edr`"),
+                (TokenKind::Boolean, "NOT"),
+                (TokenKind::LBracket, "["),
+                (TokenKind::Command, "search"),
+                (TokenKind::Field, "x"),
+                (TokenKind::Operator, "="),
+                (TokenKind::Number, "1"),
+                (TokenKind::RBracket, "]"),
+                (TokenKind::Comment, "```note```"),
+                (TokenKind::Pipe, "|"),
+                (TokenKind::Command, "head"),
+                (TokenKind::Number, "5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_input() {
+        let tokens = tokenize("search \"unterminated é");
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::String);
+        let rebuilt: String = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(rebuilt, "search \"unterminated é");
+    }
+}
@@ -0,0 +1,278 @@
+//! Warnings for costly or risky SPL, checked before a search is dispatched.
+
+use super::lexer::TokenKind;
+use super::pipeline::{self, Stage};
+use crate::models::splunk::TimeRange;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rule {
+    MissingIndex,
+    WildcardIndex,
+    LeadingWildcard,
+    UnboundedJoin,
+    Delete,
+    Write,
+    AllTime,
+    SearchAfterTransform,
+}
+
+impl Rule {
+    /// Identifier used in the `lint_disable` config list.
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::MissingIndex => "missing-index",
+            Rule::WildcardIndex => "wildcard-index",
+            Rule::LeadingWildcard => "leading-wildcard",
+            Rule::UnboundedJoin => "unbounded-join",
+            Rule::Delete => "delete",
+            Rule::Write => "write",
+            Rule::AllTime => "all-time",
+            Rule::SearchAfterTransform => "search-after-transform",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub rule: Rule,
+    pub message: String,
+}
+
+/// Commands that turn events into a results table.
+const TRANSFORMING: &[&str] = &[
+    "stats",
+    "chart",
+    "timechart",
+    "top",
+    "rare",
+    "contingency",
+    "xyseries",
+    "tstats",
+    "mstats",
+];
+
+/// Generating commands that don't read events by time, so an all-time
+/// range costs nothing.
+const UNTIMED: &[&str] = &["makeresults", "inputlookup", "inputcsv", "rest", "metadata"];
+
+pub fn lint(src: &str, range: &TimeRange, disabled: &[String]) -> Vec<Finding> {
+    let stages = pipeline::parse(src);
+    let mut findings = Vec::new();
+    let mut add = |rule: Rule, message: String| {
+        if !disabled.iter().any(|d| d == rule.id()) {
+            findings.push(Finding { rule, message });
+        }
+    };
+
+    let bounded = range.earliest.is_some()
+        || stages
+            .iter()
+            .any(|s| s.depth == 0 && s.command == "search" && s.option("earliest").is_some());
+    let untimed = stages
+        .iter()
+        .find(|s| s.depth == 0)
+        .is_some_and(|s| s.position == 0 && UNTIMED.contains(&s.command.as_str()));
+    if !bounded && !untimed {
+        add(
+            Rule::AllTime,
+            "Time range is all time; set one or add earliest=".to_string(),
+        );
+    }
+
+    // Most recent transforming command of the pipeline open at each depth.
+    let mut transformed: Vec<Option<&str>> = Vec::new();
+    for stage in &stages {
+        transformed.resize(stage.depth + 1, None);
+        if stage.position == 0 {
+            transformed[stage.depth] = None;
+        }
+        let command = stage.command.as_str();
+        match command {
+            "search" => {
+                if stage.position == 0 {
+                    check_index(stage, &mut add);
+                } else if let Some(previous) = transformed[stage.depth] {
+                    add(
+                        Rule::SearchAfterTransform,
+                        format!(
+                            "| search after | {} filters results late; filter in the base search or use | where",
+                            previous
+                        ),
+                    );
+                }
+                for token in &stage.args {
+                    let term = token.text.trim_matches('"');
+                    if matches!(token.kind, TokenKind::Word | TokenKind::String)
+                        && term.len() > 1
+                        && term.starts_with('*')
+                    {
+                        add(
+                            Rule::LeadingWildcard,
+                            format!("Leading wildcard in {} can't use the index", token.text),
+                        );
+                    }
+                }
+            }
+            // `| tstats ... where index=*`; no index is normal for data models.
+            "tstats" | "mstats" if stage.position == 0 => check_wildcard_index(stage, &mut add),
+            "join" if !bounded => add(
+                Rule::UnboundedJoin,
+                "| join over all time; bound the search first".to_string(),
+            ),
+            "transaction" => {
+                let limited = ["maxspan", "maxpause", "maxevents"]
+                    .iter()
+                    .any(|o| stage.option(o).is_some());
+                if !bounded || !limited {
+                    add(
+                        Rule::UnboundedJoin,
+                        "| transaction without a time range or maxspan/maxpause holds every open transaction in memory".to_string(),
+                    );
+                }
+            }
+            "delete" => add(
+                Rule::Delete,
+                "| delete permanently hides events from search".to_string(),
+            ),
+            "outputlookup" => add(
+                Rule::Write,
+                "| outputlookup overwrites a lookup".to_string(),
+            ),
+            "collect" => add(
+                Rule::Write,
+                "| collect writes events into a summary index".to_string(),
+            ),
+            _ => {}
+        }
+        if TRANSFORMING.contains(&command) {
+            transformed[stage.depth] = Some(stage.command.as_str());
+        }
+    }
+
+    findings
+}
+
+fn check_index(stage: &Stage, add: &mut impl FnMut(Rule, String)) {
+    // A macro or saved search may well supply the index.
+    if stage.args.iter().any(|t| t.kind == TokenKind::Macro) {
+        return;
+    }
+    match stage.option("index") {
+        Some(_) => check_wildcard_index(stage, add),
+        // `index IN (...)` has no `=` but still names indexes.
+        None if stage.args.windows(2).any(|pair| {
+            pair[0].text.eq_ignore_ascii_case("index") && pair[1].text.eq_ignore_ascii_case("in")
+        }) => {}
+        None => add(
+            Rule::MissingIndex,
+            "No index= in the base search; it will scan your default indexes".to_string(),
+        ),
+    }
+}
+
+fn check_wildcard_index(stage: &Stage, add: &mut impl FnMut(Rule, String)) {
+    if stage
+        .option("index")
+        .is_some_and(|value| value.trim_matches('"') == "*")
+    {
+        add(
+            Rule::WildcardIndex,
+            "index=* searches every index you can read".to_string(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(src: &str, earliest: Option<&str>, disabled: &[&str]) -> Vec<&'static str> {
+        let range = TimeRange {
+            earliest: earliest.map(String::from),
+            latest: None,
+        };
+        let disabled: Vec<String> = disabled.iter().map(|s| s.to_string()).collect();
+        lint(src, &range, &disabled)
+            .into_iter()
+            .map(|f| f.rule.id())
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        assert!(rules(
+            "index=web status=500 | stats count by host",
+            Some("-24h"),
+            &[]
+        )
+        .is_empty());
+        assert_eq!(
+            rules("error | head 5", Some("-1h"), &[]),
+            vec!["missing-index"]
+        );
+        assert_eq!(
+            rules("index=* *fail earliest=-1h", None, &[]),
+            vec!["wildcard-index", "leading-wildcard"]
+        );
+        assert_eq!(
+            rules(
+                "index=web | join host [search index=cmdb] | delete",
+                None,
+                &[]
+            ),
+            vec!["all-time", "unbounded-join", "delete"]
+        );
+        assert_eq!(
+            rules(
+                "index=web | stats count by host | search count>5 | outputlookup x.csv",
+                Some("-1d"),
+                &[]
+            ),
+            vec!["search-after-transform", "write"]
+        );
+        assert_eq!(
+            rules(
+                "| inputlookup x | transaction host maxspan=5m",
+                Some("-1d"),
+                &[]
+            ),
+            Vec::<&str>::new()
+        );
+        assert!(rules("error", None, &["missing-index", "all-time"]).is_empty());
+        assert_eq!(
+            rules("| tstats count where index=* by host", None, &[]),
+            vec!["all-time", "wildcard-index"]
+        );
+        assert!(rules("| tstats count where index=web by host", Some("-1h"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_untimed_generating_commands() {
+        for src in [
+            "| makeresults count=5",
+            "| inputlookup assets.csv | stats count by owner",
+            "| rest /services/server/info",
+            "| metadata type=hosts index=web",
+        ] {
+            assert!(rules(src, None, &[]).is_empty(), "{}", src);
+        }
+        // Only the first stage counts; a later index search is still timed.
+        assert_eq!(
+            rules("index=web | append [| makeresults]", None, &[]),
+            vec!["all-time"]
+        );
+    }
+
+    #[test]
+    fn test_index_in() {
+        assert!(rules("index IN (web, auth) error", Some("-1h"), &[]).is_empty());
+        assert_eq!(
+            rules("search index", Some("-1h"), &[]),
+            vec!["missing-index"]
+        );
+        assert_eq!(
+            rules("foo=index error", Some("-1h"), &[]),
+            vec!["missing-index"]
+        );
+    }
+}
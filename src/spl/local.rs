@@ -0,0 +1,1153 @@
+//! A subset of SPL run over results that are already loaded, so they can be
+//! filtered and reshaped without dispatching a new job: `where`, `search`,
+//! `eval`, `fields`, `sort`, `head`, `dedup` and `stats`.
+
+use super::lexer::{Token, TokenKind};
+use super::pipeline::{self, Stage};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+pub const COMMANDS: &[&str] = &[
+    "where", "search", "eval", "fields", "sort", "head", "dedup", "stats",
+];
+
+/// `src` as stages to append to a query, starting with `|`. Like SPL, a
+/// pipeline that doesn't start with `|` or one of the commands begins with
+/// `search` terms.
+pub fn to_spl(src: &str) -> String {
+    let src = src.trim();
+    let first = src
+        .split(|c: char| c.is_whitespace() || c == '=')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if src.starts_with('|') {
+        src.to_string()
+    } else if COMMANDS.contains(&first.as_str()) {
+        format!("| {}", src)
+    } else {
+        format!("| search {}", src)
+    }
+}
+
+/// Runs the pipeline `src` over `results`.
+pub fn run(src: &str, results: &[Value]) -> Result<Vec<Value>, String> {
+    // Commands are only recognized after a pipe.
+    let src = to_spl(src);
+
+    let mut rows = results.to_vec();
+    for stage in pipeline::parse(&src) {
+        if stage.depth > 0 {
+            return Err("Subsearches can't run locally.".to_string());
+        }
+        rows = match stage.command.as_str() {
+            "where" => {
+                let expr = Expr::parse(stage_text(&src, &stage))?;
+                rows.into_iter()
+                    .filter(|row| expr.eval(row).truthy())
+                    .collect()
+            }
+            "search" => {
+                let mut tokens = stage.args.as_slice();
+                let search = Search::parse(&mut tokens)?;
+                if let Some(token) = tokens.first() {
+                    return Err(format!("Unexpected '{}' in search", token.text));
+                }
+                match search {
+                    Some(search) => rows.into_iter().filter(|row| search.matches(row)).collect(),
+                    None => rows,
+                }
+            }
+            "eval" => eval(stage_text(&src, &stage), rows)?,
+            "fields" => fields(&stage.args, rows),
+            "sort" => sort(&stage.args, rows)?,
+            "head" => {
+                let limit = match stage.args.first() {
+                    None => 10,
+                    Some(_) => stage
+                        .option("limit")
+                        .or(stage.args.first().map(|t| t.text))
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("head takes a number of results")?,
+                };
+                rows.truncate(limit);
+                rows
+            }
+            "dedup" => dedup(&stage.args, rows)?,
+            "stats" => stats(&stage.args, &rows)?,
+            other => {
+                return Err(format!(
+                    "'{}' can't run locally; use {}",
+                    other,
+                    COMMANDS.join(", ")
+                ))
+            }
+        };
+    }
+    Ok(rows)
+}
+
+/// Source text of a stage's arguments.
+fn stage_text<'a>(src: &'a str, stage: &Stage) -> &'a str {
+    match (stage.args.first(), stage.args.last()) {
+        (Some(first), Some(last)) => &src[first.start..last.start + last.text.len()],
+        _ => "",
+    }
+}
+
+/// A field value as eval sees it.
+#[derive(Clone, Debug, PartialEq)]
+enum Val {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+}
+
+impl Val {
+    fn from_json(value: Option<&Value>) -> Val {
+        match value {
+            None | Some(Value::Null) => Val::Null,
+            Some(Value::Bool(b)) => Val::Bool(*b),
+            Some(Value::Number(n)) => n.as_f64().map_or(Val::Null, Val::Num),
+            Some(Value::String(s)) => Val::Str(s.clone()),
+            // Multivalue fields compare on their first value.
+            Some(Value::Array(items)) => Val::from_json(items.first()),
+            Some(v) => Val::Str(v.to_string()),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Val::Null => Value::Null,
+            Val::Bool(b) => Value::Bool(*b),
+            Val::Num(n) => number(*n),
+            Val::Str(s) => Value::String(s.clone()),
+        }
+    }
+
+    fn num(&self) -> Option<f64> {
+        match self {
+            Val::Num(n) => Some(*n),
+            Val::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Val::Null => String::new(),
+            Val::Bool(b) => b.to_string(),
+            Val::Num(n) => match number(*n) {
+                Value::Number(n) => n.to_string(),
+                _ => n.to_string(),
+            },
+            Val::Str(s) => s.clone(),
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Val::Null => false,
+            Val::Bool(b) => *b,
+            Val::Num(n) => *n != 0.0,
+            Val::Str(s) => !s.is_empty(),
+        }
+    }
+}
+
+/// Whole numbers as integers so they print without a trailing `.0`.
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+/// Numeric order when both sides are numbers, otherwise text order.
+fn compare(a: &Val, b: &Val) -> Option<Ordering> {
+    if matches!(a, Val::Null) || matches!(b, Val::Null) {
+        return None;
+    }
+    match (a.num(), b.num()) {
+        (Some(x), Some(y)) => x.partial_cmp(&y),
+        _ => Some(a.text().cmp(&b.text())),
+    }
+}
+
+fn compare_op(op: &str, ordering: Option<Ordering>) -> bool {
+    let Some(ordering) = ordering else {
+        return op == "!=";
+    };
+    match op {
+        "=" | "==" => ordering == Ordering::Equal,
+        "!=" => ordering != Ordering::Equal,
+        "<" => ordering == Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        ">=" => ordering != Ordering::Less,
+        _ => false,
+    }
+}
+
+/// A case-insensitive regex for a `*` wildcard pattern.
+fn wildcard(pattern: &str, anchored: bool) -> regex::Regex {
+    let body = regex::escape(pattern).replace(r"\*", ".*");
+    let body = if anchored {
+        format!("^(?:{})$", body)
+    } else {
+        body
+    };
+    regex::RegexBuilder::new(&body)
+        .case_insensitive(true)
+        .build()
+        .expect("escaped pattern is valid")
+}
+
+// --- eval expressions ---------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "=", "<", ">", "+", "-", "*", "/", "%", ".",
+];
+
+fn lex(text: &str) -> Result<Vec<Tok>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            // Double quotes are strings, single quotes are field names.
+            chars.next();
+            let mut value = String::new();
+            let mut closed = false;
+            while let Some((_, ch)) = chars.next() {
+                match ch {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    _ if ch == c => {
+                        closed = true;
+                        break;
+                    }
+                    _ => value.push(ch),
+                }
+            }
+            if !closed {
+                return Err("Unterminated quote".to_string());
+            }
+            tokens.push(if c == '"' {
+                Tok::Str(value)
+            } else {
+                Tok::Ident(value)
+            });
+        } else if c.is_ascii_digit() {
+            let rest = &text[i..];
+            let len = rest
+                .find(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+                .unwrap_or(rest.len());
+            let n = rest[..len]
+                .parse()
+                .map_err(|_| format!("Invalid number '{}'", &rest[..len]))?;
+            tokens.push(Tok::Num(n));
+            for _ in 0..len {
+                chars.next();
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let rest = &text[i..];
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Tok::Ident(rest[..len].to_string()));
+            for _ in 0..rest[..len].chars().count() {
+                chars.next();
+            }
+        } else if c == '(' || c == ')' || c == ',' {
+            chars.next();
+            tokens.push(match c {
+                '(' => Tok::LParen,
+                ')' => Tok::RParen,
+                _ => Tok::Comma,
+            });
+        } else {
+            let rest = &text[i..];
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("Unexpected '{}'", c))?;
+            tokens.push(Tok::Op(op));
+            for _ in 0..op.len() {
+                chars.next();
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expr {
+    Lit(Val),
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Ident(w)) if w.eq_ignore_ascii_case(word)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Tok::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.keyword("OR") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.not()?;
+        while self.keyword("AND") {
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.additive()?;
+        match self.op(&["==", "!=", "<=", ">=", "=", "<", ">"]) {
+            Some(op) => Ok(Expr::Binary(Box::new(left), op, Box::new(self.additive()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.multiplicative()?;
+        while let Some(op) = self.op(&["+", "-", "."]) {
+            left = Expr::Binary(Box::new(left), op, Box::new(self.multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.op(&["*", "/", "%"]) {
+            left = Expr::Binary(Box::new(left), op, Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.op(&["-"]).is_some() {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        match self.next() {
+            Some(Tok::Num(n)) => Ok(Expr::Lit(Val::Num(n))),
+            Some(Tok::Str(s)) => Ok(Expr::Lit(Val::Str(s))),
+            Some(Tok::LParen) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Tok::RParen) => Ok(inner),
+                    _ => Err("Missing ')'".to_string()),
+                }
+            }
+            Some(Tok::Ident(name)) => {
+                if self.peek() != Some(&Tok::LParen) {
+                    return Ok(Expr::Field(name));
+                }
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() == Some(&Tok::RParen) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        args.push(self.or()?);
+                        match self.next() {
+                            Some(Tok::Comma) => continue,
+                            Some(Tok::RParen) => break,
+                            _ => return Err(format!("Missing ')' after {}(", name)),
+                        }
+                    }
+                }
+                Ok(Expr::Call(name.to_ascii_lowercase(), args))
+            }
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err("Expression ends too early".to_string()),
+        }
+    }
+}
+
+impl Expr {
+    fn parse(text: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            tokens: lex(text)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+        }
+    }
+
+    fn eval(&self, row: &Value) -> Val {
+        match self {
+            Expr::Lit(v) => v.clone(),
+            Expr::Field(name) => Val::from_json(row.get(name)),
+            Expr::Not(e) => Val::Bool(!e.eval(row).truthy()),
+            Expr::Neg(e) => e.eval(row).num().map_or(Val::Null, |n| Val::Num(-n)),
+            Expr::And(a, b) => Val::Bool(a.eval(row).truthy() && b.eval(row).truthy()),
+            Expr::Or(a, b) => Val::Bool(a.eval(row).truthy() || b.eval(row).truthy()),
+            Expr::Binary(a, op, b) => {
+                let (a, b) = (a.eval(row), b.eval(row));
+                match *op {
+                    "." => Val::Str(a.text() + &b.text()),
+                    "+" => match (a.num(), b.num()) {
+                        (Some(x), Some(y)) => Val::Num(x + y),
+                        _ if a == Val::Null || b == Val::Null => Val::Null,
+                        _ => Val::Str(a.text() + &b.text()),
+                    },
+                    "-" | "*" | "/" | "%" => match (a.num(), b.num()) {
+                        (Some(x), Some(y)) => match *op {
+                            "-" => Val::Num(x - y),
+                            "*" => Val::Num(x * y),
+                            _ if y == 0.0 => Val::Null,
+                            "/" => Val::Num(x / y),
+                            _ => Val::Num(x % y),
+                        },
+                        _ => Val::Null,
+                    },
+                    op => Val::Bool(compare_op(op, compare(&a, &b))),
+                }
+            }
+            Expr::Call(name, args) => call(name, args, row),
+        }
+    }
+}
+
+fn call(name: &str, args: &[Expr], row: &Value) -> Val {
+    let arg = |i: usize| args.get(i).map_or(Val::Null, |e| e.eval(row));
+    let text = |i: usize| arg(i).text();
+    match name {
+        "if" => {
+            if arg(0).truthy() {
+                arg(1)
+            } else {
+                arg(2)
+            }
+        }
+        "coalesce" => args
+            .iter()
+            .map(|e| e.eval(row))
+            .find(|v| *v != Val::Null)
+            .unwrap_or(Val::Null),
+        "isnull" => Val::Bool(arg(0) == Val::Null),
+        "isnotnull" => Val::Bool(arg(0) != Val::Null),
+        "isnum" => Val::Bool(arg(0).num().is_some()),
+        "null" => Val::Null,
+        "true" => Val::Bool(true),
+        "false" => Val::Bool(false),
+        "len" => Val::Num(text(0).chars().count() as f64),
+        "lower" => Val::Str(text(0).to_lowercase()),
+        "upper" => Val::Str(text(0).to_uppercase()),
+        "trim" => Val::Str(text(0).trim().to_string()),
+        "tostring" => Val::Str(text(0)),
+        "tonumber" => arg(0).num().map_or(Val::Null, Val::Num),
+        "abs" => arg(0).num().map_or(Val::Null, |n| Val::Num(n.abs())),
+        "round" => match arg(0).num() {
+            Some(n) => {
+                let scale = 10f64.powi(arg(1).num().unwrap_or(0.0) as i32);
+                Val::Num((n * scale).round() / scale)
+            }
+            None => Val::Null,
+        },
+        "substr" => {
+            // 1-based start, negative counts from the end.
+            let chars: Vec<char> = text(0).chars().collect();
+            let start = arg(1).num().unwrap_or(1.0) as i64;
+            let start = if start < 0 {
+                (chars.len() as i64 + start).max(0) as usize
+            } else {
+                (start.max(1) - 1) as usize
+            };
+            let len = arg(2).num().map_or(chars.len(), |n| n.max(0.0) as usize);
+            Val::Str(chars.iter().skip(start).take(len).collect())
+        }
+        "like" => {
+            let pattern = regex::escape(&text(1)).replace('%', ".*").replace('_', ".");
+            Val::Bool(
+                regex::Regex::new(&format!("^(?s:{})$", pattern))
+                    .is_ok_and(|re| re.is_match(&text(0))),
+            )
+        }
+        "match" => Val::Bool(regex::Regex::new(&text(1)).is_ok_and(|re| re.is_match(&text(0)))),
+        "replace" => match regex::Regex::new(&text(1)) {
+            Ok(re) => Val::Str(re.replace_all(&text(0), text(2).as_str()).into_owned()),
+            Err(_) => Val::Null,
+        },
+        _ => Val::Null,
+    }
+}
+
+fn eval(text: &str, rows: Vec<Value>) -> Result<Vec<Value>, String> {
+    let mut parser = Parser {
+        tokens: lex(text)?,
+        pos: 0,
+    };
+    let mut assignments = Vec::new();
+    loop {
+        let Some(Tok::Ident(field)) = parser.next() else {
+            return Err("eval expects field=expression".to_string());
+        };
+        if parser.op(&["="]).is_none() {
+            return Err(format!("eval expects '=' after {}", field));
+        }
+        assignments.push((field, parser.or()?));
+        match parser.next() {
+            Some(Tok::Comma) => continue,
+            None => break,
+            Some(token) => return Err(format!("Unexpected {:?}", token)),
+        }
+    }
+    Ok(rows
+        .into_iter()
+        .map(|mut row| {
+            // Later assignments see earlier ones, as in SPL.
+            for (field, expr) in &assignments {
+                let value = expr.eval(&row).to_json();
+                if let Some(object) = row.as_object_mut() {
+                    object.insert(field.clone(), value);
+                }
+            }
+            row
+        })
+        .collect())
+}
+
+// --- search terms -------------------------------------------------------
+
+/// A parsed search; wildcard patterns are compiled once, not per row.
+#[derive(Debug)]
+enum Search {
+    Field {
+        field: String,
+        op: String,
+        value: String,
+        pattern: regex::Regex,
+    },
+    Keyword(regex::Regex),
+    Not(Box<Search>),
+    And(Vec<Search>),
+    Or(Vec<Search>),
+}
+
+fn unquote(token: &Token) -> String {
+    if token.kind == TokenKind::String {
+        let inner = token.text.trim_start_matches('"').trim_end_matches('"');
+        inner.replace("\\\"", "\"").replace("\\\\", "\\")
+    } else {
+        token.text.to_string()
+    }
+}
+
+impl Search {
+    /// `a OR b`; returns `None` for an empty search.
+    fn parse(tokens: &mut &[Token]) -> Result<Option<Search>, String> {
+        let mut any = Vec::new();
+        loop {
+            let mut all = Vec::new();
+            while let Some(token) = tokens.first() {
+                match token.kind {
+                    TokenKind::RParen => break,
+                    TokenKind::Boolean if token.text.eq_ignore_ascii_case("OR") => break,
+                    TokenKind::Boolean if token.text.eq_ignore_ascii_case("AND") => {
+                        *tokens = &tokens[1..];
+                    }
+                    _ => all.push(Search::term(tokens)?),
+                }
+            }
+            if all.is_empty() {
+                if any.is_empty() && tokens.is_empty() {
+                    return Ok(None);
+                }
+                return Err("Empty search term".to_string());
+            }
+            any.push(if all.len() == 1 {
+                all.remove(0)
+            } else {
+                Search::And(all)
+            });
+            match tokens.first() {
+                Some(t) if t.kind == TokenKind::Boolean => *tokens = &tokens[1..],
+                _ => break,
+            }
+        }
+        Ok(Some(if any.len() == 1 {
+            any.remove(0)
+        } else {
+            Search::Or(any)
+        }))
+    }
+
+    fn term(tokens: &mut &[Token]) -> Result<Search, String> {
+        let Some((&token, rest)) = tokens.split_first() else {
+            return Err("Missing search term".to_string());
+        };
+        *tokens = rest;
+        match token.kind {
+            // `NOT NOT x` is fine; a trailing `NOT`, `NOT)` or `NOT OR x` isn't.
+            TokenKind::Boolean => match tokens.first() {
+                Some(next)
+                    if next.kind != TokenKind::RParen
+                        && (next.kind != TokenKind::Boolean
+                            || next.text.eq_ignore_ascii_case("NOT")) =>
+                {
+                    Ok(Search::Not(Box::new(Search::term(tokens)?)))
+                }
+                _ => Err(format!("{} needs a search term after it", token.text)),
+            },
+            TokenKind::LParen => {
+                let inner = Search::parse(tokens)?.ok_or("Empty parentheses")?;
+                match tokens.first() {
+                    Some(t) if t.kind == TokenKind::RParen => {
+                        *tokens = &tokens[1..];
+                        Ok(inner)
+                    }
+                    _ => Err("Missing ')'".to_string()),
+                }
+            }
+            TokenKind::Field => match tokens {
+                [op, value, rest @ ..] if op.kind == TokenKind::Operator => {
+                    let value = unquote(value);
+                    let term = Search::Field {
+                        field: token.text.to_string(),
+                        op: op.text.to_string(),
+                        pattern: wildcard(&value, true),
+                        value,
+                    };
+                    *tokens = rest;
+                    Ok(term)
+                }
+                _ => Err(format!("Missing value for {}", token.text)),
+            },
+            TokenKind::Word | TokenKind::String | TokenKind::Number | TokenKind::Keyword => {
+                Ok(Search::Keyword(wildcard(&unquote(&token), false)))
+            }
+            _ => Err(format!("'{}' can't be used in a local search", token.text)),
+        }
+    }
+
+    fn matches(&self, row: &Value) -> bool {
+        match self {
+            Search::Field {
+                field,
+                op,
+                value: expected,
+                pattern,
+            } => {
+                let value = Val::from_json(row.get(field));
+                match op.as_str() {
+                    "=" | "==" | "!=" => {
+                        let found = value != Val::Null && pattern.is_match(&value.text());
+                        // `field!=x` doesn't match events without the field.
+                        if op == "!=" {
+                            value != Val::Null && !found
+                        } else {
+                            found
+                        }
+                    }
+                    op => compare_op(op, compare(&value, &Val::Str(expected.clone()))),
+                }
+            }
+            Search::Keyword(re) => match row.get("_raw") {
+                Some(raw) => re.is_match(&Val::from_json(Some(raw)).text()),
+                None => row.as_object().is_some_and(|o| {
+                    o.values()
+                        .any(|v| re.is_match(&Val::from_json(Some(v)).text()))
+                }),
+            },
+            Search::Not(inner) => !inner.matches(row),
+            Search::And(terms) => terms.iter().all(|t| t.matches(row)),
+            Search::Or(terms) => terms.iter().any(|t| t.matches(row)),
+        }
+    }
+}
+
+// --- fields, sort, dedup, stats -----------------------------------------
+
+/// Names in a comma- or space-separated list of words.
+fn names<'a>(args: &[Token<'a>]) -> Vec<&'a str> {
+    args.iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Comma))
+        .map(|t| t.text)
+        .collect()
+}
+
+fn fields(args: &[Token], rows: Vec<Value>) -> Vec<Value> {
+    let mut list = names(args);
+    let remove = list.first() == Some(&"-");
+    if matches!(list.first(), Some(&"-") | Some(&"+")) {
+        list.remove(0);
+    }
+    let patterns: Vec<regex::Regex> = list.iter().map(|n| wildcard(n, true)).collect();
+    rows.into_iter()
+        .map(|row| {
+            let Value::Object(object) = row else {
+                return row;
+            };
+            let kept: Map<String, Value> = if remove {
+                object
+                    .into_iter()
+                    .filter(|(k, _)| !patterns.iter().any(|p| p.is_match(k)))
+                    .collect()
+            } else {
+                // Listed order, then `_time` and `_raw`, which SPL keeps too.
+                let mut kept = Map::new();
+                for p in &patterns {
+                    for (k, v) in &object {
+                        if p.is_match(k) && !kept.contains_key(k) {
+                            kept.insert(k.clone(), v.clone());
+                        }
+                    }
+                }
+                for internal in ["_time", "_raw"] {
+                    if let Some(v) = object.get(internal) {
+                        kept.entry(internal).or_insert_with(|| v.clone());
+                    }
+                }
+                kept
+            };
+            Value::Object(kept)
+        })
+        .collect()
+}
+
+fn sort(args: &[Token], mut rows: Vec<Value>) -> Result<Vec<Value>, String> {
+    let mut list = names(args);
+    let mut limit = None;
+    if let Some(n) = list.first().and_then(|n| n.parse::<usize>().ok()) {
+        limit = Some(n);
+        list.remove(0);
+    }
+    // `- count`, `-count` and `+host` all work.
+    let mut keys: Vec<(String, bool)> = Vec::new();
+    let mut descending = false;
+    for name in list {
+        match name {
+            "-" => descending = true,
+            "+" => descending = false,
+            _ => {
+                let (desc, field) = match name.strip_prefix('-') {
+                    Some(field) => (true, field),
+                    None => (descending, name.trim_start_matches('+')),
+                };
+                keys.push((field.to_string(), desc));
+                descending = false;
+            }
+        }
+    }
+    if keys.is_empty() {
+        return Err("sort needs at least one field".to_string());
+    }
+    rows.sort_by(|a, b| {
+        for (field, desc) in &keys {
+            let (x, y) = (Val::from_json(a.get(field)), Val::from_json(b.get(field)));
+            // Rows without the field go last either way.
+            let ordering = match (&x, &y) {
+                (Val::Null, Val::Null) => Ordering::Equal,
+                (Val::Null, _) => return Ordering::Greater,
+                (_, Val::Null) => return Ordering::Less,
+                _ => compare(&x, &y).unwrap_or(Ordering::Equal),
+            };
+            let ordering = if *desc { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+    if let Some(limit) = limit {
+        rows.truncate(limit);
+    }
+    Ok(rows)
+}
+
+fn dedup(args: &[Token], rows: Vec<Value>) -> Result<Vec<Value>, String> {
+    let mut list = names(args);
+    let mut keep = 1;
+    if let Some(n) = list.first().and_then(|n| n.parse::<usize>().ok()) {
+        keep = n.max(1);
+        list.remove(0);
+    }
+    if list.is_empty() {
+        return Err("dedup needs at least one field".to_string());
+    }
+    let mut seen: BTreeMap<Vec<String>, usize> = BTreeMap::new();
+    Ok(rows
+        .into_iter()
+        .filter(|row| {
+            let key: Option<Vec<String>> = list
+                .iter()
+                .map(|f| match Val::from_json(row.get(*f)) {
+                    Val::Null => None,
+                    v => Some(v.text()),
+                })
+                .collect();
+            // Rows missing any of the fields are dropped, as in SPL.
+            let Some(key) = key else {
+                return false;
+            };
+            let count = seen.entry(key).or_insert(0);
+            *count += 1;
+            *count <= keep
+        })
+        .collect())
+}
+
+struct Aggregate {
+    function: String,
+    field: Option<String>,
+    name: String,
+}
+
+fn stats(args: &[Token], rows: &[Value]) -> Result<Vec<Value>, String> {
+    let mut aggregates: Vec<Aggregate> = Vec::new();
+    let mut rest = args;
+    loop {
+        match rest {
+            [f, open, field, close, tail @ ..]
+                if f.kind == TokenKind::Word
+                    && open.kind == TokenKind::LParen
+                    && close.kind == TokenKind::RParen =>
+            {
+                aggregates.push(Aggregate {
+                    function: f.text.to_ascii_lowercase(),
+                    field: Some(field.text.to_string()),
+                    name: format!("{}({})", f.text, field.text),
+                });
+                rest = tail;
+            }
+            [f, tail @ ..] if f.kind == TokenKind::Word => {
+                aggregates.push(Aggregate {
+                    function: f.text.to_ascii_lowercase(),
+                    field: None,
+                    name: f.text.to_string(),
+                });
+                rest = tail;
+            }
+            _ => break,
+        }
+        if let [keyword, name, tail @ ..] = rest {
+            if keyword.kind == TokenKind::Keyword && keyword.text.eq_ignore_ascii_case("as") {
+                aggregates.last_mut().expect("just pushed").name = unquote(name);
+                rest = tail;
+            }
+        }
+        if let [comma, tail @ ..] = rest {
+            if comma.kind == TokenKind::Comma {
+                rest = tail;
+            }
+        }
+    }
+    let by: Vec<&str> = match rest {
+        [] => Vec::new(),
+        [keyword, tail @ ..]
+            if keyword.kind == TokenKind::Keyword && keyword.text.eq_ignore_ascii_case("by") =>
+        {
+            names(tail)
+        }
+        [token, ..] => return Err(format!("Unexpected '{}' in stats", token.text)),
+    };
+    if aggregates.is_empty() {
+        return Err("stats needs a function, e.g. count or avg(field)".to_string());
+    }
+    for a in &aggregates {
+        let known = matches!(
+            a.function.as_str(),
+            "count" | "dc" | "distinct_count" | "sum" | "avg" | "mean" | "min" | "max" | "values"
+        );
+        if !known || (a.field.is_none() && a.function != "count") {
+            return Err(format!("Unsupported stats function '{}'", a.name));
+        }
+    }
+
+    // Groups sorted by their by-field values, as SPL does.
+    let mut groups: BTreeMap<Vec<String>, Vec<&Value>> = BTreeMap::new();
+    for row in rows {
+        let key: Option<Vec<String>> = by
+            .iter()
+            .map(|f| match Val::from_json(row.get(*f)) {
+                Val::Null => None,
+                v => Some(v.text()),
+            })
+            .collect();
+        if let Some(key) = key {
+            groups.entry(key).or_default().push(row);
+        }
+    }
+    if by.is_empty() && groups.is_empty() {
+        groups.insert(Vec::new(), Vec::new());
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(key, members)| {
+            let mut out = Map::new();
+            for (field, value) in by.iter().zip(key) {
+                out.insert(field.to_string(), Value::String(value));
+            }
+            for a in &aggregates {
+                let values: Vec<Val> = match &a.field {
+                    Some(field) => members
+                        .iter()
+                        .map(|row| Val::from_json(row.get(field)))
+                        .filter(|v| *v != Val::Null)
+                        .collect(),
+                    None => Vec::new(),
+                };
+                let numbers: Vec<f64> = values.iter().filter_map(|v| v.num()).collect();
+                let mut distinct: Vec<String> = values.iter().map(|v| v.text()).collect();
+                distinct.sort();
+                distinct.dedup();
+                let value = match a.function.as_str() {
+                    "count" if a.field.is_none() => number(members.len() as f64),
+                    "count" => number(values.len() as f64),
+                    "dc" | "distinct_count" => number(distinct.len() as f64),
+                    "values" => Value::Array(distinct.into_iter().map(Value::String).collect()),
+                    _ if numbers.is_empty() => Value::Null,
+                    "sum" => number(numbers.iter().sum()),
+                    "avg" | "mean" => number(numbers.iter().sum::<f64>() / numbers.len() as f64),
+                    "min" => number(numbers.iter().copied().fold(f64::INFINITY, f64::min)),
+                    _ => number(numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+                };
+                out.insert(a.name.clone(), value);
+            }
+            Value::Object(out)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_run_pipeline() {
+        let results = rows();
+
+        let rows = run("host=web* NOT status=500", &results).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(run("search POST OR 404", &results).unwrap().len(), 2);
+        assert_eq!(to_spl("host=web*"), "| search host=web*");
+        assert_eq!(to_spl("head 5"), "| head 5");
+
+        let rows = run("| where status >= 400 AND like(host, \"web%\")", &results).unwrap();
+        assert_eq!(rows, vec![results[1].clone()]);
+
+        let rows = run(
+            "eval kb=round(bytes/1024, 1), label=host . \":\" . status | sort - kb | head 2 | fields label, kb",
+            &results,
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"label": "web01:200", "kb": 2, "_raw": "POST /a 200"}),
+                json!({"label": "web01:200", "kb": 0.5, "_raw": "GET /a 200"}),
+            ]
+        );
+
+        assert_eq!(run("dedup host", &results).unwrap().len(), 3);
+
+        let rows = run("stats count, sum(bytes) as total by host", &results).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"host": "db01", "count": 1, "total": null}),
+                json!({"host": "web01", "count": 2, "total": 2560}),
+                json!({"host": "web02", "count": 1, "total": 128}),
+            ]
+        );
+        assert_eq!(
+            run("stats dc(host)", &results).unwrap(),
+            vec![json!({"dc(host)": 3})]
+        );
+
+        assert!(run("| rex field=_raw \"(?<x>.)\"", &results).is_err());
+        assert!(run("where (status", &results).is_err());
+    }
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"_raw": "GET /a 200", "host": "web01", "status": "200", "bytes": "512"}),
+            json!({"_raw": "GET /b 500", "host": "web02", "status": "500", "bytes": "128"}),
+            json!({"_raw": "POST /a 200", "host": "web01", "status": "200", "bytes": "2048"}),
+            json!({"_raw": "GET /c 404", "host": "db01", "status": "404"}),
+        ]
+    }
+
+    fn hosts(src: &str) -> Vec<String> {
+        run(src, &rows())
+            .unwrap()
+            .iter()
+            .map(|row| row["host"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(hosts("NOT host=web*"), vec!["db01"]);
+        assert_eq!(hosts("NOT NOT status=404"), vec!["db01"]);
+        assert_eq!(
+            hosts("(host=db* OR status=5*) bytes!=128"),
+            Vec::<String>::new()
+        );
+        assert_eq!(hosts("HOST=WEB01 status>=200"), Vec::<String>::new());
+        assert_eq!(hosts("host=WEB01 \"/a\""), vec!["web01", "web01"]);
+        assert_eq!(hosts("search bytes>1000"), vec!["web01"]);
+        assert_eq!(hosts("get"), vec!["web01", "web02", "db01"]);
+        assert_eq!(hosts("search").len(), 4);
+
+        for src in [
+            "NOT",
+            "host=a NOT",
+            "search NOT",
+            "(NOT",
+            "NOT OR x",
+            "(host=a NOT)",
+        ] {
+            assert_eq!(
+                run(src, &rows()),
+                Err("NOT needs a search term after it".to_string()),
+                "{}",
+                src
+            );
+        }
+        assert!(run("(host=a", &rows()).is_err());
+        assert!(run("host=", &rows()).is_err());
+        assert!(run("host=a OR", &rows()).is_err());
+    }
+
+    #[test]
+    fn test_where_and_eval() {
+        assert_eq!(hosts("where bytes > 500"), vec!["web01", "web01"]);
+        assert_eq!(
+            hosts("where isnull(bytes) OR status == \"500\""),
+            vec!["web02", "db01"]
+        );
+        assert_eq!(
+            run("eval code=tonumber(status) * 2, big=if(code > 500, \"y\", \"n\") | fields code, big | head 2", &rows()).unwrap(),
+            vec![
+                json!({"code": 400, "big": "n", "_raw": "GET /a 200"}),
+                json!({"code": 1000, "big": "y", "_raw": "GET /b 500"}),
+            ]
+        );
+        assert!(run("eval = 1", &rows()).is_err());
+        assert!(run("where", &rows()).is_err());
+    }
+
+    #[test]
+    fn test_fields_sort_head() {
+        assert_eq!(
+            run("fields - _raw, b* | head 1", &rows()).unwrap(),
+            vec![json!({"host": "web01", "status": "200"})]
+        );
+        assert_eq!(
+            hosts("sort - status, +host"),
+            vec!["web02", "db01", "web01", "web01"]
+        );
+        // Rows without the field go last either way.
+        assert_eq!(hosts("sort 2 bytes"), vec!["web02", "web01"]);
+        assert_eq!(hosts("sort -bytes").last().unwrap(), "db01");
+        assert_eq!(hosts("head").len(), 4);
+        assert_eq!(hosts("head limit=1"), vec!["web01"]);
+        assert!(run("sort", &rows()).is_err());
+        assert!(run("head many", &rows()).is_err());
+    }
+
+    #[test]
+    fn test_dedup_and_stats() {
+        assert_eq!(
+            hosts("dedup 2 host"),
+            vec!["web01", "web02", "web01", "db01"]
+        );
+        // Rows missing a dedup field are dropped.
+        assert_eq!(hosts("dedup host bytes"), vec!["web01", "web02", "web01"]);
+        assert!(run("dedup", &rows()).is_err());
+
+        assert_eq!(
+            run(
+                "stats avg(bytes) as avg, min(status), values(host) by status",
+                &rows()
+            )
+            .unwrap(),
+            vec![
+                json!({"status": "200", "avg": 1280, "min(status)": 200, "values(host)": ["web01"]}),
+                json!({"status": "404", "avg": null, "min(status)": 404, "values(host)": ["db01"]}),
+                json!({"status": "500", "avg": 128, "min(status)": 500, "values(host)": ["web02"]}),
+            ]
+        );
+        assert_eq!(run("stats count", &[]).unwrap(), vec![json!({"count": 0})]);
+        assert!(run("stats median(bytes)", &rows()).is_err());
+        assert!(run("stats count host", &rows()).is_err());
+        assert!(run("search [search x]", &rows()).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod format;
+pub mod lexer;
+pub mod lint;
+pub mod local;
+pub mod pipeline;
+pub mod template;
@@ -0,0 +1,170 @@
+//! Splits a token stream into pipeline stages, including the stages of
+//! subsearches.
+
+use super::lexer::{tokenize, Token, TokenKind};
+
+#[derive(Debug)]
+pub struct Stage<'a> {
+    /// Lower-cased command name. A query that doesn't start with a pipe has
+    /// an implicit leading `search` stage.
+    pub command: String,
+    /// Arguments of the stage, without whitespace and comments. Subsearches
+    /// appear only as their brackets; their contents are separate stages.
+    pub args: Vec<Token<'a>>,
+    /// Subsearch nesting level (0 for the main pipeline).
+    pub depth: usize,
+    /// Index of the stage within its own pipeline.
+    pub position: usize,
+}
+
+impl Stage<'_> {
+    /// Value of a `name=value` argument, e.g. `index` or `maxspan`.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.args.windows(3).find_map(|w| {
+            (w[0].kind == TokenKind::Field
+                && w[0].text.eq_ignore_ascii_case(name)
+                && w[1].text == "=")
+                .then_some(w[2].text)
+        })
+    }
+}
+
+/// Commands that write to indexes or lookups, or send data elsewhere.
+pub const WRITE_COMMANDS: &[&str] = &[
+    "delete",
+    "outputlookup",
+    "collect",
+    "outputcsv",
+    "sendemail",
+    "tscollect",
+    "mcollect",
+    "meventcollect",
+    // Custom alert actions are run through `sendalert`.
+    "sendalert",
+];
+
+/// Distinct write commands used anywhere in the query, subsearches included.
+pub fn write_commands(src: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for stage in parse(src) {
+        if WRITE_COMMANDS.contains(&stage.command.as_str()) && !found.contains(&stage.command) {
+            found.push(stage.command);
+        }
+    }
+    found
+}
+
+/// Parts of the query whose SPL isn't known until Splunk runs it, so they
+/// can't be checked for write commands: `map` builds a search from each
+/// result row, and macros expand on the server.
+pub fn unchecked_parts(src: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for stage in parse(src) {
+        let parts = std::iter::once(stage.command.as_str())
+            .filter(|command| *command == "map")
+            .chain(
+                stage
+                    .args
+                    .iter()
+                    .filter(|t| t.kind == TokenKind::Macro)
+                    .map(|t| t.text),
+            );
+        for part in parts {
+            if !found.iter().any(|f| f == part) {
+                found.push(part.to_string());
+            }
+        }
+    }
+    found
+}
+
+pub fn parse(src: &str) -> Vec<Stage<'_>> {
+    let mut stages: Vec<Stage> = Vec::new();
+    // For each open pipeline: the stage currently receiving arguments and
+    // how many stages it has had.
+    let mut frames: Vec<(Option<usize>, usize)> = vec![(None, 0)];
+
+    for token in tokenize(src) {
+        let depth = frames.len() - 1;
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            TokenKind::Pipe => {
+                frames[depth].0 = None;
+                continue;
+            }
+            TokenKind::Command => {
+                let position = frames[depth].1;
+                stages.push(Stage {
+                    command: token.text.to_ascii_lowercase(),
+                    args: Vec::new(),
+                    depth,
+                    position,
+                });
+                frames[depth] = (Some(stages.len() - 1), position + 1);
+                continue;
+            }
+            TokenKind::RBracket if depth > 0 => {
+                frames.pop();
+            }
+            _ => {}
+        }
+
+        let depth = frames.len() - 1;
+        let current = match frames[depth].0 {
+            Some(i) => i,
+            None => {
+                let position = frames[depth].1;
+                stages.push(Stage {
+                    command: "search".to_string(),
+                    args: Vec::new(),
+                    depth,
+                    position,
+                });
+                frames[depth] = (Some(stages.len() - 1), position + 1);
+                stages.len() - 1
+            }
+        };
+        stages[current].args.push(token);
+        if token.kind == TokenKind::LBracket {
+            frames.push((None, 0));
+        }
+    }
+
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stages() {
+        let stages = parse("index=web [search index=cmdb | fields host] | join host [| inputlookup x] | stats count");
+        let summary: Vec<(&str, usize, usize)> = stages
+            .iter()
+            .map(|s| (s.command.as_str(), s.depth, s.position))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("search", 0, 0),
+                ("search", 1, 0),
+                ("fields", 1, 1),
+                ("join", 0, 1),
+                ("inputlookup", 1, 0),
+                ("stats", 0, 2),
+            ]
+        );
+        assert_eq!(stages[0].option("index"), Some("web"));
+        assert_eq!(stages[1].option("INDEX"), Some("cmdb"));
+        assert_eq!(
+            write_commands("index=x [| inputlookup a | outputlookup b] | collect index=s"),
+            vec!["outputlookup", "collect"]
+        );
+        assert_eq!(
+            unchecked_parts(r#"`edr` | map search="search index=x | delete" [search `edr`]"#),
+            vec!["`edr`", "map"]
+        );
+        assert!(unchecked_parts("index=x | stats count").is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! `$name$` token placeholders in saved searches, e.g.
+//! `index=edr host=$host$ user=$user$`.
+
+use std::collections::BTreeMap;
+
+/// A placeholder found in the query: byte range of `$name$`, its name, and
+/// whether it sits inside a double-quoted string.
+struct Placeholder<'a> {
+    start: usize,
+    end: usize,
+    name: &'a str,
+    quoted: bool,
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+fn placeholders(query: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut chars = query.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            '$' => {
+                let rest = &query[i + 1..];
+                let len = rest
+                    .find(|ch: char| !is_token_char(ch))
+                    .unwrap_or(rest.len());
+                // Names start with a letter or `_`, so sed and regex
+                // backreferences such as `$1$` are left alone.
+                let named = rest.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_');
+                if named && rest[len..].starts_with('$') {
+                    found.push(Placeholder {
+                        start: i,
+                        end: i + len + 2,
+                        name: &rest[..len],
+                        quoted,
+                    });
+                    // Skip past the closing `$`.
+                    for _ in 0..=len {
+                        chars.next();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Distinct token names in order of first appearance.
+pub fn tokens(query: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for p in placeholders(query) {
+        if !names.iter().any(|n| n == p.name) {
+            names.push(p.name.to_string());
+        }
+    }
+    names
+}
+
+/// Replaces every `$name$` with its value, escaped for where it appears.
+pub fn substitute(query: &str, values: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(query.len());
+    let mut last = 0;
+    for p in placeholders(query) {
+        let value = values
+            .get(p.name)
+            .ok_or_else(|| format!("No value for token ${}$", p.name))?;
+        out.push_str(&query[last..p.start]);
+        out.push_str(&escape(value, p.quoted));
+        last = p.end;
+    }
+    out.push_str(&query[last..]);
+    Ok(out)
+}
+
+/// Inside a string only `\` and `"` need escaping. Bare values are quoted
+/// when they would otherwise change the structure of the search; plain
+/// values (including wildcards like `web*`) are inserted as-is.
+fn escape(value: &str, quoted: bool) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    if quoted {
+        return escaped;
+    }
+    let needs_quotes = value.is_empty()
+        || matches!(value.to_ascii_uppercase().as_str(), "AND" | "OR" | "NOT")
+        || value.chars().any(|c| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '|' | '[' | ']' | '(' | ')' | '=' | ',' | '"' | '\'' | '`' | '<' | '>' | '!'
+                )
+        });
+    if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Checks a value against a token's validation regex (anchored at both ends).
+pub fn validate(name: &str, value: &str, pattern: Option<&str>) -> Result<(), String> {
+    let Some(pattern) = pattern else {
+        return Ok(());
+    };
+    let re = regex::Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|e| format!("Invalid pattern for ${}$: {}", name, e))?;
+    if re.is_match(value) {
+        Ok(())
+    } else {
+        Err(format!("${}$ must match {}", name, pattern))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_and_substitute() {
+        let query = r#"index=edr host=$host$ user="$user$" $host$ cost=$5"#;
+        assert_eq!(tokens(query), vec!["host", "user"]);
+
+        let values: BTreeMap<String, String> = [
+            ("host".to_string(), "web 01".to_string()),
+            ("user".to_string(), r#"a"b"#.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            substitute(query, &values).unwrap(),
+            r#"index=edr host="web 01" user="a\"b" "web 01" cost=$5"#
+        );
+
+        let values: BTreeMap<String, String> = [("host".to_string(), "web*".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(substitute("host=$host$", &values).unwrap(), "host=web*");
+        assert!(substitute("user=$user$", &values).is_err());
+    }
+
+    #[test]
+    fn test_backreferences_are_not_tokens() {
+        for query in [
+            r#"| rex mode=sed field=x "s/(a)(b)/$1$2/""#,
+            r#"| eval y=replace(x, "(.)", "$1$")"#,
+        ] {
+            assert!(tokens(query).is_empty(), "{}", query);
+            assert_eq!(substitute(query, &BTreeMap::new()).unwrap(), query);
+        }
+        assert_eq!(
+            tokens("host=$_host$ x=$form.user$"),
+            vec!["_host", "form.user"]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("host", "web01", Some(r"[\w.-]+")).is_ok());
+        assert!(validate("host", "web01 | delete", Some(r"[\w.-]+")).is_err());
+        assert!(validate("host", "anything", None).is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+//! Single-value panels for KPI searches that return one short row, e.g.
+//! `| stats count` or `| makeresults | eval ...`.
+
+use crate::utils::saved_searches::Threshold;
+use serde_json::Value;
+
+/// Most panels shown side by side.
+const MAX_PANELS: usize = 4;
+/// Rows with more visible fields than this are left to the table.
+const MAX_FIELDS: usize = 6;
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// The numeric fields of a single-row result, or `None` if the results don't
+/// look like a KPI.
+pub fn values(results: &[Value]) -> Option<Vec<(String, f64)>> {
+    let [row] = results else {
+        return None;
+    };
+    let fields: Vec<(&String, &Value)> = row
+        .as_object()?
+        .iter()
+        .filter(|(k, _)| !k.starts_with('_'))
+        .collect();
+    if fields.len() > MAX_FIELDS {
+        return None;
+    }
+    let numeric: Vec<(String, f64)> = fields
+        .iter()
+        .filter_map(|(k, v)| number(v).map(|n| (k.to_string(), n)))
+        .collect();
+    (!numeric.is_empty() && numeric.len() <= MAX_PANELS).then_some(numeric)
+}
+
+/// Value of `field` in a comparison run's single row.
+pub fn compare_value(results: &[Value], field: &str) -> Option<f64> {
+    results.first()?.get(field).and_then(number)
+}
+
+/// Color of the highest threshold the value reaches.
+pub fn threshold_color(value: f64, thresholds: &[Threshold]) -> Option<&str> {
+    thresholds
+        .iter()
+        .filter(|t| value >= t.value)
+        .max_by(|a, b| a.value.total_cmp(&b.value))
+        .map(|t| t.color.as_str())
+}
+
+/// Thousands separators and at most two decimals.
+pub fn format_number(value: f64) -> String {
+    let text = if value.fract() == 0.0 {
+        format!("{}", value.abs() as u64)
+    } else {
+        format!("{:.2}", value.abs())
+    };
+    let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+    let mut grouped = String::new();
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    if frac.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!("{}{}.{}", sign, grouped, frac)
+    }
+}
+
+fn glyph(c: char) -> Option<[&'static str; 5]> {
+    Some(match c {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => ["██ ", " █ ", " █ ", " █ ", "███"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", "███", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        '.' => [" ", " ", " ", " ", "█"],
+        ',' => [" ", " ", " ", "█", "▀"],
+        '-' => ["   ", "   ", "███", "   ", "   "],
+        _ => return None,
+    })
+}
+
+/// Five rows of block digits for `text`, or `None` if it has characters
+/// without a glyph.
+pub fn big_text(text: &str) -> Option<Vec<String>> {
+    let glyphs: Vec<[&str; 5]> = text.chars().map(glyph).collect::<Option<_>>()?;
+    Some(
+        (0..5)
+            .map(|row| glyphs.iter().map(|g| g[row]).collect::<Vec<_>>().join(" "))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_values_and_formatting() {
+        let kpi = vec![json!({"count": "1234", "avg": 2.5, "host": "web", "_time": "x"})];
+        assert_eq!(
+            values(&kpi),
+            Some(vec![
+                ("count".to_string(), 1234.0),
+                ("avg".to_string(), 2.5)
+            ])
+        );
+        assert!(values(&[json!({"count": 1}), json!({"count": 2})]).is_none());
+        assert!(values(&[json!({"host": "web"})]).is_none());
+
+        assert_eq!(format_number(1234567.0), "1,234,567");
+        assert_eq!(format_number(-1234.5), "-1,234.50");
+        assert_eq!(big_text("1.0").unwrap()[4], "███ █ ███");
+        assert!(big_text("12k").is_none());
+
+        let thresholds = vec![
+            Threshold {
+                value: 100.0,
+                color: "red".to_string(),
+            },
+            Threshold {
+                value: 10.0,
+                color: "yellow".to_string(),
+            },
+        ];
+        assert_eq!(threshold_color(50.0, &thresholds), Some("yellow"));
+        assert_eq!(threshold_color(500.0, &thresholds), Some("red"));
+        assert_eq!(threshold_color(5.0, &thresholds), None);
+    }
+}
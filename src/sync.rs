@@ -0,0 +1,394 @@
+//! Syncing local saved searches with the reports of one Splunk app. Searches
+//! are paired by name and marked local-only (`+`), server-only (`-`),
+//! modified (`~`), unchanged (`=`) or in conflict (`!`) when several owners
+//! use the name. A push creates or updates the report, if its ACL lets you
+//! write it; a pull overwrites the local query, time range and description
+//! but keeps the rest of the sidecar. Conflicts are never pushed or pulled.
+
+use crate::api::{Namespace, SplunkClient};
+use crate::config::Config;
+use crate::models::splunk::ServerSavedSearch;
+use crate::utils::saved_searches::{SavedSearch, SavedSearchManager};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SyncStatus {
+    /// Exists only in the local `saved_searches` directory.
+    LocalOnly,
+    /// Exists only on the server.
+    ServerOnly,
+    /// Exists on both sides with different SPL.
+    Modified,
+    Unchanged,
+    /// Several owners have a server search with this name in the app, so
+    /// it can't be paired with the local one.
+    Conflict,
+}
+
+impl SyncStatus {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            SyncStatus::LocalOnly => "+",
+            SyncStatus::ServerOnly => "-",
+            SyncStatus::Modified => "~",
+            SyncStatus::Unchanged => "=",
+            SyncStatus::Conflict => "!",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncEntry {
+    pub name: String,
+    pub status: SyncStatus,
+    pub local: Option<String>,
+    pub server: Option<ServerSavedSearch>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiffTag {
+    Same,
+    Added,
+    Removed,
+}
+
+/// Pairs local searches with the server searches of one app by name. A name
+/// used by more than one owner is a `Conflict`.
+pub fn diff(local: &[(String, String)], server: &[ServerSavedSearch]) -> Vec<SyncEntry> {
+    let mut entries: BTreeMap<String, SyncEntry> = BTreeMap::new();
+
+    for (name, query) in local {
+        entries.insert(
+            name.clone(),
+            SyncEntry {
+                name: name.clone(),
+                status: SyncStatus::LocalOnly,
+                local: Some(query.clone()),
+                server: None,
+            },
+        );
+    }
+
+    for saved in server {
+        let entry = entries
+            .entry(saved.name.clone())
+            .or_insert_with(|| SyncEntry {
+                name: saved.name.clone(),
+                status: SyncStatus::ServerOnly,
+                local: None,
+                server: None,
+            });
+        let other_owner = entry
+            .server
+            .as_ref()
+            .is_some_and(|s| s.owner != saved.owner);
+        entry.status = match &entry.local {
+            _ if other_owner || entry.status == SyncStatus::Conflict => SyncStatus::Conflict,
+            Some(query) if query.trim() == saved.search.trim() => SyncStatus::Unchanged,
+            Some(_) => SyncStatus::Modified,
+            None => SyncStatus::ServerOnly,
+        };
+        entry.server = Some(saved.clone());
+    }
+
+    entries.into_values().collect()
+}
+
+/// Line diff from `old` (server) to `new` (local) based on the longest common subsequence.
+pub fn line_diff(old: &str, new: &str) -> Vec<(DiffTag, String)> {
+    let a: Vec<&str> = old.trim().lines().collect();
+    let b: Vec<&str> = new.trim().lines().collect();
+
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push((DiffTag::Same, a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push((DiffTag::Removed, a[i].to_string()));
+            i += 1;
+        } else {
+            out.push((DiffTag::Added, b[j].to_string()));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| (DiffTag::Removed, l.to_string())));
+    out.extend(b[j..].iter().map(|l| (DiffTag::Added, l.to_string())));
+    out
+}
+
+/// Loads every local saved search as `(name, query)` pairs.
+pub fn load_local() -> std::io::Result<Vec<(String, String)>> {
+    SavedSearchManager::list_searches()?
+        .into_iter()
+        .map(|name| SavedSearchManager::load_search(&name).map(|query| (name, query)))
+        .collect()
+}
+
+/// Fetches the saved searches that belong to `app` (not ones shared into it).
+pub async fn load_server(
+    client: &SplunkClient,
+    app: &str,
+) -> Result<Vec<ServerSavedSearch>, Box<dyn Error + Send + Sync>> {
+    Ok(client
+        .list_saved_searches()
+        .await?
+        .into_iter()
+        .filter(|s| s.app == app)
+        .collect())
+}
+
+/// Pushes a local search to the server, creating or updating the report.
+pub async fn push(
+    client: &SplunkClient,
+    namespace: &Namespace,
+    entry: &SyncEntry,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    check_conflict(entry)?;
+    let Some(query) = &entry.local else {
+        return Err(format!("'{}' has no local copy to push", entry.name).into());
+    };
+    match (&entry.server, entry.status) {
+        (_, SyncStatus::Unchanged) => Ok(format!("'{}' already up to date", entry.name)),
+        (Some(saved), _) if !saved.can_write => Err(format!(
+            "Permission denied by ACL: '{}' is not writable by you",
+            entry.name
+        )
+        .into()),
+        (Some(saved), _) => {
+            client.update_saved_search(saved, query.trim()).await?;
+            Ok(format!("Updated '{}' in {}", entry.name, saved.app))
+        }
+        (None, _) => {
+            client
+                .create_saved_search(namespace, &entry.name, query.trim())
+                .await?;
+            Ok(format!("Created '{}' in {}", entry.name, namespace.app))
+        }
+    }
+}
+
+fn check_conflict(entry: &SyncEntry) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if entry.status == SyncStatus::Conflict {
+        return Err(format!(
+            "'{}' exists for more than one owner in the app; rename one on the server",
+            entry.name
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Writes a server search into the local `saved_searches` directory.
+pub fn pull(entry: &SyncEntry) -> Result<String, Box<dyn Error + Send + Sync>> {
+    check_conflict(entry)?;
+    let Some(saved) = &entry.server else {
+        return Err(format!("'{}' does not exist on the server", entry.name).into());
+    };
+    let mut meta = SavedSearchManager::load_metadata(&entry.name).unwrap_or_default();
+    if !saved.description.is_empty() {
+        meta.description = saved.description.clone();
+    }
+    meta.earliest = Some(saved.earliest_time.clone()).filter(|t| !t.is_empty());
+    meta.latest = Some(saved.latest_time.clone()).filter(|t| !t.is_empty());
+    SavedSearchManager::save(&SavedSearch {
+        name: entry.name.clone(),
+        query: saved.search.clone(),
+        meta,
+    })?;
+    Ok(format!("Pulled '{}' into local saved searches", entry.name))
+}
+
+/// Options for `spelunktui sync`.
+pub struct SyncArgs {
+    pub app: Option<String>,
+    pub owner: Option<String>,
+    pub push: Vec<String>,
+    pub pull: Vec<String>,
+    pub push_all: bool,
+    pub pull_all: bool,
+}
+
+/// CLI entry point: prints the diff and applies the requested pushes and pulls.
+pub async fn run(args: SyncArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = Config::load()?;
+    config.validate()?;
+
+    let app = args
+        .app
+        .or_else(|| config.app.clone())
+        .ok_or("No app given. Pass --app or set `app` in the active profile.")?;
+    let namespace = Namespace {
+        owner: args
+            .owner
+            .or_else(|| config.owner.clone())
+            .unwrap_or_else(|| "nobody".to_string()),
+        app,
+    };
+    let client = SplunkClient::new(
+        config.splunk_base_url.clone(),
+        config.splunk_token.clone(),
+        config.splunk_verify_ssl,
+    )
+    .with_namespace(Some(namespace.clone()))
+    .with_read_only(config.read_only);
+
+    let entries = diff(&load_local()?, &load_server(&client, &namespace.app).await?);
+
+    println!("Local saved searches vs app '{}':", namespace.app);
+    for entry in &entries {
+        println!("{} {}", entry.status.marker(), entry.name);
+        if entry.status == SyncStatus::Modified {
+            let server = entry
+                .server
+                .as_ref()
+                .map(|s| s.search.as_str())
+                .unwrap_or("");
+            let local = entry.local.as_deref().unwrap_or("");
+            for (tag, line) in line_diff(server, local) {
+                let prefix = match tag {
+                    DiffTag::Same => ' ',
+                    DiffTag::Added => '+',
+                    DiffTag::Removed => '-',
+                };
+                println!("    {}{}", prefix, line);
+            }
+        }
+    }
+
+    // Whether each entry is pushed (`Some(true)`) or pulled; doing both is
+    // refused before anything changes.
+    let mut plan = Vec::new();
+    for entry in &entries {
+        let wants_push = args.push.contains(&entry.name)
+            || (args.push_all && entry.status == SyncStatus::LocalOnly)
+            || (args.push_all && entry.status == SyncStatus::Modified);
+        let wants_pull = args.pull.contains(&entry.name)
+            || (args.pull_all && entry.status == SyncStatus::ServerOnly)
+            || (args.pull_all && entry.status == SyncStatus::Modified);
+        match (wants_push, wants_pull) {
+            (true, true) => {
+                return Err(format!(
+                    "'{}' would be both pushed and pulled; choose one",
+                    entry.name
+                )
+                .into())
+            }
+            (false, false) => {}
+            (push, _) => plan.push((entry, push)),
+        }
+    }
+
+    let mut failures = 0;
+    for (entry, is_push) in plan {
+        let result = if is_push {
+            push(&client, &namespace, entry).await
+        } else {
+            pull(entry)
+        };
+        match result {
+            Ok(msg) => println!("{}", msg),
+            Err(e) => {
+                failures += 1;
+                eprintln!("{}: {}", entry.name, e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!("{} sync operation(s) failed", failures).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, search: &str) -> ServerSavedSearch {
+        ServerSavedSearch {
+            name: name.to_string(),
+            app: "detections".to_string(),
+            search: search.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_statuses() {
+        let local = vec![
+            ("a".to_string(), "index=a".to_string()),
+            ("b".to_string(), "index=b\n".to_string()),
+            ("c".to_string(), "index=c | stats count".to_string()),
+        ];
+        let remote = vec![
+            server("b", "index=b"),
+            server("c", "index=c"),
+            server("d", "index=d"),
+        ];
+
+        let statuses: Vec<(String, SyncStatus)> = diff(&local, &remote)
+            .into_iter()
+            .map(|e| (e.name, e.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("a".to_string(), SyncStatus::LocalOnly),
+                ("b".to_string(), SyncStatus::Unchanged),
+                ("c".to_string(), SyncStatus::Modified),
+                ("d".to_string(), SyncStatus::ServerOnly),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_same_name_other_owner() {
+        let local = vec![("a".to_string(), "index=a".to_string())];
+        let mine = ServerSavedSearch {
+            owner: "alice".to_string(),
+            ..server("a", "index=a")
+        };
+        let theirs = ServerSavedSearch {
+            owner: "bob".to_string(),
+            ..server("a", "index=other")
+        };
+        let entries = diff(&local, &[mine.clone(), theirs]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, SyncStatus::Conflict);
+        assert!(pull(&entries[0]).unwrap_err().to_string().contains("owner"));
+
+        let entries = diff(&local, &[mine.clone(), mine]);
+        assert_eq!(entries[0].status, SyncStatus::Unchanged);
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let d = line_diff(
+            "index=main\n| stats count",
+            "index=main\n| stats count by host",
+        );
+        assert_eq!(
+            d,
+            vec![
+                (DiffTag::Same, "index=main".to_string()),
+                (DiffTag::Removed, "| stats count".to_string()),
+                (DiffTag::Added, "| stats count by host".to_string()),
+            ]
+        );
+    }
+}
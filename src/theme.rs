@@ -0,0 +1,320 @@
+//! Color themes: the built-ins plus `*.toml` files in
+//! `~/.config/spelunktui/themes/`, each setting every `AppTheme` color and
+//! optionally the syntax theme of the detail highlighter.
+
+use directories::ProjectDirs;
+use ratatui::style::Color;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppTheme {
+    #[serde(deserialize_with = "color")]
+    pub border: Color,
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+    #[serde(deserialize_with = "color")]
+    pub input_edit: Color,
+    #[serde(deserialize_with = "color")]
+    pub title_main: Color,
+    #[serde(deserialize_with = "color")]
+    pub title_secondary: Color,
+    #[serde(deserialize_with = "color")]
+    pub summary_highlight: Color,
+    #[serde(deserialize_with = "color")]
+    pub owner_label: Color,
+    #[serde(deserialize_with = "color")]
+    pub date_label: Color,
+    #[serde(deserialize_with = "color")]
+    pub active_label: Color,
+    /// Errors, and the fifth chart series.
+    #[serde(deserialize_with = "color")]
+    pub error: Color,
+    #[serde(deserialize_with = "color")]
+    pub separator: Color,
+}
+
+/// A color name (`red`, `dark gray`, `light_cyan`), a hex `#rrggbb` or a
+/// 256-color index.
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Spec {
+        Index(u8),
+        Text(String),
+    }
+    match Spec::deserialize(deserializer)? {
+        Spec::Index(i) => Ok(Color::Indexed(i)),
+        Spec::Text(s) => s
+            .parse()
+            .map_err(|_| D::Error::custom(format!("invalid color '{}'", s))),
+    }
+}
+
+impl AppTheme {
+    pub fn default_theme() -> Self {
+        Self {
+            border: Color::Green,
+            text: Color::White,
+            input_edit: Color::Yellow,
+            title_main: Color::Green,
+            title_secondary: Color::Cyan,
+            summary_highlight: Color::Magenta,
+            owner_label: Color::Cyan,
+            date_label: Color::DarkGray,
+            active_label: Color::Green,
+            error: Color::Red,
+            separator: Color::DarkGray,
+        }
+    }
+
+    pub fn color_pop() -> Self {
+        Self {
+            border: Color::Cyan,
+            text: Color::White,
+            input_edit: Color::Red,
+            title_main: Color::Yellow,
+            title_secondary: Color::Green,
+            summary_highlight: Color::Blue,
+            owner_label: Color::Green,
+            date_label: Color::Gray,
+            active_label: Color::Yellow,
+            error: Color::Red,
+            separator: Color::Gray,
+        }
+    }
+
+    pub fn splunk() -> Self {
+        Self {
+            border: Color::Rgb(115, 165, 52),
+            text: Color::Rgb(255, 255, 255),
+            input_edit: Color::Rgb(245, 130, 32),
+            title_main: Color::Rgb(115, 165, 52),
+            title_secondary: Color::Rgb(0, 122, 195),
+            summary_highlight: Color::Rgb(214, 61, 139),
+            owner_label: Color::Rgb(45, 156, 219),
+            date_label: Color::Rgb(164, 164, 164),
+            active_label: Color::Rgb(115, 165, 52),
+            error: Color::Rgb(208, 2, 27),
+            separator: Color::Rgb(80, 80, 80),
+        }
+    }
+
+    pub fn neon() -> Self {
+        // Active BG: #FF1493 (DeepPink), Active FG: Black, Inactive BG: #00FF00 (Lime), Inactive FG: Black
+        Self {
+            border: Color::Rgb(0, 255, 0), // Inactive Pill BG (Lime)
+            text: Color::White,
+            input_edit: Color::Rgb(255, 20, 147), // Active Pill BG (DeepPink)
+            title_main: Color::Rgb(0, 255, 0),    // Lime
+            title_secondary: Color::Cyan,
+            summary_highlight: Color::Rgb(255, 20, 147), // DeepPink
+            owner_label: Color::Rgb(0, 255, 0),
+            date_label: Color::DarkGray,
+            active_label: Color::Rgb(255, 20, 147), // DeepPink
+            error: Color::Red,
+            separator: Color::DarkGray,
+        }
+    }
+
+    /// For terminals with a light background.
+    pub fn light() -> Self {
+        Self {
+            border: Color::Rgb(0, 95, 135),
+            text: Color::Black,
+            input_edit: Color::Rgb(175, 95, 0),
+            title_main: Color::Rgb(0, 95, 135),
+            title_secondary: Color::Rgb(0, 135, 95),
+            summary_highlight: Color::Rgb(0, 95, 175),
+            owner_label: Color::Rgb(0, 135, 95),
+            date_label: Color::Rgb(108, 108, 108),
+            active_label: Color::Rgb(0, 135, 0),
+            error: Color::Rgb(175, 0, 0),
+            separator: Color::Rgb(168, 168, 168),
+        }
+    }
+
+    /// Pure white text and bright, mutually distinct accents; nothing relies
+    /// on dark gray or on red versus green alone.
+    pub fn high_contrast() -> Self {
+        Self {
+            border: Color::White,
+            text: Color::White,
+            input_edit: Color::LightYellow,
+            title_main: Color::LightCyan,
+            title_secondary: Color::LightYellow,
+            summary_highlight: Color::Blue,
+            owner_label: Color::LightCyan,
+            date_label: Color::Gray,
+            active_label: Color::LightGreen,
+            error: Color::LightMagenta,
+            separator: Color::Gray,
+        }
+    }
+}
+
+/// The syntect theme used with a color theme.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyntaxTheme {
+    /// One of syntect's bundled themes, such as `base16-ocean.dark`.
+    Bundled(String),
+    /// A `.tmTheme` file.
+    File(PathBuf),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThemeDef {
+    pub name: String,
+    pub colors: AppTheme,
+    pub syntax: SyntaxTheme,
+    /// The file a user theme came from.
+    pub path: Option<PathBuf>,
+}
+
+pub fn builtins() -> Vec<ThemeDef> {
+    let def = |name: &str, colors, syntax: &str| ThemeDef {
+        name: name.to_string(),
+        colors,
+        syntax: SyntaxTheme::Bundled(syntax.to_string()),
+        path: None,
+    };
+    vec![
+        def("Default", AppTheme::default_theme(), "base16-ocean.dark"),
+        def("ColorPop", AppTheme::color_pop(), "base16-eighties.dark"),
+        def("Splunk", AppTheme::splunk(), "base16-mocha.dark"),
+        def("Neon", AppTheme::neon(), "base16-ocean.dark"),
+        def("Light", AppTheme::light(), "InspiredGitHub"),
+        def(
+            "High Contrast",
+            AppTheme::high_contrast(),
+            "base16-eighties.dark",
+        ),
+    ]
+}
+
+/// Parses a theme file. `name` and `syntax_theme` are optional; the name
+/// defaults to the file stem and a `.tmTheme` path is relative to `dir`.
+pub fn parse(stem: &str, source: &str, dir: &Path) -> Result<ThemeDef, String> {
+    let mut table: toml::Table = toml::from_str(source).map_err(|e| e.message().to_string())?;
+    let mut take = |key: &str| match table.remove(key) {
+        Some(toml::Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(format!("'{}' must be a string", key)),
+        None => Ok(None),
+    };
+    let name = take("name")?.unwrap_or_else(|| stem.to_string());
+    let syntax = match take("syntax_theme")? {
+        Some(s) if s.ends_with(".tmTheme") => SyntaxTheme::File(dir.join(s)),
+        Some(s) => SyntaxTheme::Bundled(s),
+        None => SyntaxTheme::Bundled("base16-ocean.dark".to_string()),
+    };
+    let colors = AppTheme::deserialize(toml::Value::Table(table)).map_err(|e| e.to_string())?;
+    Ok(ThemeDef {
+        name,
+        colors,
+        syntax,
+        path: None,
+    })
+}
+
+/// Every `*.toml` theme in `dir` by file name, with one message per file
+/// that failed to load.
+pub fn load_dir(dir: &Path) -> (Vec<ThemeDef>, Vec<String>) {
+    let mut themes = Vec::new();
+    let mut errors = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (themes, errors);
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let file = path.file_name().unwrap_or_default().to_string_lossy();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| parse(&stem, &source, dir));
+        match loaded {
+            Ok(theme) => themes.push(ThemeDef {
+                path: Some(path.clone()),
+                ..theme
+            }),
+            Err(e) => errors.push(format!("{}: {}", file, e)),
+        }
+    }
+    (themes, errors)
+}
+
+/// The built-ins followed by the user's themes; a user theme with a
+/// built-in's name replaces it.
+pub fn load_all() -> (Vec<ThemeDef>, Vec<String>) {
+    let mut themes = builtins();
+    let Some(dirs) = ProjectDirs::from("", "", "spelunktui") else {
+        return (themes, Vec::new());
+    };
+    let (user, errors) = load_dir(&dirs.config_dir().join("themes"));
+    for theme in user {
+        match themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => themes.push(theme),
+        }
+    }
+    (themes, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme_file() {
+        let source = r##"
+            name = "Solarized"
+            syntax_theme = "solarized.tmTheme"
+            border = "#268bd2"
+            text = "light gray"
+            input_edit = "yellow"
+            title_main = 33
+            title_secondary = "cyan"
+            summary_highlight = "#D33682"
+            owner_label = "green"
+            date_label = "dark_gray"
+            active_label = "light-green"
+            error = "red"
+            separator = "240"
+        "##;
+        let theme = parse("solarized", source, Path::new("/themes")).unwrap();
+        assert_eq!(theme.name, "Solarized");
+        assert_eq!(
+            theme.syntax,
+            SyntaxTheme::File(PathBuf::from("/themes/solarized.tmTheme"))
+        );
+        assert_eq!(theme.colors.border, Color::Rgb(0x26, 0x8b, 0xd2));
+        assert_eq!(theme.colors.text, Color::White);
+        assert_eq!(theme.colors.title_main, Color::Indexed(33));
+        assert_eq!(theme.colors.date_label, Color::DarkGray);
+        assert_eq!(theme.colors.active_label, Color::LightGreen);
+        assert_eq!(theme.colors.separator, Color::Indexed(240));
+
+        let unnamed = source.replace("name = \"Solarized\"", "");
+        assert_eq!(
+            parse("solarized", &unnamed, Path::new("/")).unwrap().name,
+            "solarized"
+        );
+
+        let bad = source.replace("\"yellow\"", "\"yelow\"");
+        let err = parse("x", &bad, Path::new("/")).unwrap_err();
+        assert!(err.contains("invalid color 'yelow'"), "{}", err);
+        let missing = source.replace("error = \"red\"", "");
+        assert!(parse("x", &missing, Path::new("/"))
+            .unwrap_err()
+            .contains("missing field `error`"));
+        let typo = source.replace("separator", "seperator");
+        assert!(parse("x", &typo, Path::new("/"))
+            .unwrap_err()
+            .contains("unknown field `seperator`"));
+    }
+}
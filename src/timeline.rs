@@ -0,0 +1,121 @@
+//! Columns of the timeline histogram. A job's `/timeline` buckets are merged
+//! so they fit the width of the screen.
+
+use crate::models::splunk::TimelineBucket;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde_json::Value;
+
+/// Formats `time` at `offset`, or in the machine's local zone when it is
+/// `None`. Tests pin the offset so snapshots don't depend on the machine.
+pub fn format_at(time: DateTime<Utc>, offset: Option<FixedOffset>, format: &str) -> String {
+    match offset {
+        Some(offset) => time.with_timezone(&offset).format(format).to_string(),
+        None => time
+            .with_timezone(&chrono::Local)
+            .format(format)
+            .to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// Epoch seconds, inclusive.
+    pub earliest: f64,
+    /// Epoch seconds, exclusive.
+    pub latest: f64,
+    pub count: u64,
+}
+
+/// Merges `buckets` into at most `width` columns of consecutive buckets.
+pub fn columns(buckets: &[TimelineBucket], width: usize) -> Vec<Column> {
+    if buckets.is_empty() || width == 0 {
+        return Vec::new();
+    }
+    let per_column = buckets.len().div_ceil(width);
+    buckets
+        .chunks(per_column)
+        .map(|chunk| {
+            let last = &chunk[chunk.len() - 1];
+            Column {
+                earliest: chunk[0].earliest_time,
+                latest: last.earliest_time + last.duration,
+                count: chunk.iter().map(|b| b.total_count).sum(),
+            }
+        })
+        .collect()
+}
+
+/// Time window covered by the columns `from..=to`.
+pub fn window(columns: &[Column], from: usize, to: usize) -> Option<(f64, f64)> {
+    let (from, to) = (from.min(to), from.max(to));
+    Some((columns.get(from)?.earliest, columns.get(to)?.latest))
+}
+
+/// Whether an event's `_time` falls inside `window`.
+pub fn event_in_window(event: &Value, window: (f64, f64)) -> bool {
+    event
+        .get("_time")
+        .and_then(|t| t.as_str())
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| {
+            let t = dt.timestamp_millis() as f64 / 1000.0;
+            t >= window.0 && t < window.1
+        })
+        .unwrap_or(false)
+}
+
+/// Epoch seconds as `MM-DD HH:MM:SS`, see `format_at`.
+pub fn format_time(epoch: f64, offset: Option<FixedOffset>) -> String {
+    DateTime::from_timestamp(epoch as i64, 0)
+        .map(|dt| format_at(dt, offset, "%m-%d %H:%M:%S"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_columns_and_window() {
+        let buckets: Vec<TimelineBucket> = (0..5)
+            .map(|i| TimelineBucket {
+                earliest_time: 1000.0 + 60.0 * i as f64,
+                duration: 60.0,
+                total_count: i,
+            })
+            .collect();
+        assert_eq!(columns(&buckets, 10).len(), 5);
+
+        let merged = columns(&buckets, 2);
+        assert_eq!(
+            merged,
+            vec![
+                Column {
+                    earliest: 1000.0,
+                    latest: 1180.0,
+                    count: 3
+                },
+                Column {
+                    earliest: 1180.0,
+                    latest: 1300.0,
+                    count: 7
+                },
+            ]
+        );
+        assert_eq!(window(&merged, 1, 0), Some((1000.0, 1300.0)));
+
+        let event = json!({"_time": "1970-01-01T00:20:00.000+00:00"});
+        assert!(event_in_window(&event, (1180.0, 1300.0)));
+        assert!(!event_in_window(&event, (1000.0, 1180.0)));
+
+        assert_eq!(
+            format_time(1200.0, FixedOffset::east_opt(0)),
+            "01-01 00:20:00"
+        );
+        assert_eq!(
+            format_time(1200.0, FixedOffset::east_opt(3600)),
+            "01-01 01:20:00"
+        );
+    }
+}
@@ -1,7 +1,31 @@
-use crate::api::SplunkClient;
+use crate::api::{shareable_url, Namespace, SplunkClient};
+use crate::chart::{format_value, ChartData, ChartKind};
 use crate::config::Config;
-use crate::models::splunk::JobStatus;
-use crate::utils::saved_searches::SavedSearchManager;
+use crate::dashboard::{self, Dashboard, PanelKind};
+use crate::editor::history::EditHistory;
+use crate::editor::standard::Standard;
+use crate::editor::vim::{Outcome as VimOutcome, Vim, VimState};
+use crate::editor::Buffer;
+use crate::effect::{Action, Effect, Executor, SearchChange};
+use crate::json_tree;
+use crate::keymap::{self, Keymap};
+use crate::models::splunk::{JobStatus, ServerSavedSearch, SplunkApp, TimeRange, TimelineBucket};
+use crate::palette::{self, Setting};
+use crate::spl::format::format as format_spl;
+use crate::spl::lexer::{tokenize, TokenKind};
+use crate::spl::lint::{lint, Finding};
+use crate::spl::local;
+use crate::spl::template;
+use crate::stat;
+use crate::sync::{self as search_sync, DiffTag, SyncEntry, SyncStatus};
+use crate::theme::{self, AppTheme, SyntaxTheme, ThemeDef};
+use crate::timeline::{self, Column};
+use crate::utils::clipboard;
+use crate::utils::fuzzy::fuzzy_match;
+use crate::utils::history::{reverse_find, HistoryEntry, HistoryManager};
+use crate::utils::result_search::{ResultSearch, Scope};
+use crate::utils::saved_searches::{SavedSearch, SearchMetadata, StatConfig};
+use crate::utils::token_values::TokenValues;
 use crossterm::{
     cursor::SetCursorStyle,
     event::{
@@ -14,24 +38,24 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols,
     text::{Line, Span},
     widgets::{
-        Block, BorderType, Borders, List, ListItem, ListState, Padding, Paragraph, Row, Sparkline,
-        Table, TableState, Wrap,
+        Axis, Bar, BarChart, BarGroup, Block, BorderType, Borders, Chart, Dataset, GraphType, List,
+        ListItem, ListState, Padding, Paragraph, Row, Table, TableState, Wrap,
     },
     Frame, Terminal,
 };
 use serde_json::Value;
-use std::fs::File;
-use std::io::Write;
+use std::collections::VecDeque;
 use std::process::{Command, Stdio};
 use std::{error::Error, io, sync::Arc};
-use tokio::sync::Mutex;
+use tokio::sync::mpsc;
 
 fn is_inside(rect: Rect, col: u16, row: u16) -> bool {
     col >= rect.x && col < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
 }
-use log::{error, info};
+use log::{error, info, warn};
 use syntect::highlighting::FontStyle;
 use syntect::{
     easy::HighlightLines,
@@ -39,112 +63,6 @@ use syntect::{
     parsing::SyntaxSet,
 };
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum ThemeVariant {
-    Default,
-    ColorPop,
-    Splunk,
-    Neon,
-}
-
-#[derive(Clone)]
-#[allow(dead_code)]
-pub struct AppTheme {
-    pub variant: ThemeVariant,
-    pub border: Color,
-    pub text: Color,
-    pub input_edit: Color,
-    pub title_main: Color,
-    pub title_secondary: Color,
-    pub summary_highlight: Color,
-    pub owner_label: Color,
-    pub date_label: Color,
-    pub active_label: Color,
-    pub evilness_label: Color,
-    pub confidence_filled: Color,
-    pub confidence_empty: Color,
-    pub separator: Color,
-}
-
-impl AppTheme {
-    pub fn default_theme() -> Self {
-        Self {
-            variant: ThemeVariant::Default,
-            border: Color::Green,
-            text: Color::White,
-            input_edit: Color::Yellow,
-            title_main: Color::Green,
-            title_secondary: Color::Cyan,
-            summary_highlight: Color::Magenta,
-            owner_label: Color::Cyan,
-            date_label: Color::DarkGray,
-            active_label: Color::Green,
-            evilness_label: Color::Red,
-            confidence_filled: Color::Green,
-            confidence_empty: Color::DarkGray,
-            separator: Color::DarkGray,
-        }
-    }
-
-    pub fn color_pop() -> Self {
-        Self {
-            variant: ThemeVariant::ColorPop,
-            border: Color::Cyan,
-            text: Color::White,
-            input_edit: Color::Red,
-            title_main: Color::Yellow,
-            title_secondary: Color::Green,
-            summary_highlight: Color::Blue,
-            owner_label: Color::Green,
-            date_label: Color::Gray,
-            active_label: Color::Yellow,
-            evilness_label: Color::Red,
-            confidence_filled: Color::Yellow,
-            confidence_empty: Color::DarkGray,
-            separator: Color::Gray,
-        }
-    }
-
-    pub fn splunk() -> Self {
-        Self {
-            variant: ThemeVariant::Splunk,
-            border: Color::Rgb(115, 165, 52),
-            text: Color::Rgb(255, 255, 255),
-            input_edit: Color::Rgb(245, 130, 32),
-            title_main: Color::Rgb(115, 165, 52),
-            title_secondary: Color::Rgb(0, 122, 195),
-            summary_highlight: Color::Rgb(214, 61, 139),
-            owner_label: Color::Rgb(45, 156, 219),
-            date_label: Color::Rgb(164, 164, 164),
-            active_label: Color::Rgb(115, 165, 52),
-            evilness_label: Color::Rgb(208, 2, 27),
-            confidence_filled: Color::Rgb(115, 165, 52),
-            confidence_empty: Color::Rgb(60, 68, 77),
-            separator: Color::Rgb(80, 80, 80),
-        }
-    }
-
-    pub fn neon() -> Self {
-        // Active BG: #FF1493 (DeepPink), Active FG: Black, Inactive BG: #00FF00 (Lime), Inactive FG: Black
-        Self {
-            variant: ThemeVariant::Neon,
-            border: Color::Rgb(0, 255, 0), // Inactive Pill BG (Lime)
-            text: Color::White,
-            input_edit: Color::Rgb(255, 20, 147), // Active Pill BG (DeepPink)
-            title_main: Color::Rgb(0, 255, 0),    // Lime
-            title_secondary: Color::Cyan,
-            summary_highlight: Color::Rgb(255, 20, 147), // DeepPink
-            owner_label: Color::Rgb(0, 255, 0),
-            date_label: Color::DarkGray,
-            active_label: Color::Rgb(255, 20, 147), // DeepPink
-            evilness_label: Color::Red,
-            confidence_filled: Color::Rgb(255, 20, 147),
-            confidence_empty: Color::DarkGray,
-            separator: Color::DarkGray,
-        }
-    }
-}
-
 enum InputMode {
     Normal,
     Editing,
@@ -153,25 +71,143 @@ enum InputMode {
     ConfirmOverwrite,
     LocalSearch,
     ThemeSelect,
+    AppSelect,
+    ServerSearches,
+    Sync,
+    HistorySearch,
+    HistoryBrowser,
+    LintConfirm,
+    TokenForm,
+    Timeline,
+    LocalPipeline,
+    DashboardOpen,
+    Dashboard,
+    DashboardInput,
+    Yank,
+    Palette,
     Help,
 }
 
+/// What the yank menu copies to the clipboard.
+#[derive(Clone, PartialEq, Debug)]
+enum YankTarget {
+    Query,
+    RowJson,
+    Raw,
+    TableTsv,
+    TableMarkdown,
+    JobUrl,
+    Field(String),
+}
+
+impl YankTarget {
+    fn key(&self) -> Option<char> {
+        Some(match self {
+            YankTarget::Query => 's',
+            YankTarget::RowJson => 'o',
+            YankTarget::Raw => 'r',
+            YankTarget::TableTsv => 't',
+            YankTarget::TableMarkdown => 'm',
+            YankTarget::JobUrl => 'u',
+            YankTarget::Field(_) => return None,
+        })
+    }
+
+    fn label(&self) -> String {
+        match self {
+            YankTarget::Query => "Query".to_string(),
+            YankTarget::RowJson => "Row as JSON".to_string(),
+            YankTarget::Raw => "_raw".to_string(),
+            YankTarget::TableTsv => "Visible table as TSV".to_string(),
+            YankTarget::TableMarkdown => "Visible table as Markdown".to_string(),
+            YankTarget::JobUrl => "Job URL".to_string(),
+            YankTarget::Field(name) => format!("Field {}", name),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum EditorMode {
     Standard,
     Vim(VimState),
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum VimState {
-    Normal,
-    Insert,
-}
-
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ViewMode {
     RawEvents,
     Table,
+    Chart,
+    Stat,
+}
+
+/// How the Load Search modal groups saved searches.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LoadGroupBy {
+    None,
+    Folder,
+    Tag,
+}
+
+/// A line in the Load Search modal: a group header or an index into `saved_searches`.
+enum LoadRow {
+    Header(String),
+    Search(usize),
+}
+
+/// A `$token$` being filled in before a templated search runs.
+struct TokenField {
+    name: String,
+    value: String,
+    pattern: Option<String>,
+    /// Recently used values, most recent first; Ctrl+N/Ctrl+P cycle through them.
+    recent: Vec<String>,
+    recent_index: Option<usize>,
+}
+
+/// An open dashboard: the latest results of each panel and the token values
+/// its searches run with.
+struct DashboardView {
+    dashboard: Dashboard,
+    /// Bumped whenever every panel is re-dispatched so late results from the
+    /// previous run are dropped.
+    generation: u64,
+    panels: Vec<PanelState>,
+    selected: usize,
+    values: std::collections::BTreeMap<String, String>,
+    /// Input values being edited, one per `dashboard.inputs`.
+    input_edits: Vec<String>,
+    input_selected: usize,
+}
+
+#[derive(Default, Clone)]
+struct PanelState {
+    results: Vec<Value>,
+    error: Option<String>,
+    /// Waiting for the next tick to dispatch.
+    queued: bool,
+    running: bool,
+    /// The job of the running search, cancelled when the dashboard is
+    /// refreshed or closed.
+    sid: Option<String>,
+    last_run: Option<std::time::Instant>,
+}
+
+impl DashboardView {
+    /// Cancels the panel searches still running.
+    fn cancel_searches(&mut self) -> Vec<Effect> {
+        self.panels
+            .iter_mut()
+            .filter_map(|panel| panel.sid.take())
+            .map(|sid| Effect::CancelJob { sid })
+            .collect()
+    }
+}
+
+/// A pending action on the highlighted search in the Load Search finder.
+enum LoadAction {
+    Rename(String),
+    Duplicate(String),
+    ConfirmDelete,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -181,12 +217,45 @@ pub enum ViewFocus {
     ContentDetail,
 }
 
+/// How the detail pane shows the selected event.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DetailFormat {
+    Tree,
+    Yaml,
+    Json,
+    Raw,
+}
+
+impl DetailFormat {
+    fn next(self) -> Self {
+        match self {
+            DetailFormat::Tree => DetailFormat::Yaml,
+            DetailFormat::Yaml => DetailFormat::Json,
+            DetailFormat::Json => DetailFormat::Raw,
+            DetailFormat::Raw => DetailFormat::Tree,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DetailFormat::Tree => "Tree",
+            DetailFormat::Yaml => "YAML",
+            DetailFormat::Json => "JSON",
+            DetailFormat::Raw => "_raw",
+        }
+    }
+}
+
 pub struct App {
     input: String,
     input_scroll: u16,
     input_scroll_x: u16,
     input_mode: InputMode,
-    client: Arc<SplunkClient>,
+    /// The connection as shown in the header and job URLs. Requests go
+    /// through the executor, which owns the client.
+    base_url: String,
+    namespace: Option<Namespace>,
+    read_only: bool,
     status_message: String,
     pub theme: AppTheme,
 
@@ -196,6 +265,15 @@ pub struct App {
     pub table_state: TableState,
     pub detail_scroll: u16,
 
+    // Chart View
+    chart_kind: ChartKind,
+    chart_hidden: std::collections::BTreeSet<String>,
+    chart_cursor: usize,
+
+    // Single-value panels: the saved search's settings and the comparison run
+    stat_config: Option<StatConfig>,
+    stat_compare: Option<Vec<Value>>,
+
     // Search State
     current_job_sid: Option<String>,
     current_job_status: Option<JobStatus>,
@@ -203,10 +281,29 @@ pub struct App {
     results_fetched: bool,
     scroll_offset: u16,
 
+    // Server timeline and the time window selected on it
+    timeline: Vec<TimelineBucket>,
+    timeline_columns: Vec<Column>,
+    timeline_cursor: usize,
+    timeline_anchor: Option<usize>,
+    pub timeline_area: Rect,
+    // All loaded results while a timeline filter is applied
+    unfiltered_results: Option<Vec<Value>>,
+    timeline_filter: Option<(f64, f64)>,
+    /// Applied local pipeline (`where`, `stats`, ...) and the one being edited.
+    local_pipeline: String,
+    pipeline_input: String,
+
     // Local Search
     local_search_query: String,
     search_matches: Vec<usize>,
     current_match_index: Option<usize>,
+    local_search: Option<ResultSearch>,
+    local_search_scope: Scope,
+    local_search_case: bool,
+    local_search_invert: bool,
+    /// Show only the matching rows instead of jumping between them.
+    local_search_filter: bool,
 
     // Syntax Highlighting
     syntax_set: SyntaxSet,
@@ -214,6 +311,30 @@ pub struct App {
     syntax_theme: Theme,
     cached_detail: ratatui::text::Text<'static>,
 
+    // Detail pane: the selected event after `recursive_json_parse`, its
+    // visible tree rows, the folded JSONPaths and the tree cursor
+    detail_format: DetailFormat,
+    detail_value: Option<Value>,
+    detail_rows: Vec<json_tree::Row>,
+    detail_folded: std::collections::HashSet<String>,
+    detail_tree_state: ListState,
+    /// `z` was pressed and the next key is a fold command.
+    fold_pending: bool,
+
+    // Yank menu and the `clipboard_command` from config.toml
+    yank_targets: Vec<YankTarget>,
+    yank_list_state: ListState,
+    clipboard_command: Option<String>,
+    keymap: Keymap,
+    help_scroll: u16,
+
+    // Command palette: the typed filter or command, and the matching actions
+    palette_input: String,
+    palette_matches: Vec<(keymap::Mode, keymap::Action)>,
+    palette_state: ListState,
+    /// Wrap long lines in the raw view and the detail pane (`:set nowrap`).
+    wrap_lines: bool,
+
     // Layout Areas (for mouse interaction)
     pub search_area: Rect,
     pub main_area: Rect,
@@ -222,29 +343,115 @@ pub struct App {
     // Status polling
     is_status_fetching: bool,
 
-    pub should_open_editor: bool,
-
     // Saved Search State
     save_search_name: String,
-    saved_searches: Vec<String>,
+    saved_searches: Vec<SavedSearch>,
     saved_search_list_state: ListState,
+    load_group_by: LoadGroupBy,
+    load_rows: Vec<LoadRow>,
+    load_query: String,
+    load_action: Option<LoadAction>,
     current_saved_search_name: Option<String>,
+    /// Metadata of the current saved search as last loaded or saved, so
+    /// tokens and stat settings don't have to be read from disk.
+    current_saved_search_meta: Option<SearchMetadata>,
+
+    // Query History
+    history: Vec<HistoryEntry>,
+    history_cursor: Option<usize>,
+    history_search_query: String,
+    history_search_match: Option<usize>,
+    history_list_state: TableState,
+
+    // Lint warnings shown before dispatching a search
+    lint_disable: Vec<String>,
+    lint_findings: Vec<Finding>,
+    // Query awaiting lint confirmation, after token substitution
+    pending_query: Option<String>,
+
+    // Token form for templated searches
+    token_fields: Vec<TokenField>,
+    token_selected: usize,
+    token_values: TokenValues,
+
+    // Search Parameters
+    time_range: TimeRange,
+    table_columns: Vec<String>,
+
+    // Server-side Saved Searches
+    server_searches: Vec<ServerSavedSearch>,
+    server_search_state: TableState,
+
+    // Local <-> Server Sync
+    sync_entries: Vec<SyncEntry>,
+    sync_list_state: ListState,
+    sync_marked: std::collections::BTreeSet<String>,
 
     // Editor Logic
     editor_mode: EditorMode,
+    vim: Vim,
+    standard: Standard,
+    edit_history: EditHistory,
     cursor_position: usize, // Byte index into input string
-    editor_file_path: Option<String>,
 
     // Theme Selection
     theme_list_state: ListState,
-    theme_options: Vec<&'static str>,
+    themes: Vec<ThemeDef>,
+
+    // App Namespace Selection
+    profile: String,
+    app_options: Vec<SplunkApp>,
+    app_list_state: ListState,
 
     // Timing
     job_created_at: Option<std::time::Instant>,
+
+    // Dashboards
+    dashboard: Option<DashboardView>,
+    dashboard_source: String,
+
+    /// Offset for timeline and history times; `None` is the local zone.
+    utc_offset: Option<chrono::FixedOffset>,
+}
+
+/// Everything `App` starts from that lives on disk. `run_app` loads it;
+/// tests build it by hand.
+pub struct Startup {
+    pub config: Config,
+    pub history: Vec<HistoryEntry>,
+    pub token_values: TokenValues,
+    pub themes: Vec<ThemeDef>,
+    /// Problems with the user's theme files.
+    pub theme_errors: Vec<String>,
+    /// Offset times are shown at; `None` is the local zone.
+    pub utc_offset: Option<chrono::FixedOffset>,
+}
+
+impl Startup {
+    /// Reads the history, token values and user themes for `config`.
+    pub fn load(config: Config) -> Startup {
+        let (themes, theme_errors) = theme::load_all();
+        Startup {
+            config,
+            history: HistoryManager::load().unwrap_or_default(),
+            token_values: TokenValues::load(),
+            themes,
+            theme_errors,
+            utc_offset: None,
+        }
+    }
 }
 
 impl App {
-    pub fn new(client: Arc<SplunkClient>) -> App {
+    pub fn new(client: &SplunkClient, startup: Startup) -> App {
+        let Startup {
+            config,
+            history,
+            token_values,
+            themes,
+            theme_errors,
+            utc_offset,
+        } = startup;
         let theme_set = ThemeSet::load_defaults();
         let syntax_theme = theme_set.themes["base16-ocean.dark"].clone();
         let mut app = App {
@@ -252,17 +459,48 @@ impl App {
             input_scroll: 0,
             input_scroll_x: 0,
             input_mode: InputMode::Normal,
+            timeline: Vec::new(),
+            timeline_columns: Vec::new(),
+            timeline_cursor: 0,
+            timeline_anchor: None,
+            timeline_area: Rect::default(),
+            unfiltered_results: None,
+            timeline_filter: None,
+            local_pipeline: String::new(),
+            pipeline_input: String::new(),
             local_search_query: String::new(),
             search_matches: Vec::new(),
             current_match_index: None,
+            local_search: None,
+            local_search_scope: Scope::Raw,
+            local_search_case: false,
+            local_search_invert: false,
+            local_search_filter: false,
             syntax_set: SyntaxSet::load_defaults_newlines(),
             theme_set,
             syntax_theme,
             cached_detail: ratatui::text::Text::default(),
+            detail_format: DetailFormat::Tree,
+            detail_value: None,
+            detail_rows: Vec::new(),
+            detail_folded: std::collections::HashSet::new(),
+            detail_tree_state: ListState::default(),
+            fold_pending: false,
+            yank_targets: Vec::new(),
+            yank_list_state: ListState::default(),
+            clipboard_command: config.clipboard_command,
+            keymap: Keymap::default(),
+            help_scroll: 0,
+            palette_input: String::new(),
+            palette_matches: Vec::new(),
+            palette_state: ListState::default(),
+            wrap_lines: true,
             search_area: Rect::default(),
             main_area: Rect::default(),
             detail_area: Rect::default(),
-            client,
+            base_url: client.base_url().to_string(),
+            namespace: client.namespace().cloned(),
+            read_only: client.is_read_only(),
             status_message: String::from(
                 "Press 'q' to quit, 'e' to enter search mode, 't' to toggle theme.",
             ),
@@ -271,74 +509,149 @@ impl App {
             view_focus: ViewFocus::Search,
             table_state: TableState::default(),
             detail_scroll: 0,
+            chart_kind: ChartKind::Line,
+            chart_hidden: std::collections::BTreeSet::new(),
+            chart_cursor: 0,
+            stat_config: None,
+            stat_compare: None,
             current_job_sid: None,
             current_job_status: None,
             search_results: Vec::new(),
             results_fetched: false,
             scroll_offset: 0,
             is_status_fetching: false,
-            should_open_editor: false,
             save_search_name: String::new(),
             saved_searches: Vec::new(),
             saved_search_list_state: ListState::default(),
+            load_group_by: LoadGroupBy::None,
+            load_rows: Vec::new(),
+            load_query: String::new(),
+            load_action: None,
             current_saved_search_name: None,
+            current_saved_search_meta: None,
+            history,
+            history_cursor: None,
+            history_search_query: String::new(),
+            history_search_match: None,
+            history_list_state: TableState::default(),
+            lint_disable: config.lint_disable,
+            lint_findings: Vec::new(),
+            pending_query: None,
+            token_fields: Vec::new(),
+            token_selected: 0,
+            token_values,
+            time_range: TimeRange::default(),
+            table_columns: Vec::new(),
+            server_searches: Vec::new(),
+            server_search_state: TableState::default(),
+            sync_entries: Vec::new(),
+            sync_list_state: ListState::default(),
+            sync_marked: std::collections::BTreeSet::new(),
             editor_mode: EditorMode::Standard,
+            vim: Vim::default(),
+            standard: Standard::default(),
+            edit_history: EditHistory::default(),
             cursor_position: 0,
-            editor_file_path: None,
             theme_list_state: ListState::default(),
-            theme_options: vec!["Default", "ColorPop", "Splunk", "Neon"],
+            themes,
+            profile: config.profile,
+            app_options: Vec::new(),
+            app_list_state: ListState::default(),
             job_created_at: None,
+            dashboard: None,
+            dashboard_source: String::new(),
+            utc_offset,
         };
 
-        // Load saved theme
-        if let Ok(config) = Config::load() {
-            if let Some(theme_name) = config.theme {
-                app.apply_theme(&theme_name, false);
-            }
+        let (keymap, errors) = Keymap::load(&config.keys);
+        app.keymap = keymap;
+        if !errors.is_empty() {
+            app.status_message = format!("config.toml: {}", errors.join("; "));
+        }
+        if !theme_errors.is_empty() {
+            app.status_message = format!("themes: {}", theme_errors.join("; "));
+        }
+        if let Some(theme_name) = config.theme {
+            app.apply_theme(&theme_name);
         }
         app
     }
 
+    /// Compiles the `/` query with the current scope and toggles, then
+    /// either filters the rows to the matches or jumps to the first one. An
+    /// empty query ends the search.
     fn perform_local_search(&mut self) {
         if self.local_search_query.trim().is_empty() {
+            self.local_search = None;
+            let _ = self.refresh_visible_results();
+            self.status_message = String::from("Local search cleared.");
             return;
         }
 
-        self.search_matches.clear();
-        self.current_match_index = None;
-
-        let pattern = match regex::RegexBuilder::new(&self.local_search_query)
-            .case_insensitive(true)
-            .build()
-        {
-            Ok(re) => re,
+        let loaded = self
+            .unfiltered_results
+            .as_ref()
+            .unwrap_or(&self.search_results);
+        let search = ResultSearch::new(
+            &self.local_search_query,
+            self.local_search_scope,
+            self.local_search_case,
+            self.local_search_invert,
+            |name| loaded.iter().any(|row| row.get(name).is_some()),
+        );
+        match search {
+            Ok(search) => self.local_search = Some(search),
             Err(e) => {
                 self.status_message = format!("Invalid Regex: {}", e);
                 return;
             }
-        };
-
-        for (i, result) in self.search_results.iter().enumerate() {
-            // Search in _raw or full JSON dump
-            let text = result.get("_raw").and_then(|v| v.as_str()).unwrap_or("");
-            if pattern.is_match(text) {
-                self.search_matches.push(i);
-            }
         }
+        let _ = self.refresh_visible_results();
+        let target = self
+            .local_search
+            .as_ref()
+            .and_then(|search| search.field())
+            .unwrap_or(self.local_search_scope.label())
+            .to_string();
 
         if self.search_matches.is_empty() {
-            self.status_message = format!("No matches found for '{}'", self.local_search_query);
+            self.status_message = format!(
+                "No matches found for '{}' in {}",
+                self.local_search_query, target
+            );
+        } else if self.local_search_filter {
+            self.status_message = format!(
+                "Showing {} rows matching in {}.",
+                self.search_matches.len(),
+                target
+            );
         } else {
             self.current_match_index = Some(0);
             self.jump_to_match(0);
             self.status_message = format!(
-                "Found {} matches. (1/{})",
+                "Found {} matches in {}. (1/{})",
                 self.search_matches.len(),
+                target,
                 self.search_matches.len()
             );
         }
     }
 
+    /// Rows matching the local search, recomputed whenever the rows change.
+    fn collect_search_matches(&mut self) {
+        self.search_matches = match &self.local_search {
+            Some(search) => self
+                .search_results
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| search.matches(row))
+                .map(|(i, _)| i)
+                .collect(),
+            None => Vec::new(),
+        };
+        self.current_match_index = None;
+    }
+
     fn next_match(&mut self) {
         if let Some(curr) = self.current_match_index {
             let next = if curr + 1 >= self.search_matches.len() {
@@ -376,404 +689,3332 @@ impl App {
                     self.detail_scroll = 0;
                     self.update_detail_view();
                 }
+                ViewMode::Chart => self.chart_cursor = *row_index,
+                ViewMode::Stat => {}
             }
         }
     }
 
-    async fn perform_search(&mut self) {
+    /// Asks for token values if the query is a template, then lints and
+    /// dispatches it.
+    fn perform_search(&mut self) -> Vec<Effect> {
+        self.input_mode = InputMode::Normal;
         if self.input.trim().is_empty() {
-            return;
+            return Vec::new();
         }
+        if !template::tokens(&self.input).is_empty() {
+            self.open_token_form();
+            return Vec::new();
+        }
+        self.check_and_dispatch(self.input.clone())
+    }
 
-        info!("Starting search for: {}", self.input);
-        self.status_message = format!("Creating search job for '{}'...", self.input);
-        self.current_job_sid = None;
-        self.current_job_status = None;
-        self.search_results.clear();
-        self.results_fetched = false;
-        self.scroll_offset = 0;
-        self.job_created_at = None;
+    /// Lints `query` and either asks for confirmation or dispatches it.
+    fn check_and_dispatch(&mut self, query: String) -> Vec<Effect> {
+        let findings = lint(&query, &self.time_range, &self.lint_disable);
+        if !findings.is_empty() {
+            self.status_message = format!("{} lint warning(s) for this search.", findings.len());
+            self.lint_findings = findings;
+            self.pending_query = Some(query);
+            self.input_mode = InputMode::LintConfirm;
+            return Vec::new();
+        }
+        self.dispatch_search(query)
+    }
+
+    fn dispatch_search(&mut self, query: String) -> Vec<Effect> {
+        self.input_mode = InputMode::Normal;
+        info!("Starting search for: {}", query);
+        self.status_message = format!("Creating search job for '{}'...", query);
+        self.reset_job();
+        vec![Effect::CreateSearch {
+            query,
+            range: self.time_range.clone(),
+        }]
+    }
 
-        match self.client.create_search(&self.input).await {
+    fn search_created(
+        &mut self,
+        query: String,
+        range: TimeRange,
+        result: Result<String, String>,
+    ) -> Vec<Effect> {
+        match result {
             Ok(sid) => {
-                info!("Job created successfully: {}", sid);
-                self.current_job_sid = Some(sid.clone());
                 self.status_message = format!("Job created (SID: {}). Running...", sid);
-                self.job_created_at = Some(std::time::Instant::now());
+                let record = self.record_history(&sid, query, range);
+                self.track_job(sid);
+                vec![record]
             }
             Err(e) => {
-                error!("Search creation failed: {}", e);
                 self.status_message = format!("Search failed: {}", e);
+                Vec::new()
             }
         }
     }
 
-    #[allow(dead_code)]
-    pub async fn update_job_status(&mut self) {
-        // Deprecated: Logic moved to background task in run_loop to avoid blocking UI
+    /// Opens the token form for the current query, prefilled with the last
+    /// value used for each token or its default from the saved search.
+    fn open_token_form(&mut self) {
+        let specs = self
+            .current_saved_search_meta
+            .as_ref()
+            .map(|meta| meta.tokens.clone())
+            .unwrap_or_default();
+        self.token_fields = template::tokens(&self.input)
+            .into_iter()
+            .map(|name| {
+                let spec = specs.get(&name).cloned().unwrap_or_default();
+                let recent = self.token_values.recent(&name).to_vec();
+                TokenField {
+                    value: recent.first().cloned().or(spec.default).unwrap_or_default(),
+                    name,
+                    pattern: spec.pattern,
+                    recent,
+                    recent_index: None,
+                }
+            })
+            .collect();
+        self.token_selected = 0;
+        self.input_mode = InputMode::TokenForm;
+        self.status_message =
+            String::from("Fill in the search tokens (Enter to run, Esc to cancel).");
     }
 
-    #[allow(dead_code)]
-    async fn kill_search(&mut self) {
-        if let Some(sid) = &self.current_job_sid {
-            if let Err(e) = self.client.delete_job(sid).await {
-                self.status_message = format!("Failed to kill job: {}", e);
-            } else {
-                self.status_message = String::from("Job killed.");
-                self.current_job_sid = None;
-                self.current_job_status = None;
-            }
+    /// Cycles the selected token through its recently used values.
+    fn cycle_token_value(&mut self, older: bool) {
+        let Some(field) = self.token_fields.get_mut(self.token_selected) else {
+            return;
+        };
+        if field.recent.is_empty() {
+            return;
         }
+        let last = field.recent.len() - 1;
+        let next = match (field.recent_index, older) {
+            (None, true) => 0,
+            (None, false) => last,
+            (Some(i), true) => (i + 1).min(last),
+            (Some(i), false) => i.saturating_sub(1),
+        };
+        field.recent_index = Some(next);
+        field.value = field.recent[next].clone();
     }
 
-    fn clear_results(&mut self) {
-        self.search_results.clear();
-        self.results_fetched = false;
-        self.current_job_sid = None;
-        self.current_job_status = None;
-        self.scroll_offset = 0;
-        self.status_message = String::from("Results cleared.");
-    }
-
-    fn open_in_editor(&mut self) {
-        if self.search_results.is_empty() {
-            self.status_message = String::from("No results to open.");
-            return;
+    /// Validates the form, remembers the values and runs the substituted query.
+    fn submit_token_form(&mut self) -> Vec<Effect> {
+        let mut values = std::collections::BTreeMap::new();
+        for (i, field) in self.token_fields.iter().enumerate() {
+            if let Err(e) = template::validate(&field.name, &field.value, field.pattern.as_deref())
+            {
+                self.token_selected = i;
+                self.status_message = e;
+                return Vec::new();
+            }
+            values.insert(field.name.clone(), field.value.clone());
         }
-
-        let mut temp_dir = std::env::temp_dir();
-        temp_dir.push("splunk_results.json");
-        let file_path = temp_dir.to_str().unwrap().to_string();
-
-        if let Ok(mut file) = File::create(&file_path) {
-            let json_content =
-                serde_json::to_string_pretty(&self.search_results).unwrap_or_default();
-            if file.write_all(json_content.as_bytes()).is_ok() {
-                self.status_message = format!("Saved to {}. Opening...", file_path);
-                self.editor_file_path = Some(file_path);
-                self.should_open_editor = true;
+        let query = match template::substitute(&self.input, &values) {
+            Ok(query) => query,
+            Err(e) => {
+                self.status_message = e;
+                return Vec::new();
             }
+        };
+
+        for field in &self.token_fields {
+            self.token_values.remember(&field.name, &field.value);
         }
+        self.input_mode = InputMode::Normal;
+        let mut effects = vec![Effect::SaveTokenValues(self.token_values.clone())];
+        effects.extend(self.check_and_dispatch(query));
+        effects
     }
 
-    fn open_query_in_editor(&mut self) {
-        let mut temp_dir = std::env::temp_dir();
-        temp_dir.push("splunk_query.spl");
-        let file_path = temp_dir.to_str().unwrap().to_string();
-
-        if let Ok(mut file) = File::create(&file_path) {
-            if file.write_all(self.input.as_bytes()).is_ok() {
-                self.status_message = "Editing query in external editor...".to_string();
-                self.editor_file_path = Some(file_path);
-                self.should_open_editor = true;
-            }
-        }
+    fn record_history(&mut self, sid: &str, query: String, range: TimeRange) -> Effect {
+        let entry = HistoryEntry {
+            timestamp: chrono::Utc::now(),
+            profile: self.profile.clone(),
+            query,
+            earliest: range.earliest,
+            latest: range.latest,
+            sid: sid.to_string(),
+            result_count: None,
+            duration: None,
+        };
+        self.history.push(entry.clone());
+        self.history_cursor = None;
+        Effect::AppendHistory(entry)
     }
 
-    fn open_job_url(&mut self) {
-        if let Some(sid) = &self.current_job_sid {
-            let url = self.client.get_shareable_url(sid);
-            if url.starts_with("http") {
-                let _ = open::that(url);
-                self.status_message = String::from("Opened URL in browser.");
-            } else {
-                self.status_message = String::from("Invalid URL.");
-            }
-        } else {
-            self.status_message = String::from("No active job URL.");
-        }
+    /// Stores the finished job's result count and run time on its history entry.
+    fn record_history_completion(&mut self) -> Option<Effect> {
+        let (Some(sid), Some(status)) = (&self.current_job_sid, &self.current_job_status) else {
+            return None;
+        };
+        let entry = self.history.iter_mut().rev().find(|e| &e.sid == sid)?;
+        entry.result_count = Some(status.result_count);
+        entry.duration = Some(status.run_duration);
+        Some(Effect::RecordCompletion {
+            sid: sid.clone(),
+            result_count: status.result_count,
+            duration: status.run_duration,
+        })
     }
 
-    fn scroll_down(&mut self) {
-        if !self.search_results.is_empty() {
-            self.scroll_offset = self.scroll_offset.saturating_add(1);
+    /// Up/Down recall in the editor. Only active while the buffer is empty or
+    /// still showing a recalled entry, so normal multiline navigation is kept.
+    fn recall_history(&mut self, older: bool) -> bool {
+        if self.history.is_empty() || (!self.input.is_empty() && self.history_cursor.is_none()) {
+            return false;
         }
+        let next = match (self.history_cursor, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => return false,
+            (Some(0), true) => Some(0),
+            (Some(i), true) => Some(i - 1),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        let query = next
+            .map(|i| self.history[i].query.clone())
+            .unwrap_or_default();
+        self.replace_input(query);
+        self.history_cursor = next;
+        true
     }
 
-    fn scroll_down_fast(&mut self) {
-        if !self.search_results.is_empty() {
-            self.scroll_offset = self.scroll_offset.saturating_add(10);
+    fn cursor_up_or_recall(&mut self) {
+        if !self.recall_history(true) {
+            self.move_cursor_up();
         }
     }
 
-    fn scroll_up(&mut self) {
-        if !self.search_results.is_empty() {
-            self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    fn cursor_down_or_recall(&mut self) {
+        if !self.recall_history(false) {
+            self.move_cursor_down();
         }
     }
 
-    fn scroll_up_fast(&mut self) {
-        if !self.search_results.is_empty() {
-            self.scroll_offset = self.scroll_offset.saturating_sub(10);
-        }
+    fn start_history_search(&mut self) {
+        self.history_search_query.clear();
+        self.history_search_match = None;
+        self.input_mode = InputMode::HistorySearch;
+        self.status_message =
+            String::from("reverse-i-search: Ctrl+R older | Enter accept | Esc cancel");
     }
 
-    fn apply_theme(&mut self, theme_name: &str, save: bool) {
-        self.theme = match theme_name {
-            "Default" => {
-                if let Some(t) = self.theme_set.themes.get("base16-ocean.dark") {
-                    self.syntax_theme = t.clone();
-                }
-                AppTheme::default_theme()
-            }
-            "ColorPop" => {
-                if let Some(t) = self.theme_set.themes.get("base16-eighties.dark") {
-                    self.syntax_theme = t.clone();
-                }
-                AppTheme::color_pop()
-            }
-            "Splunk" => {
-                if let Some(t) = self.theme_set.themes.get("base16-mocha.dark") {
-                    self.syntax_theme = t.clone();
-                } else if let Some(t) = self.theme_set.themes.get("InspiredGitHub") {
-                    self.syntax_theme = t.clone();
-                }
-                AppTheme::splunk()
-            }
-            "Neon" => {
-                if let Some(t) = self.theme_set.themes.get("base16-ocean.dark") {
-                    self.syntax_theme = t.clone();
-                }
-                AppTheme::neon()
-            }
-            _ => AppTheme::default_theme(),
+    /// Re-runs the reverse search; `older` continues past the current match.
+    fn update_history_search(&mut self, older: bool) {
+        let before = match (older, self.history_search_match) {
+            (true, Some(i)) => i,
+            _ => self.history.len(),
         };
-        self.update_detail_view();
-        if save {
-            let _ = Config::save_theme(theme_name);
+        match reverse_find(&self.history, &self.history_search_query, before) {
+            Some(i) => self.history_search_match = Some(i),
+            None if older => {} // Keep showing the oldest match.
+            None => self.history_search_match = None,
         }
     }
 
-    fn toggle_theme_selector(&mut self) {
-        self.input_mode = InputMode::ThemeSelect;
-        self.theme_list_state.select(Some(0));
-        self.status_message = String::from("Select theme (Up/Down/Enter), Esc to cancel.");
+    fn accept_history_search(&mut self) {
+        if let Some(query) = self
+            .history_search_match
+            .and_then(|i| self.history.get(i))
+            .map(|entry| entry.query.clone())
+        {
+            self.replace_input(query);
+        }
+        self.input_mode = InputMode::Editing;
+        self.status_message = String::from("Editing... Press Enter to search, Esc to cancel.");
     }
 
-    fn initiate_save_search(&mut self) {
-        if self.input.trim().is_empty() {
-            self.status_message = String::from("Cannot save empty search.");
+    fn open_history_browser(&mut self) {
+        if self.history.is_empty() {
+            self.status_message = String::from("No search history yet.");
             return;
         }
+        self.history_list_state.select(Some(0));
+        self.input_mode = InputMode::HistoryBrowser;
+        self.status_message =
+            String::from("History: Enter load | r re-run | a re-attach job | Esc close");
+    }
 
-        if let Some(name) = &self.current_saved_search_name {
-            self.input_mode = InputMode::ConfirmOverwrite;
-            self.status_message = format!("Overwrite saved search '{}'? (y/n/r)", name);
-        } else {
-            self.input_mode = InputMode::SaveSearch;
-            self.save_search_name.clear();
-            self.status_message =
-                String::from("Enter name for saved search (Enter to save, Esc to cancel):");
-        }
+    /// The history entry highlighted in the browser, which lists newest first.
+    fn selected_history_entry(&self) -> Option<HistoryEntry> {
+        self.history_list_state
+            .selected()
+            .and_then(|i| self.history.len().checked_sub(i + 1))
+            .and_then(|i| self.history.get(i))
+            .cloned()
     }
 
-    fn save_current_search(&mut self) {
-        let name = self.save_search_name.trim();
-        if name.is_empty() {
-            self.status_message = String::from("Name cannot be empty.");
-            return;
-        }
+    fn load_history_entry(&mut self) -> Option<HistoryEntry> {
+        let entry = self.selected_history_entry()?;
+        self.apply_history_entry(&entry);
+        Some(entry)
+    }
+
+    fn apply_history_entry(&mut self, entry: &HistoryEntry) {
+        self.replace_input(entry.query.clone());
+        self.time_range = TimeRange {
+            earliest: entry.earliest.clone(),
+            latest: entry.latest.clone(),
+        };
+        self.forget_saved_search();
+        self.input_mode = InputMode::Normal;
+        self.status_message = String::from("Loaded query from history.");
+    }
 
-        if let Err(e) = SavedSearchManager::save_search(name, &self.input) {
-            self.status_message = format!("Failed to save search: {}", e);
+    fn rerun_history_entry(&mut self) -> Vec<Effect> {
+        if self.load_history_entry().is_some() {
+            self.perform_search()
         } else {
-            self.status_message = format!("Search saved as '{}'.", name);
-            self.current_saved_search_name = Some(name.to_string());
-            self.input_mode = InputMode::Normal;
+            Vec::new()
         }
     }
 
-    fn overwrite_current_search(&mut self) {
-        if let Some(name) = self.current_saved_search_name.clone() {
-            if let Err(e) = SavedSearchManager::save_search(&name, &self.input) {
-                self.status_message = format!("Failed to save search: {}", e);
-            } else {
-                self.status_message = format!("Search '{}' overwritten.", name);
-                self.input_mode = InputMode::Normal;
-            }
-        }
+    /// Re-attaches to the entry's job if the server still has it.
+    fn reattach_history_job(&mut self) -> Vec<Effect> {
+        self.selected_history_entry()
+            .map(|entry| Effect::ReattachJob { entry })
+            .into_iter()
+            .collect()
     }
 
-    fn initiate_load_search(&mut self) {
-        match SavedSearchManager::list_searches() {
-            Ok(searches) => {
-                if searches.is_empty() {
-                    self.status_message = String::from("No saved searches found.");
-                    return;
-                }
-                self.saved_searches = searches;
-                self.input_mode = InputMode::LoadSearch;
-                self.saved_search_list_state.select(Some(0));
-                self.status_message =
-                    String::from("Select saved search (Enter to load, Esc to cancel):");
+    fn job_reattached(&mut self, entry: HistoryEntry, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.apply_history_entry(&entry);
+                self.status_message = format!("Re-attached to job {}.", entry.sid);
+                self.track_job(entry.sid);
             }
-            Err(e) => {
-                self.status_message = format!("Failed to list saved searches: {}", e);
+            Err(_) => {
+                self.status_message =
+                    format!("Job {} has expired; press 'r' to re-run.", entry.sid);
             }
         }
     }
 
-    fn load_selected_search(&mut self) {
-        if let Some(idx) = self.saved_search_list_state.selected() {
-            if let Some(name) = self.saved_searches.get(idx) {
-                match SavedSearchManager::load_search(name) {
-                    Ok(query) => {
-                        self.input = query;
-                        self.current_saved_search_name = Some(name.clone());
-                        self.input_mode = InputMode::Normal;
-                        self.status_message = format!("Loaded search '{}'.", name);
-                        self.cursor_position = self.input.len(); // Reset cursor to end
-                    }
-                    Err(e) => {
-                        self.status_message = format!("Failed to load search: {}", e);
-                    }
-                }
-            }
-        }
+    fn reset_job(&mut self) {
+        self.current_job_sid = None;
+        self.current_job_status = None;
+        self.search_results.clear();
+        self.results_fetched = false;
+        self.scroll_offset = 0;
+        self.job_created_at = None;
+        self.timeline.clear();
+        self.timeline_anchor = None;
+        self.unfiltered_results = None;
+        self.timeline_filter = None;
+        self.local_pipeline.clear();
+        self.local_search = None;
+        self.search_matches.clear();
+        self.current_match_index = None;
     }
 
-    fn list_next(&mut self) {
-        let i = match self.saved_search_list_state.selected() {
-            Some(i) => {
-                if i >= self.saved_searches.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
-        self.saved_search_list_state.select(Some(i));
+    /// Switches between the table and single-value panels to suit the new
+    /// results, and returns the comparison search to run for the panels'
+    /// delta if the saved search configures one.
+    fn prepare_stat_view(&mut self, sid: &str) -> Option<(String, TimeRange)> {
+        let kpi = stat::values(&self.search_results).is_some();
+        match self.view_mode {
+            ViewMode::Table if kpi => self.view_mode = ViewMode::Stat,
+            ViewMode::Stat if !kpi => self.view_mode = ViewMode::Table,
+            _ => {}
+        }
+        self.stat_compare = None;
+        self.stat_config = self
+            .current_saved_search_meta
+            .as_ref()
+            .and_then(|meta| meta.stat.clone());
+        let config = self.stat_config.as_ref().filter(|_| kpi)?;
+        if config.compare_earliest.is_none() && config.compare_latest.is_none() {
+            return None;
+        }
+        // The history entry holds the query as dispatched, tokens substituted.
+        let query = self
+            .history
+            .iter()
+            .rev()
+            .find(|e| e.sid == sid)?
+            .query
+            .clone();
+        Some((
+            query,
+            TimeRange {
+                earliest: config.compare_earliest.clone(),
+                latest: config.compare_latest.clone(),
+            },
+        ))
     }
 
-    fn list_previous(&mut self) {
-        let i = match self.saved_search_list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.saved_searches.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
-        };
-        self.saved_search_list_state.select(Some(i));
+    /// Time window of the timeline selection: the columns between the anchor
+    /// and the cursor, or just the cursor's column.
+    fn timeline_selection(&self) -> Option<(f64, f64)> {
+        timeline::window(
+            &self.timeline_columns,
+            self.timeline_anchor.unwrap_or(self.timeline_cursor),
+            self.timeline_cursor,
+        )
     }
 
-    // --- Cursor Logic ---
-    fn clamp_cursor(&mut self) {
-        if self.cursor_position > self.input.len() {
-            self.cursor_position = self.input.len();
+    fn open_timeline(&mut self) {
+        if self.timeline.is_empty() {
+            self.status_message = String::from("No timeline for this job yet.");
+            return;
         }
+        self.timeline_cursor = self
+            .timeline_cursor
+            .min(self.timeline_columns.len().saturating_sub(1));
+        self.input_mode = InputMode::Timeline;
+        self.status_message = String::from(
+            "Left/Right move | Space/Shift mark range | Enter filter | z zoom | c clear | Esc close",
+        );
     }
 
-    fn move_cursor_left(&mut self) {
-        if self.cursor_position > 0 {
-            // Find start of previous char (UTF-8 safe)
-            let mut new_pos = self.cursor_position - 1;
-            while new_pos > 0 && !self.input.is_char_boundary(new_pos) {
-                new_pos -= 1;
-            }
-            self.cursor_position = new_pos;
+    fn move_timeline_cursor(&mut self, step: isize, extend: bool) {
+        if extend && self.timeline_anchor.is_none() {
+            self.timeline_anchor = Some(self.timeline_cursor);
         }
+        let last = self.timeline_columns.len().saturating_sub(1) as isize;
+        self.timeline_cursor = (self.timeline_cursor as isize + step).clamp(0, last) as usize;
     }
 
-    fn move_cursor_right(&mut self) {
-        if self.cursor_position < self.input.len() {
-            // Find start of next char
-            let mut new_pos = self.cursor_position + 1;
-            while new_pos < self.input.len() && !self.input.is_char_boundary(new_pos) {
-                new_pos += 1;
-            }
-            self.cursor_position = new_pos;
+    /// Shows only the loaded events inside the selected window.
+    fn filter_to_timeline_selection(&mut self) {
+        let Some(window) = self.timeline_selection() else {
+            return;
+        };
+        self.timeline_filter = Some(window);
+        let _ = self.refresh_visible_results();
+        self.status_message = format!(
+            "Showing {} of {} loaded events from {} to {}.",
+            self.search_results.len(),
+            self.unfiltered_results.as_ref().map_or(0, |all| all.len()),
+            timeline::format_time(window.0, self.utc_offset),
+            timeline::format_time(window.1, self.utc_offset)
+        );
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn clear_timeline_filter(&mut self) {
+        if self.timeline_filter.take().is_some() {
+            let _ = self.refresh_visible_results();
+            self.status_message = String::from("Timeline filter cleared.");
         }
     }
 
-    fn move_cursor_up(&mut self) {
-        // Find the last newline before cursor.
-        let cursor_byte_idx = self.cursor_position;
-        let text_before = &self.input[..cursor_byte_idx];
-        let last_newline = text_before.rfind('\n');
+    /// Rebuilds the visible rows from everything loaded: the timeline window
+    /// first, then the local pipeline, then the local search when it only
+    /// shows matching rows. The loaded rows are kept aside while
+    /// either is active so both can be changed or cleared. A pipeline that
+    /// fails is dropped.
+    fn refresh_visible_results(&mut self) -> Result<(), String> {
+        let all = self
+            .unfiltered_results
+            .take()
+            .unwrap_or_else(|| std::mem::take(&mut self.search_results));
+        let search_filter = self
+            .local_search
+            .as_ref()
+            .filter(|_| self.local_search_filter);
+        if self.timeline_filter.is_none()
+            && self.local_pipeline.is_empty()
+            && search_filter.is_none()
+        {
+            self.search_results = all;
+            self.after_results_replaced();
+            return Ok(());
+        }
+        let mut rows: Vec<Value> = match self.timeline_filter {
+            Some(window) => all
+                .iter()
+                .filter(|event| timeline::event_in_window(event, window))
+                .cloned()
+                .collect(),
+            None => all.clone(),
+        };
+        let mut outcome = Ok(());
+        if !self.local_pipeline.is_empty() {
+            match local::run(&self.local_pipeline, &rows) {
+                Ok(output) => rows = output,
+                Err(e) => {
+                    self.local_pipeline.clear();
+                    outcome = Err(e);
+                }
+            }
+        }
+        if let Some(search) = self
+            .local_search
+            .as_ref()
+            .filter(|_| self.local_search_filter)
+        {
+            rows.retain(|row| search.matches(row));
+        }
+        self.search_results = rows;
+        self.unfiltered_results = Some(all);
+        self.after_results_replaced();
+        outcome
+    }
 
-        if let Some(last_nl_idx) = last_newline {
-            // We are not on the first line.
-            let col = cursor_byte_idx - (last_nl_idx + 1);
+    fn open_local_pipeline(&mut self) {
+        if self.search_results.is_empty() && self.unfiltered_results.is_none() {
+            self.status_message = String::from("No results to filter.");
+            return;
+        }
+        if !self.local_pipeline.is_empty() {
+            self.pipeline_input = self.local_pipeline.clone();
+        }
+        self.input_mode = InputMode::LocalPipeline;
+        self.status_message = format!(
+            "Runs over the loaded results: {}. Enter apply, Ctrl+P add to the query.",
+            local::COMMANDS.join(", ")
+        );
+    }
 
-            // Find the newline BEFORE that one to identify the previous line.
-            let text_before_prev_line = &self.input[..last_nl_idx];
-            let prev_line_start = text_before_prev_line
-                .rfind('\n')
-                .map(|i| i + 1)
-                .unwrap_or(0);
+    /// Applies the edited pipeline; an empty one clears it.
+    fn apply_local_pipeline(&mut self) {
+        self.local_pipeline = self.pipeline_input.trim().to_string();
+        match self.refresh_visible_results() {
+            Ok(()) => {
+                self.input_mode = InputMode::Normal;
+                self.status_message = match &self.unfiltered_results {
+                    Some(all) if !self.local_pipeline.is_empty() => format!(
+                        "Local pipeline: {} rows from {} loaded.",
+                        self.search_results.len(),
+                        all.len()
+                    ),
+                    _ => String::from("Local pipeline cleared."),
+                };
+            }
+            // Stay in the bar so the pipeline can be fixed.
+            Err(e) => self.status_message = format!("Local pipeline failed: {}", e),
+        }
+    }
 
-            let prev_line_len = last_nl_idx - prev_line_start;
-            let new_col = col.min(prev_line_len);
+    /// Appends the pipeline to the SPL query so the server runs it next time.
+    fn promote_local_pipeline(&mut self) {
+        let pipeline = self.pipeline_input.trim();
+        if pipeline.is_empty() {
+            return;
+        }
+        let stages = local::to_spl(pipeline);
+        let query = if self.input.trim().is_empty() {
+            stages.trim_start_matches('|').trim_start().to_string()
+        } else {
+            format!("{}\n{}", self.input.trim_end(), stages)
+        };
+        self.replace_input(query);
+        self.pipeline_input.clear();
+        self.local_pipeline.clear();
+        let _ = self.refresh_visible_results();
+        self.input_mode = InputMode::Editing;
+        self.status_message = String::from("Pipeline added to the query. Press Enter to run it.");
+    }
 
-            self.cursor_position = prev_line_start + new_col;
+    fn after_results_replaced(&mut self) {
+        self.collect_search_matches();
+        self.scroll_offset = 0;
+        self.chart_cursor = 0;
+        self.detail_scroll = 0;
+        self.table_state
+            .select((!self.search_results.is_empty()).then_some(0));
+        self.update_detail_view();
+    }
+
+    /// Re-runs the search with its time range narrowed to the selection.
+    fn zoom_to_timeline_selection(&mut self) -> Vec<Effect> {
+        let Some((earliest, latest)) = self.timeline_selection() else {
+            return Vec::new();
+        };
+        self.time_range = TimeRange {
+            earliest: Some((earliest.floor() as i64).to_string()),
+            latest: Some((latest.ceil() as i64).to_string()),
+        };
+        self.perform_search()
+    }
+
+    /// Points the background poller at `sid`; results load once the job is done.
+    fn track_job(&mut self, sid: String) {
+        self.reset_job();
+        self.current_job_sid = Some(sid);
+        self.job_created_at = Some(std::time::Instant::now());
+    }
+
+    fn initiate_server_search_browser(&mut self) -> Vec<Effect> {
+        self.status_message = String::from("Fetching saved searches from server...");
+        vec![Effect::ListServerSearches]
+    }
+
+    fn open_server_searches(&mut self, result: Result<Vec<ServerSavedSearch>, String>) {
+        let searches = match result {
+            Ok(searches) => searches,
+            Err(e) => {
+                self.status_message = format!("Failed to list saved searches: {}", e);
+                return;
+            }
+        };
+        if searches.is_empty() {
+            self.status_message = String::from("No saved searches on server.");
+            return;
         }
+
+        self.server_searches = searches;
+        self.server_search_state.select(Some(0));
+        self.input_mode = InputMode::ServerSearches;
+        self.status_message = String::from(
+            "Enter: copy SPL | d: dispatch | h: last run | s: toggle schedule | Esc: close",
+        );
     }
 
-    fn move_cursor_down(&mut self) {
-        let cursor_byte_idx = self.cursor_position;
+    fn selected_server_search(&self) -> Option<ServerSavedSearch> {
+        self.server_search_state
+            .selected()
+            .and_then(|idx| self.server_searches.get(idx))
+            .cloned()
+    }
 
-        // Find current line start and end
-        let text_before = &self.input[..cursor_byte_idx];
-        let line_start = text_before.rfind('\n').map(|i| i + 1).unwrap_or(0);
-        let col = cursor_byte_idx - line_start;
+    fn copy_server_search_to_editor(&mut self) {
+        if let Some(saved) = self.selected_server_search() {
+            self.replace_input(saved.search.clone());
+            self.forget_saved_search();
+            self.input_mode = InputMode::Normal;
+            self.status_message = format!("Copied SPL of '{}' into the editor.", saved.name);
+        }
+    }
 
-        // Find next newline
-        let text_after = &self.input[cursor_byte_idx..];
-        if let Some(next_nl_rel) = text_after.find('\n') {
-            let next_nl_idx = cursor_byte_idx + next_nl_rel;
-            let next_line_start = next_nl_idx + 1;
+    fn dispatch_server_search(&mut self) -> Vec<Effect> {
+        self.selected_server_search()
+            .map(Effect::DispatchSavedSearch)
+            .into_iter()
+            .collect()
+    }
 
-            // Find end of next line
-            let text_after_next_line = &self.input[next_line_start..];
-            let next_line_end_rel = text_after_next_line
-                .find('\n')
-                .unwrap_or(text_after_next_line.len());
-            let next_line_len = next_line_end_rel;
+    fn saved_search_dispatched(
+        &mut self,
+        saved: ServerSavedSearch,
+        result: Result<String, String>,
+    ) -> Vec<Effect> {
+        match result {
+            Ok(sid) => {
+                self.status_message = format!("Dispatched '{}' (SID: {}).", saved.name, sid);
+                let range = TimeRange {
+                    earliest: Some(saved.earliest_time.clone()).filter(|t| !t.is_empty()),
+                    latest: Some(saved.latest_time.clone()).filter(|t| !t.is_empty()),
+                };
+                let record = self.record_history(&sid, saved.search.clone(), range);
+                self.replace_input(saved.search);
+                self.track_job(sid);
+                self.input_mode = InputMode::Normal;
+                vec![record]
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to dispatch '{}': {}", saved.name, e);
+                Vec::new()
+            }
+        }
+    }
 
-            let new_col = col.min(next_line_len);
-            self.cursor_position = next_line_start + new_col;
+    fn load_server_search_last_run(&mut self) -> Vec<Effect> {
+        self.selected_server_search()
+            .map(Effect::FindLastRun)
+            .into_iter()
+            .collect()
+    }
+
+    fn last_run_found(&mut self, saved: ServerSavedSearch, result: Result<Option<String>, String>) {
+        match result {
+            Ok(Some(sid)) => {
+                self.status_message = format!(
+                    "Loading last scheduled run of '{}' (SID: {})...",
+                    saved.name, sid
+                );
+                self.replace_input(saved.search);
+                self.track_job(sid);
+                self.input_mode = InputMode::Normal;
+            }
+            Ok(None) => {
+                self.status_message = format!("'{}' has no scheduled runs in history.", saved.name);
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to load history: {}", e);
+            }
         }
     }
 
-    fn insert_char(&mut self, c: char) {
-        self.clamp_cursor();
-        self.input.insert(self.cursor_position, c);
-        self.cursor_position += c.len_utf8();
+    fn toggle_server_search_schedule(&mut self) -> Vec<Effect> {
+        self.selected_server_search()
+            .map(|saved| Effect::SetScheduled {
+                scheduled: !saved.is_scheduled,
+                saved,
+            })
+            .into_iter()
+            .collect()
     }
 
-    fn delete_char(&mut self) {
-        if self.cursor_position > 0 {
-            self.move_cursor_left(); // Go back one char
-            self.input.remove(self.cursor_position);
-            // Cursor position is already updated by move_cursor_left
+    fn schedule_set(&mut self, name: String, scheduled: bool, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                if let Some(saved) = self.server_searches.iter_mut().find(|s| s.name == name) {
+                    saved.is_scheduled = scheduled;
+                }
+                self.status_message = format!(
+                    "Schedule for '{}' {}.",
+                    name,
+                    if scheduled { "enabled" } else { "disabled" }
+                );
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to update schedule: {}", e);
+            }
         }
     }
 
-    fn toggle_vim_mode(&mut self) {
-        self.editor_mode = match self.editor_mode {
-            EditorMode::Standard => EditorMode::Vim(VimState::Normal),
-            EditorMode::Vim(_) => EditorMode::Standard,
+    fn initiate_sync(&mut self) -> Vec<Effect> {
+        let Some(ns) = self.namespace.as_ref() else {
+            self.status_message = String::from("Select an app with 'a' before syncing.");
+            return Vec::new();
         };
-        // Ensure cursor is style updated by next render
+        vec![Effect::LoadSync {
+            app: ns.app.clone(),
+        }]
     }
 
-    fn update_detail_view(&mut self) {
-        if self.view_mode == ViewMode::Table {
-            let selected_idx = self.table_state.selected().unwrap_or(0);
-            if let Some(item) = self.search_results.get(selected_idx) {
-                self.cached_detail = render_yaml_detail(&self.syntax_set, &self.syntax_theme, item);
+    fn open_sync(&mut self, app: String, result: Result<Vec<SyncEntry>, String>) {
+        match result {
+            Ok(entries) => self.set_sync_entries(entries),
+            Err(e) => {
+                self.status_message = format!("Sync failed: {}", e);
+                return;
+            }
+        }
+        self.sync_marked.clear();
+        self.sync_list_state.select(Some(0));
+        self.input_mode = InputMode::Sync;
+        self.status_message = format!(
+            "Sync with '{}': Space mark | p push | g pull | Esc close",
+            app
+        );
+    }
+
+    fn set_sync_entries(&mut self, entries: Vec<SyncEntry>) {
+        self.sync_entries = entries;
+        if self.sync_entries.is_empty() {
+            self.sync_list_state.select(None);
+        } else if self.sync_list_state.selected().unwrap_or(0) >= self.sync_entries.len() {
+            self.sync_list_state
+                .select(Some(self.sync_entries.len() - 1));
+        }
+    }
+
+    fn toggle_sync_mark(&mut self) {
+        if let Some(entry) = self
+            .sync_list_state
+            .selected()
+            .and_then(|idx| self.sync_entries.get(idx))
+        {
+            if !self.sync_marked.remove(&entry.name) {
+                self.sync_marked.insert(entry.name.clone());
+            }
+        }
+    }
+
+    /// Entries to act on: the marked ones, or the highlighted one if none are marked.
+    fn sync_targets(&self) -> Vec<SyncEntry> {
+        if self.sync_marked.is_empty() {
+            self.sync_list_state
+                .selected()
+                .and_then(|idx| self.sync_entries.get(idx))
+                .cloned()
+                .into_iter()
+                .collect()
+        } else {
+            self.sync_entries
+                .iter()
+                .filter(|e| self.sync_marked.contains(&e.name))
+                .cloned()
+                .collect()
+        }
+    }
+
+    fn apply_sync(&mut self, push: bool) -> Vec<Effect> {
+        let Some(namespace) = self.namespace.as_ref().cloned() else {
+            return Vec::new();
+        };
+        let entries = self.sync_targets();
+        self.sync_marked.clear();
+        vec![Effect::ApplySync {
+            namespace,
+            entries,
+            push,
+        }]
+    }
+
+    fn sync_applied(
+        &mut self,
+        push: bool,
+        done: usize,
+        mut errors: Vec<String>,
+        refreshed: Result<Vec<SyncEntry>, String>,
+    ) {
+        match refreshed {
+            Ok(entries) => self.set_sync_entries(entries),
+            Err(e) => errors.push(format!("refresh: {}", e)),
+        }
+        let verb = if push { "Pushed" } else { "Pulled" };
+        self.status_message = if errors.is_empty() {
+            format!("{} {} search(es).", verb, done)
+        } else {
+            format!("{} {}; failed: {}", verb, done, errors.join("; "))
+        };
+    }
+
+    fn kill_search(&mut self) -> Vec<Effect> {
+        self.current_job_sid
+            .clone()
+            .map(|sid| Effect::KillJob { sid })
+            .into_iter()
+            .collect()
+    }
+
+    fn job_killed(&mut self, sid: String, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.status_message = String::from("Job killed.");
+                if self.current_job_sid.as_deref() == Some(&sid) {
+                    self.current_job_sid = None;
+                    self.current_job_status = None;
+                }
+            }
+            Err(e) => self.status_message = format!("Failed to kill job: {}", e),
+        }
+    }
+
+    fn clear_results(&mut self) {
+        self.search_results.clear();
+        self.results_fetched = false;
+        self.current_job_sid = None;
+        self.current_job_status = None;
+        self.scroll_offset = 0;
+        self.status_message = String::from("Results cleared.");
+    }
+
+    fn open_in_editor(&mut self) -> Vec<Effect> {
+        if self.search_results.is_empty() {
+            self.status_message = String::from("No results to open.");
+            return Vec::new();
+        }
+
+        let mut temp_dir = std::env::temp_dir();
+        temp_dir.push("splunk_results.json");
+        let file_path = temp_dir.to_str().unwrap().to_string();
+
+        self.status_message = format!("Saved to {}. Opening...", file_path);
+        vec![Effect::OpenEditor {
+            path: file_path,
+            contents: serde_json::to_string_pretty(&self.search_results).unwrap_or_default(),
+            reload_query: false,
+        }]
+    }
+
+    fn format_input(&mut self) {
+        let formatted = format_spl(&self.input);
+        if formatted == self.input {
+            self.status_message = String::from("Query already formatted.");
+            return;
+        }
+        self.replace_input(formatted);
+        self.status_message = String::from("Query formatted.");
+    }
+
+    fn open_query_in_editor(&mut self) -> Vec<Effect> {
+        let mut temp_dir = std::env::temp_dir();
+        temp_dir.push("splunk_query.spl");
+        let file_path = temp_dir.to_str().unwrap().to_string();
+
+        self.status_message = "Editing query in external editor...".to_string();
+        vec![Effect::OpenEditor {
+            path: file_path,
+            contents: self.input.clone(),
+            reload_query: true,
+        }]
+    }
+
+    fn open_job_url(&mut self) -> Vec<Effect> {
+        if let Some(sid) = &self.current_job_sid {
+            let url = shareable_url(&self.base_url, self.namespace.as_ref(), sid);
+            if url.starts_with("http") {
+                self.status_message = String::from("Opened URL in browser.");
+                return vec![Effect::OpenUrl(url)];
             } else {
-                self.cached_detail = ratatui::text::Text::from("Select an event...");
+                self.status_message = String::from("Invalid URL.");
+            }
+        } else {
+            self.status_message = String::from("No active job URL.");
+        }
+        Vec::new()
+    }
+
+    fn scroll_down(&mut self) {
+        if !self.search_results.is_empty() {
+            self.scroll_offset = self.scroll_offset.saturating_add(1);
+        }
+    }
+
+    fn scroll_down_fast(&mut self) {
+        if !self.search_results.is_empty() {
+            self.scroll_offset = self.scroll_offset.saturating_add(10);
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        if !self.search_results.is_empty() {
+            self.scroll_offset = self.scroll_offset.saturating_sub(1);
+        }
+    }
+
+    fn scroll_up_fast(&mut self) {
+        if !self.search_results.is_empty() {
+            self.scroll_offset = self.scroll_offset.saturating_sub(10);
+        }
+    }
+
+    /// Which series of `data` are shown, in series order.
+    fn move_chart_cursor(&mut self, forward: bool) {
+        let len = self.search_results.len();
+        self.chart_cursor = if forward {
+            (self.chart_cursor + 1).min(len.saturating_sub(1))
+        } else {
+            self.chart_cursor
+                .saturating_sub(1)
+                .min(len.saturating_sub(1))
+        };
+    }
+
+    /// Shows or hides the `n`th series (1-based, as listed in the legend).
+    fn toggle_chart_series(&mut self, n: usize) {
+        let Some(data) = ChartData::from_results(&self.search_results) else {
+            return;
+        };
+        let Some(series) = data.series.get(n.wrapping_sub(1)) else {
+            return;
+        };
+        if !self.chart_hidden.remove(&series.name) {
+            self.chart_hidden.insert(series.name.clone());
+        }
+    }
+
+    fn apply_theme(&mut self, theme_name: &str) {
+        let Some(def) = self.themes.iter().find(|t| t.name == theme_name).cloned() else {
+            self.theme = AppTheme::default_theme();
+            self.status_message = format!("Unknown theme '{}'.", theme_name);
+            return;
+        };
+        self.theme = def.colors;
+        match def.syntax {
+            SyntaxTheme::Bundled(name) => match self.theme_set.themes.get(&name) {
+                Some(t) => self.syntax_theme = t.clone(),
+                None => self.status_message = format!("No bundled syntax theme '{}'.", name),
+            },
+            SyntaxTheme::File(path) => match ThemeSet::get_theme(&path) {
+                Ok(t) => self.syntax_theme = t,
+                Err(e) => self.status_message = format!("{}: {}", path.display(), e),
+            },
+        }
+        self.update_detail_view();
+    }
+
+    fn toggle_theme_selector(&mut self) {
+        self.input_mode = InputMode::ThemeSelect;
+        let current = self.themes.iter().position(|t| t.colors == self.theme);
+        self.theme_list_state.select(Some(current.unwrap_or(0)));
+        self.status_message = String::from("Select theme (Up/Down/Enter), Esc to cancel.");
+    }
+
+    fn initiate_app_select(&mut self) -> Vec<Effect> {
+        self.status_message = String::from("Fetching apps...");
+        vec![Effect::ListApps]
+    }
+
+    fn open_app_select(&mut self, result: Result<Vec<SplunkApp>, String>) {
+        match result {
+            Ok(apps) => {
+                if apps.is_empty() {
+                    self.status_message = String::from("No apps available.");
+                    return;
+                }
+                let current = self.namespace.as_ref().map(|ns| ns.app.clone());
+                let selected = current
+                    .and_then(|name| apps.iter().position(|a| a.name == name))
+                    .unwrap_or(0);
+                self.app_options = apps;
+                self.app_list_state.select(Some(selected));
+                self.input_mode = InputMode::AppSelect;
+                self.status_message =
+                    String::from("Select app (Up/Down/Enter), x for no app, Esc to cancel.");
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to list apps: {}", e);
+            }
+        }
+    }
+
+    fn select_app(&mut self) -> Vec<Effect> {
+        let Some(app) = self
+            .app_list_state
+            .selected()
+            .and_then(|idx| self.app_options.get(idx))
+            .cloned()
+        else {
+            return Vec::new();
+        };
+
+        let owner = self
+            .namespace
+            .as_ref()
+            .map(|ns| ns.owner.clone())
+            .unwrap_or_else(|| "nobody".to_string());
+        self.namespace = Some(Namespace {
+            owner,
+            app: app.name.clone(),
+        });
+        self.input_mode = InputMode::Normal;
+        self.status_message = format!("Switched to app '{}' ({}).", app.name, app.label);
+        vec![
+            Effect::SetNamespace(self.namespace.clone()),
+            Effect::SaveApp {
+                profile: self.profile.clone(),
+                app: Some(app.name),
+            },
+        ]
+    }
+
+    /// Goes back to the token user's default app context.
+    fn clear_app(&mut self) -> Vec<Effect> {
+        self.namespace = None;
+        self.input_mode = InputMode::Normal;
+        self.status_message = String::from("Cleared the app namespace.");
+        vec![
+            Effect::SetNamespace(None),
+            Effect::SaveApp {
+                profile: self.profile.clone(),
+                app: None,
+            },
+        ]
+    }
+
+    fn app_saved(&mut self, app: Option<String>, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.status_message = match app {
+                Some(app) => format!("Switched to app '{}' (not saved: {}).", app, e),
+                None => format!("Cleared the app namespace (not saved: {}).", e),
+            };
+        }
+    }
+
+    fn initiate_save_search(&mut self) {
+        if self.input.trim().is_empty() {
+            self.status_message = String::from("Cannot save empty search.");
+            return;
+        }
+
+        if let Some(name) = &self.current_saved_search_name {
+            self.input_mode = InputMode::ConfirmOverwrite;
+            self.status_message = format!("Overwrite saved search '{}'? (y/n/r)", name);
+        } else {
+            self.input_mode = InputMode::SaveSearch;
+            self.save_search_name.clear();
+            self.status_message =
+                String::from("Enter name for saved search (Enter to save, Esc to cancel):");
+        }
+    }
+
+    /// The current query and view to save under `name`; the executor keeps
+    /// the description, tags and folder already stored for it.
+    fn current_search(&self, name: String) -> SavedSearch {
+        let mut meta = SearchMetadata {
+            earliest: self.time_range.earliest.clone(),
+            latest: self.time_range.latest.clone(),
+            columns: self.table_columns.clone(),
+            ..SearchMetadata::default()
+        };
+        meta.view_mode = Some(
+            match self.view_mode {
+                ViewMode::Table => "table",
+                ViewMode::RawEvents => "raw",
+                ViewMode::Chart => "chart",
+                ViewMode::Stat => "stat",
+            }
+            .to_string(),
+        );
+        SavedSearch {
+            name,
+            query: self.input.clone(),
+            meta,
+        }
+    }
+
+    fn save_current_search(&mut self) -> Vec<Effect> {
+        let name = self.save_search_name.trim().to_string();
+        if name.is_empty() {
+            self.status_message = String::from("Name cannot be empty.");
+            return Vec::new();
+        }
+        vec![Effect::SaveSearch {
+            search: self.current_search(name),
+            overwrite: false,
+        }]
+    }
+
+    fn overwrite_current_search(&mut self) -> Vec<Effect> {
+        self.current_saved_search_name
+            .clone()
+            .map(|name| Effect::SaveSearch {
+                search: self.current_search(name),
+                overwrite: true,
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn search_saved(&mut self, overwrite: bool, result: Result<SavedSearch, String>) {
+        match result {
+            Ok(search) => {
+                self.status_message = if overwrite {
+                    format!("Search '{}' overwritten.", search.name)
+                } else {
+                    format!("Search saved as '{}'.", search.name)
+                };
+                self.current_saved_search_name = Some(search.name);
+                self.current_saved_search_meta = Some(search.meta);
+                self.input_mode = InputMode::Normal;
+            }
+            Err(e) => self.status_message = format!("Failed to save search: {}", e),
+        }
+    }
+
+    /// Drops the link between the editor and a saved search.
+    fn forget_saved_search(&mut self) {
+        self.current_saved_search_name = None;
+        self.current_saved_search_meta = None;
+    }
+
+    fn initiate_load_search(&mut self) -> Vec<Effect> {
+        vec![Effect::LoadSavedSearches]
+    }
+
+    /// Opens the Load Search finder, or refreshes it after a change.
+    fn saved_searches_loaded(&mut self, result: Result<Vec<SavedSearch>, String>) {
+        let searches = match result {
+            Ok(searches) => searches,
+            Err(e) => {
+                self.status_message = format!("Failed to list saved searches: {}", e);
+                return;
+            }
+        };
+        if matches!(self.input_mode, InputMode::LoadSearch) {
+            self.saved_searches = searches;
+            self.rebuild_load_rows();
+            return;
+        }
+        if searches.is_empty() {
+            self.status_message = String::from("No saved searches found.");
+            return;
+        }
+        self.saved_searches = searches;
+        self.input_mode = InputMode::LoadSearch;
+        self.load_query.clear();
+        self.load_action = None;
+        self.rebuild_load_rows();
+        self.status_message = String::from(
+            "Type to filter | Enter load | Tab group | ^R rename | ^D duplicate | ^X delete | Esc cancel",
+        );
+    }
+
+    fn cycle_load_grouping(&mut self) {
+        self.load_group_by = match self.load_group_by {
+            LoadGroupBy::None => LoadGroupBy::Folder,
+            LoadGroupBy::Folder => LoadGroupBy::Tag,
+            LoadGroupBy::Tag => LoadGroupBy::None,
+        };
+        self.rebuild_load_rows();
+        self.status_message = format!("Grouping saved searches by {:?}.", self.load_group_by);
+    }
+
+    fn rebuild_load_rows(&mut self) {
+        if !self.load_query.trim().is_empty() {
+            self.rank_load_rows();
+            return;
+        }
+
+        let mut groups: std::collections::BTreeMap<String, Vec<usize>> =
+            std::collections::BTreeMap::new();
+        for (idx, search) in self.saved_searches.iter().enumerate() {
+            let keys = match self.load_group_by {
+                LoadGroupBy::None => vec![String::new()],
+                LoadGroupBy::Folder => {
+                    vec![search
+                        .meta
+                        .folder
+                        .clone()
+                        .unwrap_or_else(|| "(no folder)".into())]
+                }
+                LoadGroupBy::Tag if search.meta.tags.is_empty() => vec!["(untagged)".into()],
+                LoadGroupBy::Tag => search.meta.tags.clone(),
+            };
+            for key in keys {
+                groups.entry(key).or_default().push(idx);
+            }
+        }
+
+        self.load_rows.clear();
+        for (group, indices) in groups {
+            if self.load_group_by != LoadGroupBy::None {
+                self.load_rows.push(LoadRow::Header(group));
+            }
+            self.load_rows
+                .extend(indices.into_iter().map(LoadRow::Search));
+        }
+
+        let first = self
+            .load_rows
+            .iter()
+            .position(|row| matches!(row, LoadRow::Search(_)));
+        self.saved_search_list_state.select(first);
+    }
+
+    /// Flat list of searches matching `load_query`, best first. Name matches
+    /// outrank tag matches, which outrank matches in the query body.
+    fn rank_load_rows(&mut self) {
+        let mut ranked: Vec<(i64, usize)> = self
+            .saved_searches
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, search)| {
+                let name = fuzzy_match(&self.load_query, &search.name).map(|(s, _)| s + 40);
+                let tags = search
+                    .meta
+                    .tags
+                    .iter()
+                    .filter_map(|t| fuzzy_match(&self.load_query, t).map(|(s, _)| s + 20))
+                    .max();
+                let body = fuzzy_match(&self.load_query, &search.query).map(|(s, _)| s);
+                [name, tags, body]
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .map(|score| (score, idx))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        self.load_rows = ranked
+            .into_iter()
+            .map(|(_, idx)| LoadRow::Search(idx))
+            .collect();
+        self.saved_search_list_state
+            .select((!self.load_rows.is_empty()).then_some(0));
+    }
+
+    fn begin_load_action(&mut self, action: LoadAction) {
+        let Some(name) = self.selected_saved_search().map(|s| s.name.clone()) else {
+            return;
+        };
+        self.status_message = match &action {
+            LoadAction::Rename(_) => format!("Rename '{}' to (Enter to confirm):", name),
+            LoadAction::Duplicate(_) => format!("Duplicate '{}' as (Enter to confirm):", name),
+            LoadAction::ConfirmDelete => format!("Delete '{}'? (y/n)", name),
+        };
+        self.load_action = Some(match action {
+            LoadAction::Rename(_) => LoadAction::Rename(name),
+            LoadAction::Duplicate(_) => LoadAction::Duplicate(format!("{} copy", name)),
+            LoadAction::ConfirmDelete => LoadAction::ConfirmDelete,
+        });
+    }
+
+    fn apply_load_action(&mut self) -> Vec<Effect> {
+        let Some(name) = self.selected_saved_search().map(|s| s.name.clone()) else {
+            self.load_action = None;
+            return Vec::new();
+        };
+        let change = match self.load_action.take() {
+            Some(LoadAction::Rename(new)) => SearchChange::Rename(new.trim().to_string()),
+            Some(LoadAction::Duplicate(new)) => SearchChange::Duplicate(new.trim().to_string()),
+            Some(LoadAction::ConfirmDelete) => SearchChange::Delete,
+            None => return Vec::new(),
+        };
+        vec![Effect::ChangeSavedSearch { name, change }]
+    }
+
+    /// Reports a rename, duplicate or delete and reloads the finder.
+    fn saved_search_changed(
+        &mut self,
+        name: String,
+        change: SearchChange,
+        result: Result<(), String>,
+    ) -> Vec<Effect> {
+        let current = self.current_saved_search_name.as_deref() == Some(name.as_str());
+        self.status_message = match (result, change) {
+            (Ok(()), SearchChange::Rename(new)) => {
+                let msg = format!("Renamed '{}' to '{}'.", name, new);
+                if current {
+                    self.current_saved_search_name = Some(new);
+                }
+                msg
+            }
+            (Ok(()), SearchChange::Duplicate(new)) => {
+                format!("Duplicated '{}' as '{}'.", name, new)
+            }
+            (Ok(()), SearchChange::Delete) => {
+                if current {
+                    self.forget_saved_search();
+                }
+                format!("Deleted '{}'.", name)
+            }
+            (Err(e), _) => format!("Failed: {}", e),
+        };
+        vec![Effect::LoadSavedSearches]
+    }
+
+    fn selected_saved_search(&self) -> Option<&SavedSearch> {
+        match self
+            .saved_search_list_state
+            .selected()
+            .and_then(|idx| self.load_rows.get(idx))
+        {
+            Some(LoadRow::Search(idx)) => self.saved_searches.get(*idx),
+            _ => None,
+        }
+    }
+
+    fn load_selected_search(&mut self) {
+        if let Some(search) = self.selected_saved_search().cloned() {
+            self.apply_saved_search(search);
+        }
+    }
+
+    /// Loads a saved search's query and its stored time range, view and columns.
+    fn apply_saved_search(&mut self, search: SavedSearch) {
+        self.replace_input(search.query);
+        self.time_range = TimeRange {
+            earliest: search.meta.earliest.clone(),
+            latest: search.meta.latest.clone(),
+        };
+        match search.meta.view_mode.as_deref() {
+            Some("raw") => self.view_mode = ViewMode::RawEvents,
+            Some("table") => self.view_mode = ViewMode::Table,
+            Some("chart") => self.view_mode = ViewMode::Chart,
+            Some("stat") => self.view_mode = ViewMode::Stat,
+            _ => {}
+        }
+        self.table_columns = search.meta.columns.clone();
+        self.current_saved_search_name = Some(search.name.clone());
+        self.current_saved_search_meta = Some(search.meta);
+        self.input_mode = InputMode::Normal;
+        self.status_message = format!("Loaded search '{}'.", search.name);
+        if !template::tokens(&self.input).is_empty() {
+            self.open_token_form();
+        }
+    }
+
+    /// Moves the Load Search selection by `step`, wrapping and skipping group headers.
+    fn list_step(&mut self, forward: bool) {
+        let len = self.load_rows.len();
+        if len == 0 {
+            return;
+        }
+        let mut i = self.saved_search_list_state.selected().unwrap_or(0);
+        for _ in 0..len {
+            i = if forward {
+                (i + 1) % len
+            } else {
+                (i + len - 1) % len
+            };
+            if matches!(self.load_rows[i], LoadRow::Search(_)) {
+                break;
+            }
+        }
+        self.saved_search_list_state.select(Some(i));
+    }
+
+    fn list_next(&mut self) {
+        self.list_step(true);
+    }
+
+    fn list_previous(&mut self) {
+        self.list_step(false);
+    }
+
+    // --- Cursor Logic ---
+    fn clamp_cursor(&mut self) {
+        if self.cursor_position > self.input.len() {
+            self.cursor_position = self.input.len();
+        }
+    }
+
+    fn move_cursor_up(&mut self) {
+        // Find the last newline before cursor.
+        let cursor_byte_idx = self.cursor_position;
+        let text_before = &self.input[..cursor_byte_idx];
+        let last_newline = text_before.rfind('\n');
+
+        if let Some(last_nl_idx) = last_newline {
+            // We are not on the first line.
+            let col = cursor_byte_idx - (last_nl_idx + 1);
+
+            // Find the newline BEFORE that one to identify the previous line.
+            let text_before_prev_line = &self.input[..last_nl_idx];
+            let prev_line_start = text_before_prev_line
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or(0);
+
+            let prev_line_len = last_nl_idx - prev_line_start;
+            let new_col = col.min(prev_line_len);
+
+            self.cursor_position = prev_line_start + new_col;
+        }
+    }
+
+    fn move_cursor_down(&mut self) {
+        let cursor_byte_idx = self.cursor_position;
+
+        // Find current line start and end
+        let text_before = &self.input[..cursor_byte_idx];
+        let line_start = text_before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let col = cursor_byte_idx - line_start;
+
+        // Find next newline
+        let text_after = &self.input[cursor_byte_idx..];
+        if let Some(next_nl_rel) = text_after.find('\n') {
+            let next_nl_idx = cursor_byte_idx + next_nl_rel;
+            let next_line_start = next_nl_idx + 1;
+
+            // Find end of next line
+            let text_after_next_line = &self.input[next_line_start..];
+            let next_line_end_rel = text_after_next_line
+                .find('\n')
+                .unwrap_or(text_after_next_line.len());
+            let next_line_len = next_line_end_rel;
+
+            let new_col = col.min(next_line_len);
+            self.cursor_position = next_line_start + new_col;
+        }
+    }
+
+    /// Replaces the whole editor buffer (recall, load, external editor) as
+    /// one undoable step.
+    fn open_dashboard_prompt(&mut self) {
+        self.input_mode = InputMode::DashboardOpen;
+        self.status_message =
+            String::from("Dashboard name or path to a Simple XML/JSON file. Enter to open.");
+    }
+
+    /// Loads `dashboard_source` from a local file or, failing that, from the
+    /// server.
+    fn open_dashboard(&mut self) -> Vec<Effect> {
+        let source = self.dashboard_source.trim().to_string();
+        self.input_mode = InputMode::Normal;
+        if source.is_empty() {
+            return Vec::new();
+        }
+        vec![Effect::LoadDashboard { source }]
+    }
+
+    /// Shows the loaded dashboard and queues every panel's search.
+    fn dashboard_loaded(&mut self, source: String, result: Result<String, String>) {
+        let dashboard = match result.and_then(|text| dashboard::parse(&text)) {
+            Ok(dashboard) => dashboard,
+            Err(e) => {
+                error!("Failed to open dashboard {}: {}", source, e);
+                self.status_message = format!("Failed to open dashboard '{}': {}", source, e);
+                return;
+            }
+        };
+        if dashboard.panels.is_empty() {
+            self.status_message = format!("Dashboard '{}' has no panels with searches.", source);
+            return;
+        }
+
+        let input_edits: Vec<String> = dashboard.inputs.iter().map(|i| i.default.clone()).collect();
+        self.dashboard = Some(DashboardView {
+            generation: 0,
+            panels: vec![PanelState::default(); dashboard.panels.len()],
+            selected: 0,
+            values: Default::default(),
+            input_edits,
+            input_selected: 0,
+            dashboard,
+        });
+        self.apply_dashboard_inputs();
+        self.input_mode = InputMode::Dashboard;
+        self.status_message = String::from(
+            "Arrows/hjkl select | i inputs | r refresh | Enter open search | Esc close",
+        );
+    }
+
+    /// Takes the edited input values and re-runs every panel with them.
+    fn apply_dashboard_inputs(&mut self) -> Vec<Effect> {
+        let Some(view) = self.dashboard.as_mut() else {
+            return Vec::new();
+        };
+        view.values = view
+            .dashboard
+            .inputs
+            .iter()
+            .zip(&view.input_edits)
+            .map(|(input, value)| (input.token.clone(), value.clone()))
+            .collect();
+        self.refresh_dashboard()
+    }
+
+    /// Queues every panel and cancels the searches of the previous run.
+    fn refresh_dashboard(&mut self) -> Vec<Effect> {
+        let Some(view) = self.dashboard.as_mut() else {
+            return Vec::new();
+        };
+        view.generation += 1;
+        for panel in &mut view.panels {
+            panel.queued = true;
+            panel.running = false;
+        }
+        view.cancel_searches()
+    }
+
+    fn close_dashboard(&mut self) -> Vec<Effect> {
+        self.dashboard
+            .take()
+            .map(|mut view| view.cancel_searches())
+            .unwrap_or_default()
+    }
+
+    fn panel_started(
+        &mut self,
+        index: usize,
+        generation: u64,
+        result: Result<String, String>,
+    ) -> Vec<Effect> {
+        let sid = match result {
+            Ok(sid) => sid,
+            Err(e) => {
+                self.finish_dashboard_panel(index, generation, Err(e));
+                return Vec::new();
+            }
+        };
+        match self.dashboard.as_mut() {
+            Some(view) if view.generation == generation => {
+                view.panels[index].sid = Some(sid.clone());
+                vec![Effect::WaitForPanel {
+                    index,
+                    generation,
+                    sid,
+                }]
+            }
+            // Refreshed or closed while the job was being created.
+            _ => vec![Effect::CancelJob { sid }],
+        }
+    }
+
+    /// Panels to dispatch now: queued ones and those whose refresh interval
+    /// has passed. Panels whose tokens can't be resolved get the error instead.
+    fn due_dashboard_panels(&mut self) -> Vec<(usize, u64, String, TimeRange)> {
+        let Some(view) = self.dashboard.as_mut() else {
+            return Vec::new();
+        };
+        let mut due = Vec::new();
+        for (i, (panel, state)) in view
+            .dashboard
+            .panels
+            .iter()
+            .zip(view.panels.iter_mut())
+            .enumerate()
+        {
+            let refresh_due = match (panel.refresh, state.last_run) {
+                (Some(interval), Some(last)) => last.elapsed() >= interval,
+                _ => false,
+            };
+            if state.running || !(state.queued || refresh_due) {
+                continue;
+            }
+            state.queued = false;
+            match panel.resolve(&view.values) {
+                Ok((query, range)) => {
+                    state.running = true;
+                    due.push((i, view.generation, query, range));
+                }
+                Err(e) => {
+                    state.error = Some(e);
+                    state.last_run = Some(std::time::Instant::now());
+                }
+            }
+        }
+        due
+    }
+
+    fn finish_dashboard_panel(
+        &mut self,
+        index: usize,
+        generation: u64,
+        outcome: Result<Vec<Value>, String>,
+    ) {
+        let Some(view) = self.dashboard.as_mut() else {
+            return;
+        };
+        if view.generation != generation {
+            return;
+        }
+        let state = &mut view.panels[index];
+        state.running = false;
+        state.sid = None;
+        state.last_run = Some(std::time::Instant::now());
+        match outcome {
+            Ok(results) => {
+                state.results = results;
+                state.error = None;
+            }
+            Err(e) => state.error = Some(e),
+        }
+    }
+
+    /// Moves the panel selection: `step` through the panels in order, or
+    /// `rows` up/down to the first panel of another row.
+    fn move_dashboard_selection(&mut self, step: isize, rows: isize) {
+        let Some(view) = self.dashboard.as_mut() else {
+            return;
+        };
+        let panels = &view.dashboard.panels;
+        let last = panels.len() as isize - 1;
+        if rows == 0 {
+            view.selected = (view.selected as isize + step).clamp(0, last) as usize;
+            return;
+        }
+        let current = panels[view.selected].row as isize;
+        let target = (current + rows).clamp(0, panels[last as usize].row as isize) as usize;
+        if let Some(i) = panels.iter().position(|p| p.row == target) {
+            view.selected = i;
+        }
+    }
+
+    /// Runs the selected panel's search in the main view.
+    fn open_dashboard_panel(&mut self) -> Vec<Effect> {
+        let Some(view) = &self.dashboard else {
+            return Vec::new();
+        };
+        let panel = &view.dashboard.panels[view.selected];
+        match panel.resolve(&view.values) {
+            Ok((query, range)) => {
+                match panel.kind {
+                    PanelKind::Chart(kind) => {
+                        self.view_mode = ViewMode::Chart;
+                        self.chart_kind = kind;
+                    }
+                    PanelKind::Single => self.view_mode = ViewMode::Stat,
+                    PanelKind::Table | PanelKind::Events => self.view_mode = ViewMode::Table,
+                }
+                let mut effects = self.close_dashboard();
+                self.forget_saved_search();
+                self.table_columns.clear();
+                self.time_range = range;
+                self.replace_input(query);
+                effects.extend(self.perform_search());
+                effects
+            }
+            Err(e) => {
+                self.status_message = e;
+                Vec::new()
+            }
+        }
+    }
+
+    fn replace_input(&mut self, text: String) {
+        self.edit_history.record(&self.input, self.cursor_position);
+        self.input = text;
+        self.cursor_position = self.input.len();
+        self.standard.reset();
+    }
+
+    fn handle_standard_key(&mut self, key: event::KeyEvent) {
+        self.clamp_cursor();
+        let text_len = self.input.len();
+        let mut buf = Buffer {
+            text: &mut self.input,
+            cursor: &mut self.cursor_position,
+            history: &mut self.edit_history,
+        };
+        self.standard.handle_key(key, &mut buf);
+        if self.input.len() != text_len {
+            self.history_cursor = None;
+        }
+    }
+
+    fn handle_vim_key(&mut self, key: event::KeyEvent) -> VimOutcome {
+        let EditorMode::Vim(mut state) = self.editor_mode else {
+            return VimOutcome::Handled;
+        };
+        let before = state;
+        let text_len = self.input.len();
+        let mut buf = Buffer {
+            text: &mut self.input,
+            cursor: &mut self.cursor_position,
+            history: &mut self.edit_history,
+        };
+        let outcome = self.vim.handle_key(key, &mut state, &mut buf);
+        self.editor_mode = EditorMode::Vim(state);
+        if self.input.len() != text_len {
+            self.history_cursor = None;
+        }
+        if state != before {
+            self.status_message = state.label().to_string();
+        }
+        outcome
+    }
+
+    fn toggle_vim_mode(&mut self) {
+        self.editor_mode = match self.editor_mode {
+            EditorMode::Standard => EditorMode::Vim(VimState::Normal),
+            EditorMode::Vim(_) => EditorMode::Standard,
+        };
+        self.vim.reset();
+        self.standard.reset();
+        // Ensure cursor is style updated by next render
+    }
+
+    /// Applies `action` and returns the effects it asks for. Nothing here
+    /// waits on the network or the terminal.
+    pub fn update(&mut self, action: Action) -> Vec<Effect> {
+        match action {
+            Action::Key(key) => return self.handle_key(key),
+            Action::Mouse(mouse) => self.handle_mouse(mouse),
+            Action::Tick => return self.tick(),
+            Action::QueryEdited(content) => {
+                self.replace_input(content);
+                self.status_message = String::from("Query updated from editor.");
+            }
+            Action::Copied {
+                what,
+                bytes,
+                result,
+            } => self.copied(what, bytes, result),
+            Action::CopyCommandFailed { what, text, error } => {
+                return self.copy_command_failed(what, text, error)
+            }
+            Action::SearchCreated {
+                query,
+                range,
+                result,
+            } => return self.search_created(query, range, result),
+            Action::JobStatus { sid, result } => return self.job_status(sid, result),
+            Action::ResultsLoaded { sid, result } => return self.results_loaded(sid, result),
+            Action::TimelineLoaded { sid, buckets } => {
+                if self.current_job_sid.as_deref() == Some(&sid) {
+                    self.timeline = buckets;
+                }
+            }
+            Action::ComparisonLoaded { sid, results } => {
+                if self.current_job_sid.as_deref() == Some(&sid) {
+                    self.stat_compare = Some(results);
+                }
+            }
+            Action::PanelStarted {
+                index,
+                generation,
+                result,
+            } => return self.panel_started(index, generation, result),
+            Action::PanelLoaded {
+                index,
+                generation,
+                result,
+            } => self.finish_dashboard_panel(index, generation, result),
+            Action::JobKilled { sid, result } => self.job_killed(sid, result),
+            Action::JobReattached { entry, result } => self.job_reattached(entry, result),
+            Action::ServerSearchesLoaded(result) => self.open_server_searches(result),
+            Action::SavedSearchDispatched { saved, result } => {
+                return self.saved_search_dispatched(saved, result)
+            }
+            Action::LastRunFound { saved, result } => self.last_run_found(saved, result),
+            Action::ScheduleSet {
+                name,
+                scheduled,
+                result,
+            } => self.schedule_set(name, scheduled, result),
+            Action::SyncLoaded { app, result } => self.open_sync(app, result),
+            Action::SyncApplied {
+                push,
+                done,
+                errors,
+                refreshed,
+            } => self.sync_applied(push, done, errors, refreshed),
+            Action::AppsLoaded(result) => self.open_app_select(result),
+            Action::DashboardLoaded { source, result } => self.dashboard_loaded(source, result),
+            Action::AppSaved { app, result } => self.app_saved(app, result),
+            Action::ProfileLoaded { name, result } => return self.switch_profile(name, result),
+            Action::SearchSaved { overwrite, result } => self.search_saved(overwrite, result),
+            Action::SavedSearchesLoaded(result) => self.saved_searches_loaded(result),
+            Action::SavedSearchChanged {
+                name,
+                change,
+                result,
+            } => return self.saved_search_changed(name, change, result),
+            Action::Exported {
+                path,
+                rows,
+                bytes,
+                result,
+            } => {
+                self.status_message = match result {
+                    Ok(()) => format!(
+                        "Exported {} rows to {} ({} bytes).",
+                        rows,
+                        path.display(),
+                        bytes
+                    ),
+                    Err(e) => format!("Export to {} failed: {}", path.display(), e),
+                };
+            }
+        }
+        Vec::new()
+    }
+
+    /// Polls the running job and dispatches due dashboard panels.
+    fn tick(&mut self) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        if let Some(sid) = self
+            .current_job_sid
+            .clone()
+            .filter(|_| !self.results_fetched && !self.is_status_fetching)
+        {
+            self.is_status_fetching = true;
+            effects.push(Effect::PollJob { sid });
+        }
+        // Dashboard panels run concurrently, each in its own task.
+        for (index, generation, query, range) in self.due_dashboard_panels() {
+            effects.push(Effect::RunPanel {
+                index,
+                generation,
+                query,
+                range,
+            });
+        }
+        effects
+    }
+
+    /// Fetches the results once the job is done. Replies for a job that is no
+    /// longer current are dropped.
+    fn job_status(&mut self, sid: String, result: Result<JobStatus, String>) -> Vec<Effect> {
+        if self.current_job_sid.as_deref() != Some(&sid) {
+            self.is_status_fetching = false;
+            return Vec::new();
+        }
+        match result {
+            Ok(status) if status.is_done => {
+                self.current_job_status = Some(status);
+                self.status_message = String::from("Job done. Fetching results...");
+                vec![Effect::FetchResults { sid }]
+            }
+            Ok(status) => {
+                self.status_message =
+                    format!("Job running... Dispatched: {}", status.dispatch_state);
+                self.current_job_status = Some(status);
+                self.is_status_fetching = false;
+                Vec::new()
+            }
+            Err(_) => {
+                self.is_status_fetching = false;
+                Vec::new()
+            }
+        }
+    }
+
+    /// Shows the results and asks for the timeline and, for single-value
+    /// panels, the comparison search.
+    fn results_loaded(&mut self, sid: String, result: Result<Vec<Value>, String>) -> Vec<Effect> {
+        self.is_status_fetching = false;
+        if self.current_job_sid.as_deref() != Some(&sid) {
+            return Vec::new();
+        }
+        match result {
+            Ok(results) => {
+                self.search_results = results;
+                self.results_fetched = true;
+                let record = self.record_history_completion();
+                self.status_message = format!("Loaded {} results.", self.search_results.len());
+                if self.view_mode == ViewMode::Table {
+                    self.update_detail_view();
+                }
+                let comparison = self.prepare_stat_view(&sid);
+                let mut effects = vec![Effect::FetchTimeline { sid: sid.clone() }];
+                effects.extend(record);
+                if let Some((query, range)) = comparison {
+                    effects.push(Effect::RunComparison { sid, query, range });
+                }
+                effects
+            }
+            Err(e) => {
+                self.status_message = format!("Failed to fetch results: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse_event: event::MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown => {
+                if let ViewFocus::Search = self.view_focus {
+                    let line_count = self.input.lines().count();
+                    let max_scroll = line_count.saturating_sub(3); // 3 lines visible (header height 5)
+                    if self.input_scroll < max_scroll as u16 {
+                        self.input_scroll = self.input_scroll.saturating_add(1);
+                    }
+                } else {
+                    self.move_selection(true);
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                if let ViewFocus::Search = self.view_focus {
+                    self.input_scroll = self.input_scroll.saturating_sub(1);
+                } else {
+                    self.move_selection(false);
+                }
+            }
+            _ => {}
+        }
+        // Dragging across the timeline extends the selection.
+        if let (MouseEventKind::Drag(MouseButton::Left), InputMode::Timeline) =
+            (mouse_event.kind, &self.input_mode)
+        {
+            let area = self.timeline_area;
+            let column = mouse_event.column.saturating_sub(area.x + 1) as usize;
+            self.timeline_cursor = column.min(self.timeline_columns.len().saturating_sub(1));
+        }
+        if let MouseEventKind::Down(MouseButton::Left) = mouse_event.kind {
+            let col = mouse_event.column;
+            let row = mouse_event.row;
+
+            if is_inside(self.search_area, col, row) {
+                self.view_focus = ViewFocus::Search;
+                self.input_mode = InputMode::Editing;
+
+                // Calculate cursor position from mouse click
+                let rel_x = col.saturating_sub(self.search_area.x + 1); // +1 for border
+                let rel_y = row.saturating_sub(self.search_area.y + 1); // +1 for border
+
+                let target_line_idx = (rel_y + self.input_scroll) as usize;
+                let target_col_idx = (rel_x + self.input_scroll_x) as usize;
+
+                let lines: Vec<&str> = self.input.lines().collect();
+                if target_line_idx < lines.len() {
+                    let line = lines[target_line_idx];
+                    // Calculate byte offset up to this line
+                    let mut offset = 0;
+                    for line in lines.iter().take(target_line_idx) {
+                        offset += line.len() + 1; // +1 for newline
+                    }
+
+                    // Add column offset (clamped to line length)
+                    let col_bytes = line
+                        .chars()
+                        .take(target_col_idx)
+                        .map(|c| c.len_utf8())
+                        .sum::<usize>();
+                    offset += col_bytes.min(line.len());
+
+                    self.cursor_position = offset;
+                } else if !lines.is_empty() {
+                    // Clicked below text, move to end
+                    self.cursor_position = self.input.len();
+                }
+            } else if is_inside(self.timeline_area, col, row) {
+                let column = col.saturating_sub(self.timeline_area.x + 1) as usize;
+                if column < self.timeline_columns.len() {
+                    self.open_timeline();
+                    self.timeline_cursor = column;
+                    self.timeline_anchor = Some(column);
+                }
+            } else if is_inside(self.main_area, col, row) {
+                self.view_focus = ViewFocus::ContentList;
+            } else if is_inside(self.detail_area, col, row) {
+                self.view_focus = ViewFocus::ContentDetail;
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: event::KeyEvent) -> Vec<Effect> {
+        // Global Key Handlers (Pre-InputMode)
+        if self.keymap.action(&[keymap::Mode::Global], &key) == Some(keymap::Action::Help) {
+            self.input_mode = InputMode::Help;
+            // Skip further processing for this key to prevent typing it
+            return Vec::new();
+        }
+
+        match self.input_mode {
+            InputMode::Normal => match key.code {
+                // Detail tree folds: z then a/o/c/M/R/0-9
+                KeyCode::Char(c) if self.fold_pending => {
+                    self.fold_pending = false;
+                    self.fold_command(c);
+                }
+                _ if self.fold_pending => {
+                    self.fold_pending = false;
+                }
+                KeyCode::Char(c @ '1'..='9') if self.view_mode == ViewMode::Chart => {
+                    self.toggle_chart_series(c as usize - '0' as usize);
+                }
+                _ => {
+                    let modes = self.key_modes();
+                    if let Some(action) = self.keymap.action(&modes, &key) {
+                        return self.perform_action(action);
+                    }
+                }
+            },
+            InputMode::Editing => {
+                // Vim keeps its own Enter and Ctrl+X, and its
+                // command modes keep Ctrl+R for redo.
+                let vim = matches!(self.editor_mode, EditorMode::Vim(_));
+                let vim_command = vim && self.editor_mode != EditorMode::Vim(VimState::Insert);
+                match self.keymap.action(&[keymap::Mode::Editing], &key) {
+                    Some(action @ (keymap::Action::FormatQuery | keymap::Action::ToggleVim)) => {
+                        return self.perform_action(action);
+                    }
+                    Some(keymap::Action::HistorySearch) if !vim_command => {
+                        self.start_history_search();
+                        return Vec::new();
+                    }
+                    Some(action @ (keymap::Action::RunSearch | keymap::Action::EditExternally))
+                        if !vim =>
+                    {
+                        return self.perform_action(action);
+                    }
+                    _ => {}
+                }
+
+                match self.editor_mode {
+                    EditorMode::Standard => match key.code {
+                        KeyCode::Up
+                            if !key
+                                .modifiers
+                                .contains(crossterm::event::KeyModifiers::SHIFT) =>
+                        {
+                            self.cursor_up_or_recall()
+                        }
+                        KeyCode::Down
+                            if !key
+                                .modifiers
+                                .contains(crossterm::event::KeyModifiers::SHIFT) =>
+                        {
+                            self.cursor_down_or_recall()
+                        }
+                        KeyCode::Esc => {
+                            self.input_mode = InputMode::Normal;
+                            self.status_message = String::from("Search cancelled.");
+                        }
+                        _ => self.handle_standard_key(key),
+                    },
+                    EditorMode::Vim(_) => match self.handle_vim_key(key) {
+                        VimOutcome::Submit => return self.perform_search(),
+                        VimOutcome::Cancel => {
+                            self.input_mode = InputMode::Normal;
+                            self.status_message = String::from("Search cancelled.");
+                        }
+                        VimOutcome::Handled => {}
+                    },
+                }
+            }
+            InputMode::SaveSearch => match key.code {
+                KeyCode::Enter => {
+                    return self.save_current_search();
+                }
+                KeyCode::Char(c) => {
+                    self.save_search_name.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.save_search_name.pop();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Save cancelled.");
+                }
+                _ => {}
+            },
+            InputMode::ConfirmOverwrite => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    return self.overwrite_current_search();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Save cancelled.");
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    self.input_mode = InputMode::SaveSearch;
+                    self.save_search_name =
+                        self.current_saved_search_name.clone().unwrap_or_default();
+                    self.status_message = String::from("Enter name for saved search:");
+                }
+                _ => {}
+            },
+            InputMode::LoadSearch => {
+                let ctrl = key
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::CONTROL);
+                match self.load_action.as_mut() {
+                    Some(LoadAction::Rename(buf)) | Some(LoadAction::Duplicate(buf)) => {
+                        match key.code {
+                            KeyCode::Enter => return self.apply_load_action(),
+                            KeyCode::Esc => {
+                                self.load_action = None;
+                                self.status_message = String::from("Cancelled.");
+                            }
+                            KeyCode::Backspace => {
+                                buf.pop();
+                            }
+                            KeyCode::Char(c) if !ctrl => buf.push(c),
+                            _ => {}
+                        }
+                    }
+                    Some(LoadAction::ConfirmDelete) => match key.code {
+                        KeyCode::Char('y') | KeyCode::Char('Y') => return self.apply_load_action(),
+                        _ => {
+                            self.load_action = None;
+                            self.status_message = String::from("Delete cancelled.");
+                        }
+                    },
+                    None => match key.code {
+                        KeyCode::Enter => {
+                            self.load_selected_search();
+                        }
+                        KeyCode::Tab => {
+                            self.cycle_load_grouping();
+                        }
+                        KeyCode::Down => self.list_next(),
+                        KeyCode::Up => self.list_previous(),
+                        KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.list_next(),
+                        KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.list_previous(),
+                        KeyCode::Char('r') if ctrl => {
+                            self.begin_load_action(LoadAction::Rename(String::new()))
+                        }
+                        KeyCode::Char('d') if ctrl => {
+                            self.begin_load_action(LoadAction::Duplicate(String::new()))
+                        }
+                        KeyCode::Char('x') if ctrl => {
+                            self.begin_load_action(LoadAction::ConfirmDelete)
+                        }
+                        KeyCode::Char(c) if !ctrl => {
+                            self.load_query.push(c);
+                            self.rebuild_load_rows();
+                        }
+                        KeyCode::Backspace => {
+                            self.load_query.pop();
+                            self.rebuild_load_rows();
+                        }
+                        KeyCode::Esc => {
+                            self.input_mode = InputMode::Normal;
+                            self.status_message = String::from("Load cancelled.");
+                        }
+                        _ => {}
+                    },
+                }
+            }
+            InputMode::LocalPipeline => match key.code {
+                KeyCode::Enter => {
+                    self.apply_local_pipeline();
+                }
+                KeyCode::Char('p')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.promote_local_pipeline();
+                }
+                KeyCode::Char(c) => {
+                    self.pipeline_input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.pipeline_input.pop();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message.clear();
+                }
+                _ => {}
+            },
+            InputMode::LocalSearch => match key.code {
+                KeyCode::Enter => {
+                    self.perform_local_search();
+                    self.input_mode = InputMode::Normal;
+                }
+                KeyCode::Tab => {
+                    self.local_search_scope = self.local_search_scope.next();
+                }
+                KeyCode::Char('t')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.local_search_case = !self.local_search_case;
+                }
+                KeyCode::Char('v')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.local_search_invert = !self.local_search_invert;
+                }
+                KeyCode::Char('f')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.local_search_filter = !self.local_search_filter;
+                }
+                KeyCode::Char(c) => {
+                    self.local_search_query.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.local_search_query.pop();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Local search cancelled.");
+                }
+                _ => {}
+            },
+            InputMode::Palette => match key.code {
+                KeyCode::Enter => return self.run_palette(),
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                }
+                KeyCode::Down => {
+                    let last = self.palette_matches.len().saturating_sub(1);
+                    let i = self.palette_state.selected().unwrap_or(0);
+                    self.palette_state.select(Some((i + 1).min(last)));
+                }
+                KeyCode::Up => {
+                    let i = self.palette_state.selected().unwrap_or(0);
+                    self.palette_state.select(Some(i.saturating_sub(1)));
+                }
+                KeyCode::Char('n') | KeyCode::Char('j')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    let last = self.palette_matches.len().saturating_sub(1);
+                    let i = self.palette_state.selected().unwrap_or(0);
+                    self.palette_state.select(Some((i + 1).min(last)));
+                }
+                KeyCode::Char('p') | KeyCode::Char('k')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    let i = self.palette_state.selected().unwrap_or(0);
+                    self.palette_state.select(Some(i.saturating_sub(1)));
+                }
+                KeyCode::Char(c) => {
+                    self.palette_input.push(c);
+                    self.update_palette();
+                }
+                KeyCode::Backspace => {
+                    self.palette_input.pop();
+                    self.update_palette();
+                }
+                _ => {}
+            },
+            InputMode::Yank => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let last = self.yank_targets.len().saturating_sub(1);
+                    let i = self.yank_list_state.selected().unwrap_or(0);
+                    self.yank_list_state.select(Some((i + 1).min(last)));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = self.yank_list_state.selected().unwrap_or(0);
+                    self.yank_list_state.select(Some(i.saturating_sub(1)));
+                }
+                KeyCode::Enter => {
+                    let target = self
+                        .yank_list_state
+                        .selected()
+                        .and_then(|i| self.yank_targets.get(i).cloned());
+                    if let Some(target) = target {
+                        return self.yank(target);
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.input_mode = InputMode::Normal;
+                }
+                KeyCode::Char(c) => {
+                    let target = self
+                        .yank_targets
+                        .iter()
+                        .find(|t| t.key() == Some(c))
+                        .cloned();
+                    if let Some(target) = target {
+                        return self.yank(target);
+                    }
+                }
+                _ => {}
+            },
+            InputMode::ThemeSelect => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let i = match self.theme_list_state.selected() {
+                        Some(i) => {
+                            if i >= self.themes.len() - 1 {
+                                0
+                            } else {
+                                i + 1
+                            }
+                        }
+                        None => 0,
+                    };
+                    self.theme_list_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let i = match self.theme_list_state.selected() {
+                        Some(i) => {
+                            if i == 0 {
+                                self.themes.len() - 1
+                            } else {
+                                i - 1
+                            }
+                        }
+                        None => 0,
+                    };
+                    self.theme_list_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+                    if let Some(idx) = self.theme_list_state.selected() {
+                        let theme_name = self.themes[idx].name.clone();
+                        self.apply_theme(&theme_name);
+                        self.status_message = format!("Theme '{}' applied.", theme_name);
+                        return vec![Effect::SaveTheme(theme_name)];
+                    }
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Theme selection cancelled.");
+                }
+                _ => {}
+            },
+            InputMode::AppSelect => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let len = self.app_options.len();
+                    let i = match self.app_list_state.selected() {
+                        Some(i) if i + 1 < len => i + 1,
+                        _ => 0,
+                    };
+                    self.app_list_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let len = self.app_options.len();
+                    let i = match self.app_list_state.selected() {
+                        Some(0) | None => len.saturating_sub(1),
+                        Some(i) => i - 1,
+                    };
+                    self.app_list_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    return self.select_app();
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    return self.clear_app();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("App selection cancelled.");
+                }
+                _ => {}
+            },
+            InputMode::ServerSearches => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let len = self.server_searches.len();
+                    let i = match self.server_search_state.selected() {
+                        Some(i) if i + 1 < len => i + 1,
+                        _ => 0,
+                    };
+                    self.server_search_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let len = self.server_searches.len();
+                    let i = match self.server_search_state.selected() {
+                        Some(0) | None => len.saturating_sub(1),
+                        Some(i) => i - 1,
+                    };
+                    self.server_search_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    self.copy_server_search_to_editor();
+                }
+                KeyCode::Char('d') => {
+                    return self.dispatch_server_search();
+                }
+                KeyCode::Char('h') => {
+                    return self.load_server_search_last_run();
+                }
+                KeyCode::Char('s') => {
+                    return self.toggle_server_search_schedule();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Closed saved searches.");
+                }
+                _ => {}
+            },
+            InputMode::Sync => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let len = self.sync_entries.len();
+                    let i = match self.sync_list_state.selected() {
+                        Some(i) if i + 1 < len => i + 1,
+                        _ => 0,
+                    };
+                    self.sync_list_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let len = self.sync_entries.len();
+                    let i = match self.sync_list_state.selected() {
+                        Some(0) | None => len.saturating_sub(1),
+                        Some(i) => i - 1,
+                    };
+                    self.sync_list_state.select(Some(i));
+                }
+                KeyCode::Char(' ') => {
+                    self.toggle_sync_mark();
+                }
+                KeyCode::Char('p') => {
+                    return self.apply_sync(true);
+                }
+                KeyCode::Char('g') => {
+                    return self.apply_sync(false);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Closed sync.");
+                }
+                _ => {}
+            },
+            InputMode::HistorySearch => match key.code {
+                KeyCode::Char('r')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.update_history_search(true);
+                }
+                KeyCode::Char('g')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.input_mode = InputMode::Editing;
+                }
+                KeyCode::Char(c) if !c.is_control() => {
+                    self.history_search_query.push(c);
+                    self.update_history_search(false);
+                }
+                KeyCode::Backspace => {
+                    self.history_search_query.pop();
+                    self.update_history_search(false);
+                }
+                KeyCode::Enter => {
+                    self.accept_history_search();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Editing;
+                    self.status_message = String::from("History search cancelled.");
+                }
+                _ => {}
+            },
+            InputMode::HistoryBrowser => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let len = self.history.len();
+                    let i = match self.history_list_state.selected() {
+                        Some(i) if i + 1 < len => i + 1,
+                        _ => 0,
+                    };
+                    self.history_list_state.select(Some(i));
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let len = self.history.len();
+                    let i = match self.history_list_state.selected() {
+                        Some(0) | None => len.saturating_sub(1),
+                        Some(i) => i - 1,
+                    };
+                    self.history_list_state.select(Some(i));
+                }
+                KeyCode::Enter => {
+                    self.load_history_entry();
+                }
+                KeyCode::Char('r') => {
+                    return self.rerun_history_entry();
+                }
+                KeyCode::Char('a') => {
+                    return self.reattach_history_job();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Closed history.");
+                }
+                _ => {}
+            },
+            InputMode::LintConfirm => match key.code {
+                KeyCode::Enter | KeyCode::Char('y') => {
+                    let query = self
+                        .pending_query
+                        .take()
+                        .unwrap_or_else(|| self.input.clone());
+                    return self.dispatch_search(query);
+                }
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.pending_query = None;
+                    self.input_mode = InputMode::Editing;
+                    self.status_message =
+                        String::from("Search not run. Edit the query or press Enter again.");
+                }
+                _ => {}
+            },
+            InputMode::Timeline => {
+                let extend = key
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::SHIFT);
+                match key.code {
+                    KeyCode::Left | KeyCode::Char('h') => self.move_timeline_cursor(-1, extend),
+                    KeyCode::Right | KeyCode::Char('l') => self.move_timeline_cursor(1, extend),
+                    KeyCode::Char('H') => self.move_timeline_cursor(-1, true),
+                    KeyCode::Char('L') => self.move_timeline_cursor(1, true),
+                    KeyCode::Home | KeyCode::Char('0') => {
+                        self.move_timeline_cursor(isize::MIN / 2, extend)
+                    }
+                    KeyCode::End | KeyCode::Char('$') => {
+                        self.move_timeline_cursor(isize::MAX / 2, extend)
+                    }
+                    KeyCode::Char(' ') => {
+                        self.timeline_anchor = match self.timeline_anchor {
+                            Some(_) => None,
+                            None => Some(self.timeline_cursor),
+                        };
+                    }
+                    KeyCode::Enter | KeyCode::Char('f') => {
+                        self.filter_to_timeline_selection();
+                    }
+                    KeyCode::Char('z') => {
+                        return self.zoom_to_timeline_selection();
+                    }
+                    KeyCode::Char('c') | KeyCode::Backspace => {
+                        self.timeline_anchor = None;
+                        self.clear_timeline_filter();
+                    }
+                    KeyCode::Esc | KeyCode::Char('T') | KeyCode::Char('q') => {
+                        self.input_mode = InputMode::Normal;
+                        self.status_message.clear();
+                    }
+                    _ => {}
+                }
+            }
+            InputMode::TokenForm => match key.code {
+                KeyCode::Enter => {
+                    return self.submit_token_form();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Search not run.");
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    let len = self.token_fields.len().max(1);
+                    self.token_selected = (self.token_selected + 1) % len;
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    let len = self.token_fields.len().max(1);
+                    self.token_selected = (self.token_selected + len - 1) % len;
+                }
+                KeyCode::Char('n')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.cycle_token_value(true);
+                }
+                KeyCode::Char('p')
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                {
+                    self.cycle_token_value(false);
+                }
+                KeyCode::Char(c) => {
+                    let selected = self.token_selected;
+                    if let Some(field) = self.token_fields.get_mut(selected) {
+                        field.value.push(c);
+                        field.recent_index = None;
+                    }
+                }
+                KeyCode::Backspace => {
+                    let selected = self.token_selected;
+                    if let Some(field) = self.token_fields.get_mut(selected) {
+                        field.value.pop();
+                        field.recent_index = None;
+                    }
+                }
+                _ => {}
+            },
+            InputMode::DashboardOpen => match key.code {
+                KeyCode::Enter => {
+                    return self.open_dashboard();
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message.clear();
+                }
+                KeyCode::Char(c) => {
+                    self.dashboard_source.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.dashboard_source.pop();
+                }
+                _ => {}
+            },
+            InputMode::Dashboard => match key.code {
+                KeyCode::Left | KeyCode::Char('h') => self.move_dashboard_selection(-1, 0),
+                KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                    self.move_dashboard_selection(1, 0)
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.move_dashboard_selection(0, -1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_dashboard_selection(0, 1),
+                KeyCode::Char('r') => {
+                    self.status_message = String::from("Refreshing dashboard...");
+                    return self.refresh_dashboard();
+                }
+                KeyCode::Char('i') => {
+                    if self
+                        .dashboard
+                        .as_ref()
+                        .is_some_and(|view| !view.dashboard.inputs.is_empty())
+                    {
+                        self.input_mode = InputMode::DashboardInput;
+                    } else {
+                        self.status_message = String::from("This dashboard has no inputs.");
+                    }
+                }
+                KeyCode::Enter => {
+                    return self.open_dashboard_panel();
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.input_mode = InputMode::Normal;
+                    self.status_message = String::from("Dashboard closed.");
+                    return self.close_dashboard();
+                }
+                _ => {}
+            },
+            InputMode::DashboardInput => {
+                if let Some(view) = self.dashboard.as_mut() {
+                    let len = view.input_edits.len().max(1);
+                    match key.code {
+                        KeyCode::Tab | KeyCode::Down => {
+                            view.input_selected = (view.input_selected + 1) % len;
+                        }
+                        KeyCode::BackTab | KeyCode::Up => {
+                            view.input_selected = (view.input_selected + len - 1) % len;
+                        }
+                        KeyCode::Char(c) => {
+                            if let Some(value) = view.input_edits.get_mut(view.input_selected) {
+                                value.push(c);
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            if let Some(value) = view.input_edits.get_mut(view.input_selected) {
+                                value.pop();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                match key.code {
+                    KeyCode::Enter => {
+                        self.input_mode = InputMode::Dashboard;
+                        return self.apply_dashboard_inputs();
+                    }
+                    KeyCode::Esc => {
+                        // Drop unapplied edits.
+                        if let Some(view) = self.dashboard.as_mut() {
+                            view.input_edits = view
+                                .dashboard
+                                .inputs
+                                .iter()
+                                .map(|i| view.values[&i.token].clone())
+                                .collect();
+                        }
+                        self.input_mode = InputMode::Dashboard;
+                    }
+                    _ => {}
+                }
+            }
+            InputMode::Help => match key.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                    self.input_mode = InputMode::Normal;
+                    self.help_scroll = 0;
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.help_scroll = self.help_scroll.saturating_add(1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.help_scroll = self.help_scroll.saturating_sub(1);
+                }
+                KeyCode::PageDown => {
+                    self.help_scroll = self.help_scroll.saturating_add(10);
+                }
+                KeyCode::PageUp => {
+                    self.help_scroll = self.help_scroll.saturating_sub(10);
+                }
+                _ => {}
+            },
+        } // End InputMode match
+        Vec::new()
+    }
+
+    /// Keymap modes for a key in Normal input, most specific first.
+    fn key_modes(&self) -> Vec<keymap::Mode> {
+        let mut modes = Vec::new();
+        if self.view_mode == ViewMode::Table && self.view_focus == ViewFocus::ContentDetail {
+            modes.push(keymap::Mode::Detail);
+        }
+        if self.view_mode == ViewMode::Chart && self.view_focus == ViewFocus::ContentList {
+            modes.push(keymap::Mode::Chart);
+        }
+        modes.push(keymap::Mode::Normal);
+        modes
+    }
+
+    /// Runs a bound action.
+    fn perform_action(&mut self, action: keymap::Action) -> Vec<Effect> {
+        match action {
+            keymap::Action::Help => self.input_mode = InputMode::Help,
+            keymap::Action::Palette => self.open_palette(),
+            keymap::Action::Quit => return vec![Effect::Quit],
+            keymap::Action::EditQuery => {
+                self.input_mode = InputMode::Editing;
+                self.status_message =
+                    String::from("Editing... Press Enter to search, Esc to cancel.");
+                // If re-entering, ensure cursor is valid
+                self.clamp_cursor();
+            }
+            keymap::Action::RunSearch => return self.perform_search(),
+            keymap::Action::KillJob => return self.kill_search(),
+            keymap::Action::CycleTheme => self.toggle_theme_selector(),
+            keymap::Action::SelectApp => return self.initiate_app_select(),
+            keymap::Action::LoadSearch => return self.initiate_load_search(),
+            keymap::Action::SaveSearch => self.initiate_save_search(),
+            keymap::Action::ServerSearches => return self.initiate_server_search_browser(),
+            keymap::Action::Sync => return self.initiate_sync(),
+            keymap::Action::History => self.open_history_browser(),
+            keymap::Action::Timeline => self.open_timeline(),
+            keymap::Action::Dashboard => {
+                if self.dashboard.is_some() {
+                    self.input_mode = InputMode::Dashboard;
+                } else {
+                    self.open_dashboard_prompt();
+                }
+            }
+            keymap::Action::CycleView => self.cycle_view_mode(),
+            keymap::Action::OpenInEditor => {
+                return if let ViewFocus::Search = self.view_focus {
+                    self.open_query_in_editor()
+                } else {
+                    self.open_in_editor()
+                };
+            }
+            keymap::Action::OpenJobUrl => return self.open_job_url(),
+            keymap::Action::Copy => self.open_yank_menu(),
+            keymap::Action::ClearResults => self.clear_results(),
+            keymap::Action::LocalSearch => {
+                // Keeps the last query so it can be refined; an empty one
+                // clears the search.
+                self.input_mode = InputMode::LocalSearch;
+                self.status_message = String::from("Regex, or field:regex to search one field...");
+            }
+            keymap::Action::NextMatch => self.next_match(),
+            keymap::Action::PrevMatch => self.prev_match(),
+            keymap::Action::LocalPipeline => self.open_local_pipeline(),
+            keymap::Action::CycleFocus => {
+                self.view_focus = match self.view_focus {
+                    ViewFocus::Search => ViewFocus::ContentList,
+                    ViewFocus::ContentList if self.view_mode == ViewMode::Table => {
+                        ViewFocus::ContentDetail
+                    }
+                    ViewFocus::ContentList | ViewFocus::ContentDetail => ViewFocus::Search,
+                };
+            }
+            keymap::Action::MoveDown => self.move_selection(true),
+            keymap::Action::MoveUp => self.move_selection(false),
+            keymap::Action::ScrollDownFast => self.scroll_down_fast(),
+            keymap::Action::ScrollUpFast => self.scroll_up_fast(),
+            keymap::Action::FocusList => self.view_focus = ViewFocus::ContentList,
+            keymap::Action::FocusDetail => {
+                if self.view_mode == ViewMode::Table {
+                    self.view_focus = ViewFocus::ContentDetail;
+                }
+            }
+            keymap::Action::CycleDetailFormat => self.cycle_detail_format(),
+            keymap::Action::ToggleFold if self.tree_focused() => self.fold_command('a'),
+            keymap::Action::Fold if self.tree_focused() => {
+                self.fold_pending = true;
+                self.status_message = String::from(
+                    "Fold: a toggle, o open, c close, M fold all, R open all, 0-9 depth",
+                );
+            }
+            keymap::Action::CopyValue if self.tree_focused() => return self.copy_detail(false),
+            keymap::Action::CopyPath if self.tree_focused() => return self.copy_detail(true),
+            keymap::Action::ToggleFold
+            | keymap::Action::Fold
+            | keymap::Action::CopyValue
+            | keymap::Action::CopyPath => {}
+            keymap::Action::ChartLeft => self.move_chart_cursor(false),
+            keymap::Action::ChartRight => self.move_chart_cursor(true),
+            keymap::Action::CycleChartKind => {
+                self.chart_kind = self.chart_kind.next();
+                self.status_message = format!("{} chart.", self.chart_kind.label());
+            }
+            keymap::Action::FormatQuery => self.format_input(),
+            keymap::Action::ToggleVim => {
+                self.toggle_vim_mode();
+                let mode_msg = match self.editor_mode {
+                    EditorMode::Standard => "Standard Mode",
+                    EditorMode::Vim(_) => "Vim Mode",
+                };
+                self.status_message = format!("Switched to {}.", mode_msg);
+            }
+            keymap::Action::HistorySearch => self.start_history_search(),
+            keymap::Action::EditExternally => return self.open_query_in_editor(),
+        }
+        Vec::new()
+    }
+
+    fn cycle_view_mode(&mut self) {
+        match self.view_mode {
+            ViewMode::RawEvents => {
+                self.view_mode = ViewMode::Table;
+                // Sync selection from scroll_offset
+                let idx = self.scroll_offset as usize;
+                if idx < self.search_results.len() {
+                    self.table_state.select(Some(idx));
+                    self.update_detail_view();
+                } else if !self.search_results.is_empty() {
+                    self.table_state.select(Some(0));
+                    self.update_detail_view();
+                }
+            }
+            ViewMode::Table => {
+                self.view_mode = ViewMode::Chart;
+                self.chart_cursor = self.table_state.selected().unwrap_or(0);
+            }
+            ViewMode::Chart => {
+                self.view_mode = ViewMode::Stat;
+            }
+            ViewMode::Stat => {
+                self.view_mode = ViewMode::RawEvents;
+                // Sync scroll_offset from the chart cursor
+                self.scroll_offset = self.chart_cursor as u16;
+            }
+        }
+        self.status_message = format!("Switched to {:?} mode.", self.view_mode);
+    }
+
+    /// Moves the table row, the tree cursor or the raw view by one, without
+    /// wrapping. Down from the search box focuses the results.
+    fn move_selection(&mut self, down: bool) {
+        if let ViewFocus::Search = self.view_focus {
+            if down {
+                self.view_focus = ViewFocus::ContentList;
+            }
+            return;
+        }
+        match (self.view_mode, self.view_focus) {
+            (ViewMode::Chart | ViewMode::Stat, _) => {}
+            (ViewMode::RawEvents, _) if down => self.scroll_down(),
+            (ViewMode::RawEvents, _) => self.scroll_up(),
+            (ViewMode::Table, ViewFocus::ContentList) => {
+                if self.search_results.is_empty() {
+                    return;
+                }
+                let last = self.search_results.len() - 1;
+                let next = match self.table_state.selected() {
+                    Some(i) if down => (i + 1).min(last),
+                    Some(i) => i.saturating_sub(1),
+                    None => 0,
+                };
+                self.table_state.select(Some(next));
+                self.detail_scroll = 0; // Reset detail scroll on row change
+                self.update_detail_view();
+            }
+            (ViewMode::Table, ViewFocus::ContentDetail) if self.tree_focused() => {
+                self.move_detail_cursor(if down { 1 } else { -1 });
+            }
+            (ViewMode::Table, ViewFocus::ContentDetail) if down => {
+                self.detail_scroll = self.detail_scroll.saturating_add(1);
+            }
+            (ViewMode::Table, ViewFocus::ContentDetail) => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(1);
+            }
+            (ViewMode::Table, ViewFocus::Search) => {}
+        }
+    }
+
+    fn update_detail_view(&mut self) {
+        if self.view_mode == ViewMode::Table {
+            let selected_idx = self.table_state.selected().unwrap_or(0);
+            if let Some(item) = self.search_results.get(selected_idx) {
+                let parsed = recursive_json_parse(item.clone());
+                if self.detail_value.as_ref() != Some(&parsed) {
+                    self.detail_tree_state.select(Some(0));
+                }
+                let mut detail = match self.detail_format {
+                    DetailFormat::Tree => {
+                        self.detail_rows = json_tree::rows(&parsed, &self.detail_folded);
+                        let last = self.detail_rows.len().saturating_sub(1);
+                        let cursor = self.detail_tree_state.selected().unwrap_or(0).min(last);
+                        self.detail_tree_state.select(Some(cursor));
+                        tree_lines(&self.theme, &parsed, &self.detail_rows).into()
+                    }
+                    DetailFormat::Yaml => render_highlighted(
+                        &self.syntax_set,
+                        &self.syntax_theme,
+                        &serde_yaml::to_string(&parsed)
+                            .unwrap_or_else(|e| format!("Error converting to YAML: {}", e)),
+                        "yaml",
+                    ),
+                    DetailFormat::Json => render_highlighted(
+                        &self.syntax_set,
+                        &self.syntax_theme,
+                        &serde_json::to_string_pretty(&parsed).unwrap_or_default(),
+                        "json",
+                    ),
+                    DetailFormat::Raw => ratatui::text::Text::from(
+                        item.get("_raw")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("This event has no _raw field.")
+                            .to_string(),
+                    ),
+                };
+                if let Some(search) = &self.local_search {
+                    let style = match_style(&self.theme);
+                    detail.lines = detail
+                        .lines
+                        .into_iter()
+                        .map(|line| {
+                            let text: String =
+                                line.spans.iter().map(|s| s.content.as_ref()).collect();
+                            highlight_line(line, &search.highlights(None, &text), style)
+                        })
+                        .collect();
+                }
+                self.cached_detail = detail;
+                self.detail_value = Some(parsed);
+            } else {
+                self.cached_detail = ratatui::text::Text::from("Select an event...");
+                self.detail_value = None;
+                self.detail_rows.clear();
+            }
+        }
+    }
+
+    fn tree_focused(&self) -> bool {
+        self.view_mode == ViewMode::Table
+            && self.view_focus == ViewFocus::ContentDetail
+            && self.detail_format == DetailFormat::Tree
+    }
+
+    fn cycle_detail_format(&mut self) {
+        self.detail_format = self.detail_format.next();
+        self.detail_scroll = 0;
+        self.update_detail_view();
+        self.status_message = format!("Detail view: {}.", self.detail_format.label());
+    }
+
+    fn move_detail_cursor(&mut self, step: isize) {
+        let last = self.detail_rows.len().saturating_sub(1);
+        let cursor = self.detail_tree_state.selected().unwrap_or(0);
+        self.detail_tree_state
+            .select(Some(cursor.saturating_add_signed(step).min(last)));
+    }
+
+    fn detail_row(&self) -> Option<&json_tree::Row> {
+        self.detail_rows.get(self.detail_tree_state.selected()?)
+    }
+
+    /// Replaces the folds, keeping the cursor on its row or on the nearest
+    /// ancestor that is still visible.
+    fn refold(&mut self, folded: std::collections::HashSet<String>) {
+        let path = self
+            .detail_row()
+            .map(|r| r.path.clone())
+            .unwrap_or_default();
+        self.detail_folded = folded;
+        self.update_detail_view();
+        let row = (1..=path.len())
+            .rev()
+            .find_map(|len| self.detail_rows.iter().position(|r| r.path == path[..len]));
+        self.detail_tree_state.select(Some(row.unwrap_or(0)));
+    }
+
+    /// Vim-style fold commands after `z`: `a` toggle, `o` open, `c` close,
+    /// `M` fold all, `R` open all and `0`-`9` fold to that depth.
+    fn fold_command(&mut self, command: char) {
+        let Some(value) = &self.detail_value else {
+            return;
+        };
+        let mut folded = self.detail_folded.clone();
+        match command {
+            'M' => folded = json_tree::fold_to_depth(value, 0),
+            'R' => folded.clear(),
+            '0'..='9' => folded = json_tree::fold_to_depth(value, command as usize - '0' as usize),
+            'a' | 'o' | 'c' => {
+                let Some(row) = self.detail_row() else {
+                    return;
+                };
+                // On a scalar, `c` closes the enclosing container.
+                let path = if row.children.is_some() {
+                    &row.path[..]
+                } else if command == 'c' {
+                    &row.path[..row.path.len() - 1]
+                } else {
+                    return;
+                };
+                if path.is_empty() {
+                    return;
+                }
+                let key = json_tree::json_path(path);
+                let close = match command {
+                    'a' => !row.folded,
+                    'o' => false,
+                    _ => true,
+                };
+                if close {
+                    folded.insert(key);
+                } else {
+                    folded.remove(&key);
+                }
+                let path = path.to_vec();
+                self.refold(folded);
+                if let Some(i) = self.detail_rows.iter().position(|r| r.path == path) {
+                    self.detail_tree_state.select(Some(i));
+                }
+                return;
+            }
+            _ => return,
+        }
+        self.refold(folded);
+    }
+
+    /// Copies the JSONPath or the value of the tree row under the cursor.
+    fn copy_detail(&mut self, path: bool) -> Vec<Effect> {
+        let (Some(row), Some(value)) = (self.detail_row(), &self.detail_value) else {
+            return Vec::new();
+        };
+        let json_path = json_tree::json_path(&row.path);
+        if path {
+            return self.copy_text("JSONPath", json_path);
+        }
+        let text = match json_tree::get(value, &row.path) {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => serde_json::to_string_pretty(v).unwrap_or_default(),
+            None => return Vec::new(),
+        };
+        self.copy_text(&format!("Value of {}", json_path), text)
+    }
+
+    /// Copies `text` to the system clipboard and the editors' kill buffer and
+    /// yank register.
+    fn copy_text(&mut self, what: &str, text: String) -> Vec<Effect> {
+        self.standard.set_kill_buffer(text.clone());
+        self.vim.set_register(text.clone());
+        vec![Effect::Copy {
+            what: what.to_string(),
+            text,
+            command: self.clipboard_command.clone(),
+        }]
+    }
+
+    /// Falls back to OSC 52 when `clipboard_command` fails or times out.
+    fn copy_command_failed(&mut self, what: String, text: String, error: String) -> Vec<Effect> {
+        warn!("clipboard_command failed, using OSC 52: {}", error);
+        vec![Effect::Copy {
+            what,
+            text,
+            command: None,
+        }]
+    }
+
+    fn copied(&mut self, what: String, bytes: usize, result: Result<String, String>) {
+        self.status_message = match result {
+            Ok(via) => format!("{} copied ({} bytes) via {}.", what, bytes, via),
+            Err(e) => format!(
+                "Clipboard error: {}. {} is in the editor's kill buffer.",
+                e, what
+            ),
+        };
+    }
+
+    /// The row the current view points at.
+    fn selected_row(&self) -> Option<&Value> {
+        let idx = match self.view_mode {
+            ViewMode::Table => self.table_state.selected().unwrap_or(0),
+            ViewMode::RawEvents => self.scroll_offset as usize,
+            ViewMode::Chart => self.chart_cursor,
+            ViewMode::Stat => 0,
+        };
+        self.search_results.get(idx)
+    }
+
+    /// Columns the table shows; empty for the time/sourcetype/message
+    /// summary of events. Saved searches can pin their own columns, and
+    /// reshaped or transforming results show theirs.
+    fn shown_columns(&self) -> Vec<String> {
+        if !self.table_columns.is_empty() {
+            self.table_columns.clone()
+        } else if !self.local_pipeline.is_empty()
+            || self.search_results.iter().all(|r| r.get("_raw").is_none())
+        {
+            result_columns(&self.search_results)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Columns for copying or exporting the visible table.
+    fn export_columns(&self) -> Vec<String> {
+        let columns = self.shown_columns();
+        if columns.is_empty() {
+            vec!["_time".into(), "sourcetype".into(), "_raw".into()]
+        } else {
+            columns
+        }
+    }
+
+    fn open_palette(&mut self) {
+        self.palette_input.clear();
+        self.input_mode = InputMode::Palette;
+        self.update_palette();
+    }
+
+    /// Ranks the actions against the typed text, unless it is a command.
+    fn update_palette(&mut self) {
+        let actions: Vec<(keymap::Mode, keymap::Action)> = self
+            .keymap
+            .actions()
+            .into_iter()
+            .filter(|(_, a)| *a != keymap::Action::Palette)
+            .collect();
+        self.palette_matches = if palette::parse(&self.palette_input).is_some() {
+            Vec::new()
+        } else {
+            let texts: Vec<String> = actions
+                .iter()
+                .map(|(_, a)| format!("{} {}", a.description(), a.name()))
+                .collect();
+            palette::rank(&self.palette_input, texts.iter().map(String::as_str))
+                .into_iter()
+                .map(|i| actions[i])
+                .collect()
+        };
+        self.palette_state.select(Some(0));
+    }
+
+    /// Runs the typed command, the action named exactly, or the highlighted
+    /// one.
+    fn run_palette(&mut self) -> Vec<Effect> {
+        self.input_mode = InputMode::Normal;
+        let input = self
+            .palette_input
+            .trim()
+            .trim_start_matches(':')
+            .to_string();
+        match palette::parse(&input) {
+            Some(Ok(command)) => return self.run_command(command),
+            Some(Err(e)) => {
+                self.status_message = e;
+                return Vec::new();
+            }
+            None => {}
+        }
+        let named = self
+            .keymap
+            .actions()
+            .into_iter()
+            .find(|(_, a)| a.name() == input || (input == "q" && *a == keymap::Action::Quit));
+        let chosen = named.or_else(|| {
+            self.palette_state
+                .selected()
+                .and_then(|i| self.palette_matches.get(i).copied())
+        });
+        match chosen {
+            Some((_, action)) => self.perform_action(action),
+            None => {
+                self.status_message = format!("No action or command '{}'.", input);
+                Vec::new()
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: palette::Command) -> Vec<Effect> {
+        match command {
+            palette::Command::Earliest(time) => {
+                self.time_range.earliest = time;
+                self.status_message = format!("Time range: {}.", self.time_range.label());
+            }
+            palette::Command::Latest(time) => {
+                self.time_range.latest = time;
+                self.status_message = format!("Time range: {}.", self.time_range.label());
+            }
+            palette::Command::Export { format, path } => {
+                let path = match (path.strip_prefix("~/"), directories::BaseDirs::new()) {
+                    (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+                    _ => std::path::PathBuf::from(&path),
+                };
+                let text = format.render(&self.export_columns(), &self.search_results);
+                return vec![Effect::Export {
+                    path,
+                    text,
+                    rows: self.search_results.len(),
+                }];
+            }
+            palette::Command::Set(settings) => {
+                for setting in settings {
+                    match setting {
+                        Setting::Wrap(wrap) => self.wrap_lines = wrap,
+                        Setting::Vim(vim) => {
+                            if vim != matches!(self.editor_mode, EditorMode::Vim(_)) {
+                                self.toggle_vim_mode();
+                            }
+                        }
+                    }
+                }
+                self.status_message = format!(
+                    "Options: {}, {}.",
+                    if self.wrap_lines { "wrap" } else { "nowrap" },
+                    if matches!(self.editor_mode, EditorMode::Vim(_)) {
+                        "vim"
+                    } else {
+                        "novim"
+                    }
+                );
+            }
+            palette::Command::Theme(name) => {
+                let found = self
+                    .themes
+                    .iter()
+                    .find(|t| t.name.eq_ignore_ascii_case(&name))
+                    .map(|t| t.name.clone());
+                match found {
+                    Some(theme) => {
+                        self.apply_theme(&theme);
+                        self.status_message = format!("Theme '{}' applied.", theme);
+                        return vec![Effect::SaveTheme(theme)];
+                    }
+                    None => {
+                        let names: Vec<&str> =
+                            self.themes.iter().map(|t| t.name.as_str()).collect();
+                        self.status_message =
+                            format!("Unknown theme '{}'. Themes: {}.", name, names.join(", "))
+                    }
+                }
+            }
+            palette::Command::Profile(name) => return vec![Effect::LoadProfile { name }],
+        }
+        Vec::new()
+    }
+
+    /// Reconnects with another `[profiles.<name>]` from config.toml.
+    fn switch_profile(&mut self, name: String, result: Result<Box<Config>, String>) -> Vec<Effect> {
+        let config = match result {
+            Ok(config) => config,
+            Err(e) => {
+                self.status_message = format!("Profile '{}': {}", name, e);
+                return Vec::new();
+            }
+        };
+        let client = SplunkClient::new(
+            config.splunk_base_url.clone(),
+            config.splunk_token.clone(),
+            config.splunk_verify_ssl,
+        )
+        .with_namespace(config.namespace())
+        .with_read_only(config.read_only);
+        self.base_url = client.base_url().to_string();
+        self.namespace = client.namespace().cloned();
+        self.read_only = client.is_read_only();
+        self.profile = config.profile;
+        self.lint_disable = config.lint_disable;
+        self.status_message = format!(
+            "Switched to profile '{}' ({}).",
+            self.profile, config.splunk_base_url
+        );
+        // The job belongs to the old server; stop polling it, and cancel it
+        // there (the cancel runs before the executor switches clients).
+        let mut effects: Vec<Effect> = self
+            .current_job_sid
+            .clone()
+            .filter(|_| !self.results_fetched)
+            .map(|sid| Effect::CancelJob { sid })
+            .into_iter()
+            .collect();
+        self.reset_job();
+        self.is_status_fetching = false;
+        effects.push(Effect::Connect(Arc::new(client)));
+        effects
+    }
+
+    fn open_yank_menu(&mut self) {
+        let mut targets = vec![YankTarget::Query];
+        let row = self.selected_row();
+        if row.is_some() {
+            targets.push(YankTarget::RowJson);
+        }
+        if row.and_then(|r| r.get("_raw")).is_some() {
+            targets.push(YankTarget::Raw);
+        }
+        if !self.search_results.is_empty() {
+            targets.extend([YankTarget::TableTsv, YankTarget::TableMarkdown]);
+        }
+        if self.current_job_sid.is_some() {
+            targets.push(YankTarget::JobUrl);
+        }
+        if let Some(fields) = row.and_then(|r| r.as_object()) {
+            targets.extend(
+                fields
+                    .keys()
+                    .filter(|k| k.as_str() != "_raw")
+                    .map(|k| YankTarget::Field(k.clone())),
+            );
+        }
+        self.yank_targets = targets;
+        self.yank_list_state.select(Some(0));
+        self.input_mode = InputMode::Yank;
+    }
+
+    fn yank(&mut self, target: YankTarget) -> Vec<Effect> {
+        self.input_mode = InputMode::Normal;
+        let row = self.selected_row();
+        let table = |format: fn(&[String], &[Value]) -> String| {
+            format(&self.export_columns(), &self.search_results)
+        };
+        let text = match &target {
+            YankTarget::Query => Some(self.input.clone()),
+            YankTarget::RowJson => row.map(|r| serde_json::to_string_pretty(r).unwrap_or_default()),
+            YankTarget::Raw => row.map(|r| clipboard::cell_text(r, "_raw")),
+            YankTarget::Field(name) => row.map(|r| clipboard::cell_text(r, name)),
+            YankTarget::TableTsv => Some(table(clipboard::tsv)),
+            YankTarget::TableMarkdown => Some(table(clipboard::markdown)),
+            YankTarget::JobUrl => self
+                .current_job_sid
+                .as_ref()
+                .map(|sid| shareable_url(&self.base_url, self.namespace.as_ref(), sid)),
+        };
+        match text {
+            Some(text) => self.copy_text(&target.label(), text),
+            None => {
+                self.status_message = format!("Nothing to copy for {}.", target.label());
+                Vec::new()
             }
         }
     }
 }
 
-pub async fn run_app() -> Result<(), Box<dyn Error>> {
+pub async fn run_app(dashboard: Option<String>) -> Result<(), Box<dyn Error>> {
     let config = crate::config::Config::load()?;
     config.validate()?;
     info!("Loaded Config URL: '{}'", config.splunk_base_url);
@@ -784,14 +4025,24 @@ pub async fn run_app() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let client = Arc::new(SplunkClient::new(
-        config.splunk_base_url,
-        config.splunk_token,
-        config.splunk_verify_ssl,
-    ));
-    let app = Arc::new(Mutex::new(App::new(client)));
+    let namespace = config.namespace();
+    let client = Arc::new(
+        SplunkClient::new(
+            config.splunk_base_url.clone(),
+            config.splunk_token.clone(),
+            config.splunk_verify_ssl,
+        )
+        .with_namespace(namespace)
+        .with_read_only(config.read_only),
+    );
+    let mut app = App::new(&client, Startup::load(config));
+    let mut startup = Vec::new();
+    if let Some(source) = dashboard {
+        app.dashboard_source = source;
+        startup = app.open_dashboard();
+    }
 
-    let res = run_loop(&mut terminal, app).await;
+    let res = run_loop(&mut terminal, app, client, startup).await;
 
     disable_raw_mode()?;
     execute!(
@@ -811,141 +4062,103 @@ pub async fn run_app() -> Result<(), Box<dyn Error>> {
 
 async fn run_loop<B: Backend + std::io::Write>(
     terminal: &mut Terminal<B>,
-    app: Arc<Mutex<App>>,
+    mut app: App,
+    client: Arc<SplunkClient>,
+    startup: Vec<Effect>,
 ) -> Result<(), Box<dyn Error>>
 where
     <B as Backend>::Error: 'static,
 {
     let tick_rate = std::time::Duration::from_millis(250);
     let mut last_tick = std::time::Instant::now();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut executor = Executor::new(client, tx);
+    let mut effects: VecDeque<Effect> = startup.into();
 
     loop {
-        let mut app_guard = app.lock().await;
-
-        if app_guard.should_open_editor {
-            if let Some(file_path) = app_guard.editor_file_path.clone() {
-                let is_editing_query = file_path.ends_with("splunk_query.spl");
-
-                app_guard.should_open_editor = false;
-                app_guard.editor_file_path = None;
-                drop(app_guard);
-
-                disable_raw_mode()?;
-                execute!(
-                    terminal.backend_mut(),
-                    LeaveAlternateScreen,
-                    DisableMouseCapture
-                )?;
-                terminal.show_cursor()?;
-
-                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-                let _ = Command::new(editor)
-                    .arg(&file_path)
-                    .stdin(Stdio::inherit())
-                    .stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .status();
-
-                enable_raw_mode()?;
-                execute!(
-                    terminal.backend_mut(),
-                    EnterAlternateScreen,
-                    EnableMouseCapture
-                )?;
-                terminal.hide_cursor()?;
-                terminal.clear()?;
-
-                app_guard = app.lock().await;
-
-                // If we were editing the query, reload it
-                if is_editing_query {
-                    if let Ok(content) = std::fs::read_to_string(file_path) {
-                        app_guard.input = content;
-                        app_guard.cursor_position = app_guard.input.len(); // Reset cursor to end
-                        app_guard.status_message = String::from("Query updated from editor.");
+        // Effects that need the terminal run here; their outcomes are applied
+        // straight away. Everything else goes to the executor.
+        while let Some(effect) = effects.pop_front() {
+            let action = match effect {
+                Effect::Quit => return Ok(()),
+                Effect::OpenEditor {
+                    path,
+                    contents,
+                    reload_query,
+                } => {
+                    if let Err(e) = std::fs::write(&path, contents) {
+                        error!("Failed to write {}: {}", path, e);
+                        continue;
+                    }
+                    disable_raw_mode()?;
+                    execute!(
+                        terminal.backend_mut(),
+                        LeaveAlternateScreen,
+                        DisableMouseCapture
+                    )?;
+                    terminal.show_cursor()?;
+
+                    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+                    let _ = Command::new(editor)
+                        .arg(&path)
+                        .stdin(Stdio::inherit())
+                        .stdout(Stdio::inherit())
+                        .stderr(Stdio::inherit())
+                        .status();
+
+                    enable_raw_mode()?;
+                    execute!(
+                        terminal.backend_mut(),
+                        EnterAlternateScreen,
+                        EnableMouseCapture
+                    )?;
+                    terminal.hide_cursor()?;
+                    terminal.clear()?;
+
+                    match std::fs::read_to_string(&path) {
+                        Ok(content) if reload_query => Action::QueryEdited(content),
+                        _ => continue,
                     }
                 }
-            } else {
-                app_guard.should_open_editor = false; // Reset if path missing
-            }
+                Effect::Copy {
+                    what,
+                    text,
+                    command: None,
+                } => Action::Copied {
+                    what,
+                    bytes: text.len(),
+                    result: clipboard::copy_osc52(&text)
+                        .map(|()| "OSC 52".to_string())
+                        .map_err(|e| e.to_string()),
+                },
+                effect => {
+                    executor.run(effect);
+                    continue;
+                }
+            };
+            effects.extend(app.update(action));
         }
 
         // Set cursor style based on mode
-        let cursor_style = match app_guard.input_mode {
-            InputMode::Editing => match app_guard.editor_mode {
+        let cursor_style = match app.input_mode {
+            InputMode::Editing => match app.editor_mode {
                 EditorMode::Standard => SetCursorStyle::SteadyBar,
                 EditorMode::Vim(VimState::Insert) => SetCursorStyle::SteadyBar,
-                EditorMode::Vim(VimState::Normal) => SetCursorStyle::SteadyBlock,
+                EditorMode::Vim(_) => SetCursorStyle::SteadyBlock,
             },
             _ => SetCursorStyle::DefaultUserShape,
         };
         // We can't easily execute! inside loop efficiently without check, but it's fine for TUI
         let _ = execute!(terminal.backend_mut(), cursor_style);
 
-        terminal.draw(|f| ui(f, &mut app_guard))?;
+        terminal.draw(|f| ui(f, &mut app))?;
 
+        let mut actions = Vec::new();
+        while let Ok(action) = rx.try_recv() {
+            actions.push(action);
+        }
         if last_tick.elapsed() >= tick_rate {
-            // Check if we need to spawn a status check
-            let needs_fetch = app_guard.current_job_sid.is_some()
-                && !app_guard.results_fetched
-                && !app_guard.is_status_fetching;
-
-            if needs_fetch {
-                app_guard.is_status_fetching = true;
-                // Don't overwrite running message with "Checking status..."
-                // app_guard.status_message = String::from("Checking status...");
-
-                let client = app_guard.client.clone();
-                let sid = app_guard.current_job_sid.as_ref().unwrap().clone();
-                let app_clone = app.clone();
-
-                tokio::spawn(async move {
-                    // 1. Check Status
-                    match client.get_job_status(&sid).await {
-                        Ok(status) => {
-                            let mut app = app_clone.lock().await;
-                            app.current_job_status = Some(status.clone());
-
-                            if status.is_done {
-                                // 2. If done, Fetch Results (still in background task)
-                                app.status_message = String::from("Job done. Fetching results...");
-                                drop(app); // Drop lock while fetching results
-
-                                match client.get_results(&sid, 100, 0).await {
-                                    Ok(results) => {
-                                        let mut app = app_clone.lock().await;
-                                        app.search_results = results;
-                                        app.results_fetched = true;
-                                        app.status_message =
-                                            format!("Loaded {} results.", app.search_results.len());
-                                        app.is_status_fetching = false;
-                                        if app.view_mode == ViewMode::Table {
-                                            app.update_detail_view();
-                                        }
-                                    }
-                                    Err(e) => {
-                                        let mut app = app_clone.lock().await;
-                                        error!("Failed to fetch results for job {}: {}", sid, e);
-                                        app.status_message =
-                                            format!("Failed to fetch results: {}", e);
-                                        app.is_status_fetching = false;
-                                    }
-                                }
-                            } else {
-                                // Not done
-                                app.status_message =
-                                    format!("Job running... Dispatched: {}", status.dispatch_state);
-                                app.is_status_fetching = false;
-                            }
-                        }
-                        Err(e) => {
-                            let mut app = app_clone.lock().await;
-                            error!("Failed to check status for job {}: {}", sid, e);
-                            app.is_status_fetching = false;
-                        }
-                    }
-                });
-            }
+            actions.push(Action::Tick);
             last_tick = std::time::Instant::now();
         }
 
@@ -953,680 +4166,18 @@ where
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| std::time::Duration::from_secs(0));
 
-        if crossterm::event::poll(timeout)? {
-            let evt = event::read()?;
-            match evt {
-                Event::Mouse(mouse_event) => {
-                    match mouse_event.kind {
-                        MouseEventKind::ScrollDown => {
-                            if let ViewFocus::Search = app_guard.view_focus {
-                                let line_count = app_guard.input.lines().count();
-                                let max_scroll = line_count.saturating_sub(3); // 3 lines visible (header height 5)
-                                if app_guard.input_scroll < max_scroll as u16 {
-                                    app_guard.input_scroll =
-                                        app_guard.input_scroll.saturating_add(1);
-                                }
-                            } else {
-                                match app_guard.view_mode {
-                                    ViewMode::RawEvents => app_guard.scroll_down(),
-                                    ViewMode::Table => {
-                                        match app_guard.view_focus {
-                                            ViewFocus::ContentList => {
-                                                // Scroll table
-                                                let next = match app_guard.table_state.selected() {
-                                                    Some(i) => {
-                                                        if i >= app_guard
-                                                            .search_results
-                                                            .len()
-                                                            .saturating_sub(1)
-                                                        {
-                                                            i // Stop at bottom (no wrap)
-                                                        } else {
-                                                            i + 1
-                                                        }
-                                                    }
-                                                    None => 0,
-                                                };
-                                                if !app_guard.search_results.is_empty() {
-                                                    app_guard.table_state.select(Some(next));
-                                                    app_guard.detail_scroll = 0;
-                                                    app_guard.update_detail_view();
-                                                }
-                                            }
-                                            ViewFocus::ContentDetail => {
-                                                app_guard.detail_scroll =
-                                                    app_guard.detail_scroll.saturating_add(1);
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        MouseEventKind::ScrollUp => {
-                            if let ViewFocus::Search = app_guard.view_focus {
-                                app_guard.input_scroll = app_guard.input_scroll.saturating_sub(1);
-                            } else {
-                                match app_guard.view_mode {
-                                    ViewMode::RawEvents => app_guard.scroll_up(),
-                                    ViewMode::Table => {
-                                        match app_guard.view_focus {
-                                            ViewFocus::ContentList => {
-                                                let prev = match app_guard.table_state.selected() {
-                                                    Some(i) => {
-                                                        if i == 0 {
-                                                            0 // Stop at top (no wrap)
-                                                        } else {
-                                                            i - 1
-                                                        }
-                                                    }
-                                                    None => 0,
-                                                };
-                                                if !app_guard.search_results.is_empty() {
-                                                    app_guard.table_state.select(Some(prev));
-                                                    app_guard.detail_scroll = 0;
-                                                    app_guard.update_detail_view();
-                                                }
-                                            }
-                                            ViewFocus::ContentDetail => {
-                                                app_guard.detail_scroll =
-                                                    app_guard.detail_scroll.saturating_sub(1);
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                    if let MouseEventKind::Down(MouseButton::Left) = mouse_event.kind {
-                        let col = mouse_event.column;
-                        let row = mouse_event.row;
-
-                        if is_inside(app_guard.search_area, col, row) {
-                            app_guard.view_focus = ViewFocus::Search;
-                            app_guard.input_mode = InputMode::Editing;
-
-                            // Calculate cursor position from mouse click
-                            let rel_x = col.saturating_sub(app_guard.search_area.x + 1); // +1 for border
-                            let rel_y = row.saturating_sub(app_guard.search_area.y + 1); // +1 for border
-
-                            let target_line_idx = (rel_y + app_guard.input_scroll) as usize;
-                            let target_col_idx = (rel_x + app_guard.input_scroll_x) as usize;
-
-                            let lines: Vec<&str> = app_guard.input.lines().collect();
-                            if target_line_idx < lines.len() {
-                                let line = lines[target_line_idx];
-                                // Calculate byte offset up to this line
-                                let mut offset = 0;
-                                for line in lines.iter().take(target_line_idx) {
-                                    offset += line.len() + 1; // +1 for newline
-                                }
-
-                                // Add column offset (clamped to line length)
-                                let col_bytes = line
-                                    .chars()
-                                    .take(target_col_idx)
-                                    .map(|c| c.len_utf8())
-                                    .sum::<usize>();
-                                offset += col_bytes.min(line.len());
-
-                                app_guard.cursor_position = offset;
-                            } else if !lines.is_empty() {
-                                // Clicked below text, move to end
-                                app_guard.cursor_position = app_guard.input.len();
-                            }
-                        } else if is_inside(app_guard.main_area, col, row) {
-                            app_guard.view_focus = ViewFocus::ContentList;
-                        } else if is_inside(app_guard.detail_area, col, row) {
-                            app_guard.view_focus = ViewFocus::ContentDetail;
-                        }
-                    }
-                }
+        if actions.is_empty() && crossterm::event::poll(timeout)? {
+            match event::read()? {
+                Event::Mouse(mouse_event) => actions.push(Action::Mouse(mouse_event)),
                 Event::Key(key) => {
                     info!("Key event received: {:?}", key);
-                    // Global Key Handlers (Pre-InputMode)
-                    // Check for Ctrl + / (and variants like Ctrl + _ or Ctrl + ?)
-                    if key
-                        .modifiers
-                        .contains(crossterm::event::KeyModifiers::CONTROL)
-                    {
-                        match key.code {
-                            KeyCode::Char('/')
-                            | KeyCode::Char('_')
-                            | KeyCode::Char('?')
-                            | KeyCode::Char('7') => {
-                                app_guard.input_mode = InputMode::Help;
-                                // Skip further processing for this key to prevent typing it
-                                continue;
-                            }
-                            _ => {}
-                        }
-                    }
-
-                    match app_guard.input_mode {
-                        InputMode::Normal => match key.code {
-                            KeyCode::Char('e') => {
-                                app_guard.input_mode = InputMode::Editing;
-                                app_guard.status_message = String::from(
-                                    "Editing... Press Enter to search, Esc to cancel.",
-                                );
-                                // If re-entering, ensure cursor is valid
-                                app_guard.clamp_cursor();
-                            }
-                            KeyCode::Char('t')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.toggle_theme_selector();
-                            }
-                            // Help
-                            KeyCode::Char('/')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.input_mode = InputMode::Help;
-                            }
-                            KeyCode::Char('t') => {
-                                app_guard.toggle_theme_selector();
-                            }
-                            KeyCode::Char('q') => {
-                                return Ok(());
-                            }
-                            KeyCode::Char('k')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.scroll_up_fast();
-                            }
-                            KeyCode::Char('x')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.open_in_editor();
-                            }
-                            // Rebind Clear to ^R (Reset) to free ^L for Fast Scroll
-                            KeyCode::Char('r')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.clear_results();
-                            }
-                            // Fast Scroll
-                            KeyCode::Char('j')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.scroll_down_fast();
-                            }
-                            KeyCode::Char('l')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.initiate_load_search();
-                            }
-                            // Open URL
-                            KeyCode::Char('E')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::SHIFT) =>
-                            {
-                                app_guard.open_job_url();
-                            }
-                            // Saved Searches
-                            KeyCode::Char('s')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                app_guard.initiate_save_search();
-                            }
-
-                            // Toggle View Mode (Ctrl+v OR Ctrl+m)
-                            KeyCode::Char('v') | KeyCode::Char('m')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                match app_guard.view_mode {
-                                    ViewMode::RawEvents => {
-                                        app_guard.view_mode = ViewMode::Table;
-                                        // Sync selection from scroll_offset
-                                        let idx = app_guard.scroll_offset as usize;
-                                        if idx < app_guard.search_results.len() {
-                                            app_guard.table_state.select(Some(idx));
-                                            app_guard.update_detail_view();
-                                        } else if !app_guard.search_results.is_empty() {
-                                            app_guard.table_state.select(Some(0));
-                                            app_guard.update_detail_view();
-                                        }
-                                    }
-                                    ViewMode::Table => {
-                                        app_guard.view_mode = ViewMode::RawEvents;
-                                        // Sync scroll_offset from table selection
-                                        if let Some(idx) = app_guard.table_state.selected() {
-                                            app_guard.scroll_offset = idx as u16;
-                                            // Warning: truncation if > u16
-                                        }
-                                    }
-                                }
-                                app_guard.status_message =
-                                    format!("Switched to {:?} mode.", app_guard.view_mode);
-                            }
-
-                            // Local Search Trigger
-                            KeyCode::Char('/') => {
-                                app_guard.input_mode = InputMode::LocalSearch;
-                                app_guard.local_search_query.clear();
-                                app_guard.status_message =
-                                    String::from("Enter regex search query...");
-                            }
-
-                            // Local Search Navigation
-                            KeyCode::Char('n') => {
-                                app_guard.next_match();
-                            }
-                            KeyCode::Char('N') => {
-                                app_guard.prev_match();
-                            }
-
-                            KeyCode::Tab => {
-                                app_guard.view_focus = match app_guard.view_focus {
-                                    ViewFocus::Search => ViewFocus::ContentList,
-                                    ViewFocus::ContentList => {
-                                        if app_guard.view_mode == ViewMode::Table {
-                                            ViewFocus::ContentDetail
-                                        } else {
-                                            ViewFocus::Search
-                                        }
-                                    }
-                                    ViewFocus::ContentDetail => ViewFocus::Search,
-                                };
-                            }
-
-                            KeyCode::Left | KeyCode::Char('h') => {
-                                app_guard.view_focus = ViewFocus::ContentList;
-                            }
-
-                            KeyCode::Right | KeyCode::Char('l')
-                                if !key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                if app_guard.view_mode == ViewMode::Table {
-                                    app_guard.view_focus = ViewFocus::ContentDetail;
-                                }
-                            }
-
-                            KeyCode::Down | KeyCode::Char('j')
-                                if !key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                if let ViewFocus::Search = app_guard.view_focus {
-                                    // Optional: Down from Search goes to Content
-                                    app_guard.view_focus = ViewFocus::ContentList;
-                                } else {
-                                    match app_guard.view_mode {
-                                        ViewMode::RawEvents => app_guard.scroll_down(),
-                                        ViewMode::Table => {
-                                            match app_guard.view_focus {
-                                                ViewFocus::ContentList => {
-                                                    let next =
-                                                        match app_guard.table_state.selected() {
-                                                            Some(i) => {
-                                                                if i >= app_guard
-                                                                    .search_results
-                                                                    .len()
-                                                                    .saturating_sub(1)
-                                                                {
-                                                                    i // No wrap
-                                                                } else {
-                                                                    i + 1
-                                                                }
-                                                            }
-                                                            None => 0,
-                                                        };
-                                                    if !app_guard.search_results.is_empty() {
-                                                        app_guard.table_state.select(Some(next));
-                                                        app_guard.detail_scroll = 0; // Reset detail scroll on row change
-                                                        app_guard.update_detail_view();
-                                                    }
-                                                }
-                                                ViewFocus::ContentDetail => {
-                                                    app_guard.detail_scroll =
-                                                        app_guard.detail_scroll.saturating_add(1);
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Up | KeyCode::Char('k')
-                                if !key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                match app_guard.view_mode {
-                                    ViewMode::RawEvents => app_guard.scroll_up(),
-                                    ViewMode::Table => {
-                                        match app_guard.view_focus {
-                                            ViewFocus::ContentList => {
-                                                let prev = match app_guard.table_state.selected() {
-                                                    Some(i) => {
-                                                        if i == 0 {
-                                                            0 // No wrap
-                                                        } else {
-                                                            i - 1
-                                                        }
-                                                    }
-                                                    None => 0,
-                                                };
-                                                if !app_guard.search_results.is_empty() {
-                                                    app_guard.table_state.select(Some(prev));
-                                                    app_guard.detail_scroll = 0;
-                                                    app_guard.update_detail_view();
-                                                }
-                                            }
-                                            ViewFocus::ContentDetail => {
-                                                app_guard.detail_scroll =
-                                                    app_guard.detail_scroll.saturating_sub(1);
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-
-                            KeyCode::Char('x')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                if let ViewFocus::Search = app_guard.view_focus {
-                                    app_guard.open_query_in_editor();
-                                } else {
-                                    app_guard.open_in_editor();
-                                }
-                            }
-                            // 'x' mapping removed as requested
-                            KeyCode::Enter => {
-                                drop(app_guard);
-                                let mut app_guard_search = app.lock().await;
-                                app_guard_search.perform_search().await;
-                                app_guard_search.input_mode = InputMode::Normal;
-                            }
-                            _ => {}
-                        },
-                        InputMode::Editing => {
-                            // Toggle Vim Mode
-                            if key.code == KeyCode::Char('v')
-                                && key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL)
-                            {
-                                app_guard.toggle_vim_mode();
-                                let mode_msg = match app_guard.editor_mode {
-                                    EditorMode::Standard => "Standard Mode",
-                                    EditorMode::Vim(_) => "Vim Mode",
-                                };
-                                app_guard.status_message = format!("Switched to {}.", mode_msg);
-                                continue; // Skip other handlers
-                            }
-
-                            match app_guard.editor_mode {
-                                EditorMode::Standard => match key.code {
-                                    KeyCode::Enter
-                                        if key
-                                            .modifiers
-                                            .contains(crossterm::event::KeyModifiers::SHIFT) =>
-                                    {
-                                        app_guard.insert_char('\n');
-                                    }
-                                    KeyCode::Enter => {
-                                        drop(app_guard);
-                                        let mut app_guard_search = app.lock().await;
-                                        app_guard_search.perform_search().await;
-                                        app_guard_search.input_mode = InputMode::Normal;
-                                    }
-                                    KeyCode::Char('j')
-                                        if key
-                                            .modifiers
-                                            .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                                    {
-                                        app_guard.insert_char('\n');
-                                    }
-                                    KeyCode::Char('x')
-                                        if key
-                                            .modifiers
-                                            .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                                    {
-                                        app_guard.open_query_in_editor();
-                                    }
-                                    KeyCode::Char(c) => {
-                                        if !c.is_control() {
-                                            app_guard.insert_char(c);
-                                        }
-                                    }
-                                    KeyCode::Backspace => {
-                                        app_guard.delete_char();
-                                    }
-                                    KeyCode::Left => app_guard.move_cursor_left(),
-                                    KeyCode::Right => app_guard.move_cursor_right(),
-                                    KeyCode::Up => app_guard.move_cursor_up(),
-                                    KeyCode::Down => app_guard.move_cursor_down(),
-                                    KeyCode::Esc => {
-                                        app_guard.input_mode = InputMode::Normal;
-                                        app_guard.status_message =
-                                            String::from("Search cancelled.");
-                                    }
-                                    _ => {}
-                                },
-                                EditorMode::Vim(state) => match state {
-                                    VimState::Normal => match key.code {
-                                        KeyCode::Char('i') => {
-                                            app_guard.editor_mode =
-                                                EditorMode::Vim(VimState::Insert);
-                                            app_guard.status_message = String::from("-- INSERT --");
-                                        }
-                                        KeyCode::Char('h') | KeyCode::Left => {
-                                            app_guard.move_cursor_left()
-                                        }
-                                        KeyCode::Char('l') | KeyCode::Right => {
-                                            app_guard.move_cursor_right()
-                                        }
-                                        KeyCode::Char('k') | KeyCode::Up => {
-                                            app_guard.move_cursor_up()
-                                        }
-                                        KeyCode::Char('j') | KeyCode::Down => {
-                                            app_guard.move_cursor_down()
-                                        }
-                                        KeyCode::Char('x') => { // Delete char in normal mode
-                                             // vim 'x' deletes char under cursor.
-                                             // Current delete_char deletes BEFORE cursor (backspace style).
-                                             // We need delete_curr_char.
-                                             // For now, let's just ignore or implement later.
-                                             // Let's implement basics.
-                                        }
-                                        KeyCode::Enter => {
-                                            // Allow search submission from Normal mode?
-                                            // Usually 'Enter' in Normal mode goes down.
-                                            // But this is a search editor.
-                                            // Let's keep Enter to submit.
-                                            drop(app_guard);
-                                            let mut app_guard_search = app.lock().await;
-                                            app_guard_search.perform_search().await;
-                                            app_guard_search.input_mode = InputMode::Normal;
-                                        }
-                                        KeyCode::Esc => {
-                                            // Exit editing entirely?
-                                            app_guard.input_mode = InputMode::Normal;
-                                            app_guard.status_message =
-                                                String::from("Search cancelled.");
-                                        }
-                                        _ => {}
-                                    },
-                                    VimState::Insert => match key.code {
-                                        KeyCode::Esc => {
-                                            app_guard.editor_mode =
-                                                EditorMode::Vim(VimState::Normal);
-                                            app_guard.status_message = String::from("-- NORMAL --");
-                                            app_guard.move_cursor_left(); // Vim usually moves cursor left on Esc
-                                        }
-                                        KeyCode::Enter
-                                            if key.modifiers.contains(
-                                                crossterm::event::KeyModifiers::SHIFT,
-                                            ) =>
-                                        {
-                                            app_guard.insert_char('\n');
-                                        }
-                                        KeyCode::Enter => {
-                                            drop(app_guard);
-                                            let mut app_guard_search = app.lock().await;
-                                            app_guard_search.perform_search().await;
-                                            app_guard_search.input_mode = InputMode::Normal;
-                                        }
-                                        KeyCode::Char(c) => {
-                                            if !c.is_control() {
-                                                app_guard.insert_char(c);
-                                            }
-                                        }
-                                        KeyCode::Backspace => app_guard.delete_char(),
-                                        _ => {}
-                                    },
-                                },
-                            }
-                        }
-                        InputMode::SaveSearch => match key.code {
-                            KeyCode::Enter => {
-                                app_guard.save_current_search();
-                            }
-                            KeyCode::Char(c) => {
-                                app_guard.save_search_name.push(c);
-                            }
-                            KeyCode::Backspace => {
-                                app_guard.save_search_name.pop();
-                            }
-                            KeyCode::Esc => {
-                                app_guard.input_mode = InputMode::Normal;
-                                app_guard.status_message = String::from("Save cancelled.");
-                            }
-                            _ => {}
-                        },
-                        InputMode::ConfirmOverwrite => match key.code {
-                            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                                app_guard.overwrite_current_search();
-                            }
-                            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                                app_guard.input_mode = InputMode::Normal;
-                                app_guard.status_message = String::from("Save cancelled.");
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                app_guard.input_mode = InputMode::SaveSearch;
-                                app_guard.save_search_name = app_guard
-                                    .current_saved_search_name
-                                    .clone()
-                                    .unwrap_or_default();
-                                app_guard.status_message =
-                                    String::from("Enter name for saved search:");
-                            }
-                            _ => {}
-                        },
-                        InputMode::LoadSearch => match key.code {
-                            KeyCode::Enter => {
-                                app_guard.load_selected_search();
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                app_guard.list_next();
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                app_guard.list_previous();
-                            }
-                            KeyCode::Esc => {
-                                app_guard.input_mode = InputMode::Normal;
-                                app_guard.status_message = String::from("Load cancelled.");
-                            }
-                            _ => {}
-                        },
-                        InputMode::LocalSearch => match key.code {
-                            KeyCode::Enter => {
-                                app_guard.perform_local_search();
-                                app_guard.input_mode = InputMode::Normal;
-                            }
-                            KeyCode::Char(c) => {
-                                app_guard.local_search_query.push(c);
-                            }
-                            KeyCode::Backspace => {
-                                app_guard.local_search_query.pop();
-                            }
-                            KeyCode::Esc => {
-                                app_guard.input_mode = InputMode::Normal;
-                                app_guard.status_message = String::from("Local search cancelled.");
-                            }
-                            _ => {}
-                        },
-                        InputMode::ThemeSelect => match key.code {
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                let i = match app_guard.theme_list_state.selected() {
-                                    Some(i) => {
-                                        if i >= app_guard.theme_options.len() - 1 {
-                                            0
-                                        } else {
-                                            i + 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app_guard.theme_list_state.select(Some(i));
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                let i = match app_guard.theme_list_state.selected() {
-                                    Some(i) => {
-                                        if i == 0 {
-                                            app_guard.theme_options.len() - 1
-                                        } else {
-                                            i - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app_guard.theme_list_state.select(Some(i));
-                            }
-                            KeyCode::Enter => {
-                                if let Some(idx) = app_guard.theme_list_state.selected() {
-                                    let theme_name = app_guard.theme_options[idx];
-                                    app_guard.apply_theme(theme_name, true);
-                                    app_guard.status_message =
-                                        format!("Theme '{}' applied.", theme_name);
-                                }
-                                app_guard.input_mode = InputMode::Normal;
-                            }
-                            KeyCode::Esc => {
-                                app_guard.input_mode = InputMode::Normal;
-                                app_guard.status_message =
-                                    String::from("Theme selection cancelled.");
-                            }
-                            _ => {}
-                        },
-                        InputMode::Help => match key.code {
-                            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
-                                app_guard.input_mode = InputMode::Normal;
-                            }
-                            _ => {}
-                        },
-                    } // End InputMode match
-                } // End Key match
+                    actions.push(Action::Key(key));
+                }
                 _ => {}
-            } // End Event match
+            }
+        }
+        for action in actions {
+            effects.extend(app.update(action));
         }
     }
 }
@@ -1678,33 +4229,692 @@ fn syntect_style_to_ratatui(style: syntect::highlighting::Style) -> Style {
     if style.font_style.contains(FontStyle::ITALIC) {
         s = s.add_modifier(Modifier::ITALIC);
     }
-    if style.font_style.contains(FontStyle::UNDERLINE) {
-        s = s.add_modifier(Modifier::UNDERLINED);
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        s = s.add_modifier(Modifier::UNDERLINED);
+    }
+
+    s
+}
+
+/// Large-digit panels for single-row KPI results, colored by the saved
+/// search's thresholds and showing the delta against its comparison run.
+fn render_stat(
+    f: &mut Frame,
+    theme: &AppTheme,
+    results: &[Value],
+    config: &StatConfig,
+    compare: Option<&[Value]>,
+    area: Rect,
+) {
+    let Some(values) = stat::values(results) else {
+        let text =
+            Paragraph::new("Single-value panels need one row with up to four numeric fields.")
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(theme.text));
+        f.render_widget(text, area);
+        return;
+    };
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, values.len() as u32);
+            values.len()
+        ])
+        .split(area);
+
+    for ((field, value), panel) in values.iter().zip(panels.iter()) {
+        let color = stat::threshold_color(*value, &config.thresholds)
+            .and_then(|c| c.parse::<Color>().ok())
+            .unwrap_or(theme.title_main);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(theme.border))
+            .title(field.clone());
+        let inner = block.inner(*panel);
+        f.render_widget(block, *panel);
+
+        let text = stat::format_number(*value);
+        let style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+        let mut lines: Vec<Line> = match stat::big_text(&text) {
+            Some(rows) if rows[0].chars().count() <= inner.width as usize => rows
+                .into_iter()
+                .map(|row| Line::styled(row, style))
+                .collect(),
+            _ => vec![Line::styled(text, style)],
+        };
+
+        if let Some(previous) = compare.and_then(|results| stat::compare_value(results, field)) {
+            let delta = value - previous;
+            let arrow = match delta.partial_cmp(&0.0) {
+                Some(std::cmp::Ordering::Greater) => "▲",
+                Some(std::cmp::Ordering::Less) => "▼",
+                _ => "=",
+            };
+            let percent = if previous != 0.0 {
+                format!(" ({:+.1}%)", delta / previous.abs() * 100.0)
+            } else {
+                String::new()
+            };
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                format!(
+                    "{} {}{} vs {}",
+                    arrow,
+                    stat::format_number(delta),
+                    percent,
+                    stat::format_number(previous)
+                ),
+                Style::default().fg(theme.title_secondary),
+            ));
+        }
+
+        let top = inner.height.saturating_sub(lines.len() as u16) / 2;
+        let body = Rect {
+            y: inner.y + top,
+            height: inner.height - top,
+            ..inner
+        };
+        f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), body);
+    }
+}
+
+/// Draws the job's timeline as a histogram, one column per (merged) bucket,
+/// highlighting the selection while the timeline is focused.
+fn render_timeline(f: &mut Frame, app: &mut App, area: Rect) {
+    const LEVELS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let height = area.height.saturating_sub(2) as usize;
+    app.timeline_columns = timeline::columns(&app.timeline, inner_width);
+    let columns = &app.timeline_columns;
+    let Some((first, last)) = columns.first().zip(columns.last()) else {
+        return;
+    };
+
+    let focused = matches!(app.input_mode, InputMode::Timeline);
+    let cursor = app.timeline_cursor.min(columns.len() - 1);
+    let anchor = app.timeline_anchor.unwrap_or(cursor);
+    let selected = anchor.min(cursor)..=anchor.max(cursor);
+    let max = columns.iter().map(|c| c.count).max().unwrap_or(0).max(1);
+
+    let lines: Vec<Line> = (0..height)
+        .map(|row| {
+            // Eighths of a cell filled below this row.
+            let floor = (height - 1 - row) * 8;
+            let spans: Vec<Span> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    let level = (column.count * (height * 8) as u64).div_ceil(max) as usize;
+                    let fill = level.saturating_sub(floor).min(8);
+                    let mut style = Style::default().fg(app.theme.summary_highlight);
+                    if focused && selected.contains(&i) {
+                        style = style.fg(app.theme.active_label);
+                    }
+                    if focused && i == cursor {
+                        style = style.bg(app.theme.separator);
+                    }
+                    Span::styled(LEVELS[fill], style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+
+    let total: u64 = columns.iter().map(|c| c.count).sum();
+    let mut title = format!("Timeline ({} events)", total);
+    if focused {
+        if let Some((earliest, latest)) = app.timeline_selection() {
+            let count: u64 = columns[selected].iter().map(|c| c.count).sum();
+            title = format!(
+                "Timeline: {} to {} ({} events)",
+                timeline::format_time(earliest, app.utc_offset),
+                timeline::format_time(latest, app.utc_offset),
+                count
+            );
+        }
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(if focused {
+            app.theme.active_label
+        } else {
+            app.theme.border
+        }))
+        .title(title)
+        .title_bottom(
+            Line::from(timeline::format_time(first.earliest, app.utc_offset)).left_aligned(),
+        )
+        .title_bottom(
+            Line::from(timeline::format_time(last.latest, app.utc_offset)).right_aligned(),
+        );
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Colors for chart series, starting from the theme's accents.
+fn series_color(theme: &AppTheme, index: usize) -> Color {
+    let palette = [
+        theme.summary_highlight,
+        theme.title_main,
+        theme.active_label,
+        theme.title_secondary,
+        theme.error,
+        Color::Cyan,
+        Color::Yellow,
+        Color::Magenta,
+        Color::Green,
+    ];
+    palette[index % palette.len()]
+}
+
+/// What to chart and how: shared by the chart view and dashboard panels.
+struct ChartView<'a> {
+    results: &'a [Value],
+    kind: ChartKind,
+    hidden: &'a std::collections::BTreeSet<String>,
+    cursor: usize,
+    /// Show the legend with the values at the cursor.
+    legend: bool,
+}
+
+/// Renders transforming results as a line, bar or stacked-area chart with a
+/// legend showing each series' value at the cursor.
+fn render_chart(f: &mut Frame, theme: &AppTheme, view: &ChartView, area: Rect) {
+    let Some(data) = ChartData::from_results(view.results) else {
+        let text =
+            Paragraph::new("No numeric columns to chart. Try a timechart, chart or stats search.")
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(theme.text));
+        f.render_widget(text, area);
+        return;
+    };
+    let visible: Vec<bool> = data
+        .series
+        .iter()
+        .map(|s| !view.hidden.contains(&s.name))
+        .collect();
+    let cursor = view.cursor.min(data.len().saturating_sub(1));
+
+    let legend_width = if view.legend { 32 } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(20), Constraint::Length(legend_width)])
+        .split(area);
+
+    // Legend: cursor position, then each series with its value there.
+    let mut legend = vec![
+        Line::styled(
+            format!("{} {}", data.x_field, data.x_labels[cursor]),
+            Style::default()
+                .fg(theme.title_secondary)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Line::raw(""),
+    ];
+    for (i, series) in data.series.iter().enumerate() {
+        let value = series.values[cursor].map(format_value).unwrap_or_default();
+        let (marker, style) = if visible[i] {
+            ("■", Style::default().fg(series_color(theme, i)))
+        } else {
+            ("□", Style::default().fg(theme.separator))
+        };
+        legend.push(Line::from(vec![
+            Span::styled(format!("{} {} ", i + 1, marker), style),
+            Span::styled(format!("{} ", series.name), Style::default().fg(theme.text)),
+            Span::styled(value, style.add_modifier(Modifier::BOLD)),
+        ]));
+    }
+    legend.push(Line::raw(""));
+    legend.push(Line::styled(
+        "←/→ move | 1-9 toggle | c type",
+        Style::default().fg(theme.separator),
+    ));
+    f.render_widget(
+        Paragraph::new(legend).block(
+            Block::default()
+                .borders(Borders::LEFT)
+                .border_style(Style::default().fg(theme.border))
+                .padding(Padding::new(1, 0, 0, 0)),
+        ),
+        chunks[1],
+    );
+
+    let chart_area = chunks[0];
+    if view.kind == ChartKind::Bar {
+        render_bar_chart(f, theme, &data, &visible, cursor, chart_area);
+        return;
+    }
+
+    let stacked = view.kind == ChartKind::StackedArea;
+    let (y_min, y_max) = data.bounds(&visible, stacked);
+    let x_max = (data.len().saturating_sub(1) as f64).max(1.0);
+
+    // Points per dataset, kept alongside the series index for its color.
+    let mut points: Vec<(usize, Vec<(f64, f64)>)> = Vec::new();
+    if stacked {
+        // Draw the top band first so each lower band paints over it; sample
+        // between rows so the bands read as filled areas.
+        let samples = (chart_area.width as usize * 4).max(2);
+        let indices: Vec<usize> = (0..data.series.len()).filter(|i| visible[*i]).collect();
+        for (band, totals) in data.stacked(&visible).iter().enumerate().rev() {
+            let filled = (0..=samples)
+                .map(|s| {
+                    let x = x_max * s as f64 / samples as f64;
+                    let lo = (x.floor() as usize).min(totals.len() - 1);
+                    let hi = (lo + 1).min(totals.len() - 1);
+                    let t = x - lo as f64;
+                    (x, totals[lo] + (totals[hi] - totals[lo]) * t)
+                })
+                .collect();
+            points.push((indices[band], filled));
+        }
+    } else {
+        for (i, series) in data.series.iter().enumerate() {
+            if visible[i] {
+                let line = series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(x, v)| v.map(|v| (x as f64, v)))
+                    .collect();
+                points.push((i, line));
+            }
+        }
+    }
+    let cursor_line = [(cursor as f64, y_min), (cursor as f64, y_max)];
+
+    let mut datasets: Vec<Dataset> = points
+        .iter()
+        .map(|(i, pts)| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(if stacked {
+                    GraphType::Bar
+                } else {
+                    GraphType::Line
+                })
+                .style(Style::default().fg(series_color(theme, *i)))
+                .data(pts)
+        })
+        .collect();
+    datasets.push(
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.text))
+            .data(&cursor_line),
+    );
+
+    let last = data.len() - 1;
+    let x_labels = vec![
+        Span::raw(data.x_labels[0].clone()),
+        Span::raw(data.x_labels[last / 2].clone()),
+        Span::raw(data.x_labels[last].clone()),
+    ];
+    let y_labels = vec![
+        Span::raw(format_value(y_min)),
+        Span::raw(format_value((y_min + y_max) / 2.0)),
+        Span::raw(format_value(y_max)),
+    ];
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.border))
+                .bounds([0.0, x_max])
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.border))
+                .bounds([y_min, y_max])
+                .labels(y_labels),
+        );
+    f.render_widget(chart, chart_area);
+}
+
+/// Grouped bars, one group per row, scrolled so the cursor's group is visible.
+fn render_bar_chart(
+    f: &mut Frame,
+    theme: &AppTheme,
+    data: &ChartData,
+    visible: &[bool],
+    cursor: usize,
+    area: Rect,
+) {
+    let shown: Vec<usize> = (0..data.series.len()).filter(|i| visible[*i]).collect();
+    if shown.is_empty() {
+        return;
+    }
+    let (_, y_max) = data.bounds(visible, false);
+    // BarChart takes integers, so scale values and show the real ones as text.
+    let scale = 1000.0 / y_max;
+
+    let per_group = shown.len() as u16;
+    let bar_width =
+        ((area.width / data.len().max(1) as u16).saturating_sub(1) / per_group).clamp(1, 8);
+    let group_width = bar_width * per_group + 1;
+    let fits = (area.width / group_width).max(1) as usize;
+    let start = cursor.saturating_sub(fits - 1);
+
+    let groups: Vec<BarGroup> = (start..data.len().min(start + fits))
+        .map(|row| {
+            let bars: Vec<Bar> = shown
+                .iter()
+                .map(|i| {
+                    let value = data.series[*i].values[row].unwrap_or(0.0);
+                    let mut style = Style::default().fg(series_color(theme, *i));
+                    if row == cursor {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    Bar::default()
+                        .value((value.max(0.0) * scale) as u64)
+                        .text_value(format_value(value))
+                        .style(style)
+                })
+                .collect();
+            let label_style = if row == cursor {
+                Style::default()
+                    .fg(theme.title_secondary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            // Keep the end of timestamps (the time) and the start of anything else.
+            let label = &data.x_labels[row];
+            let width = (group_width - 1) as usize;
+            let label: String = if data.x_field == "_time" {
+                let skip = label.chars().count().saturating_sub(width);
+                label.chars().skip(skip).collect()
+            } else {
+                label.chars().take(width).collect()
+            };
+            BarGroup::default()
+                .label(Line::styled(label, label_style))
+                .bars(&bars)
+        })
+        .collect();
+
+    let mut chart = BarChart::default()
+        .bar_width(bar_width)
+        .bar_gap(0)
+        .group_gap(1)
+        .max(1000);
+    for group in groups {
+        chart = chart.data(group);
+    }
+    f.render_widget(chart, area);
+}
+
+/// Lays the dashboard's panels out row by row, scrolled so the selected
+/// panel is visible.
+fn render_dashboard(
+    f: &mut Frame,
+    theme: &AppTheme,
+    view: &DashboardView,
+    focused: bool,
+    area: Rect,
+) {
+    let title = if view.dashboard.title.is_empty() {
+        "Dashboard".to_string()
+    } else {
+        format!("Dashboard: {}", view.dashboard.title)
+    };
+    let inputs: Vec<String> = view
+        .dashboard
+        .inputs
+        .iter()
+        .map(|i| format!("{}={}", i.token, view.values[&i.token]))
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.border))
+        .title(title)
+        .title_bottom(Line::styled(
+            inputs.join("  "),
+            Style::default().fg(theme.separator),
+        ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let row_count = view.dashboard.panels.last().map_or(0, |p| p.row + 1);
+    let row_height = (inner.height / row_count.max(1) as u16)
+        .max(8)
+        .min(inner.height);
+    let rows_shown = (inner.height / row_height.max(1)).max(1) as usize;
+    let selected_row = view.dashboard.panels[view.selected].row;
+    let first_row = selected_row.saturating_sub(rows_shown - 1);
+
+    for (slot, row) in (first_row..row_count.min(first_row + rows_shown)).enumerate() {
+        let row_area = Rect {
+            y: inner.y + slot as u16 * row_height,
+            height: row_height,
+            ..inner
+        };
+        let members: Vec<usize> = (0..view.dashboard.panels.len())
+            .filter(|i| view.dashboard.panels[*i].row == row)
+            .collect();
+        let total: u32 = members
+            .iter()
+            .map(|i| view.dashboard.panels[*i].width)
+            .sum();
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                members
+                    .iter()
+                    .map(|i| Constraint::Ratio(view.dashboard.panels[*i].width, total))
+                    .collect::<Vec<_>>(),
+            )
+            .split(row_area);
+        for (i, cell) in members.iter().zip(cells.iter()) {
+            render_dashboard_panel(f, theme, view, *i, focused && *i == view.selected, *cell);
+        }
+    }
+}
+
+fn render_dashboard_panel(
+    f: &mut Frame,
+    theme: &AppTheme,
+    view: &DashboardView,
+    index: usize,
+    selected: bool,
+    area: Rect,
+) {
+    let panel = &view.dashboard.panels[index];
+    let state = &view.panels[index];
+    let mut title = if panel.title.is_empty() {
+        format!("Panel {}", index + 1)
+    } else {
+        panel.title.clone()
+    };
+    if state.running {
+        title.push_str(" (running)");
+    }
+    let border = if selected {
+        theme.title_main
+    } else {
+        theme.border
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(border))
+        .title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    if let Some(e) = &state.error {
+        f.render_widget(
+            Paragraph::new(e.as_str())
+                .wrap(Wrap { trim: true })
+                .style(Style::default().fg(theme.error)),
+            inner,
+        );
+        return;
+    }
+    if state.results.is_empty() {
+        let text = if state.running || state.last_run.is_none() {
+            "Loading..."
+        } else {
+            "No results."
+        };
+        f.render_widget(
+            Paragraph::new(text)
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(theme.separator)),
+            inner,
+        );
+        return;
+    }
+
+    match panel.kind {
+        PanelKind::Chart(kind) => {
+            let view = ChartView {
+                results: &state.results,
+                kind,
+                hidden: &Default::default(),
+                cursor: 0,
+                legend: false,
+            };
+            render_chart(f, theme, &view, inner);
+        }
+        PanelKind::Single => {
+            render_stat(
+                f,
+                theme,
+                &state.results,
+                &StatConfig::default(),
+                None,
+                inner,
+            );
+        }
+        PanelKind::Table | PanelKind::Events => {
+            render_results_table(
+                f,
+                theme,
+                &state.results,
+                panel.kind == PanelKind::Events,
+                inner,
+            );
+        }
+    }
+}
+
+fn match_style(theme: &AppTheme) -> Style {
+    Style::default().bg(theme.input_edit).fg(Color::Black)
+}
+
+/// Patches `style` onto the byte `ranges` of the line's text, splitting spans
+/// where a range starts or ends inside one.
+fn highlight_line<'a>(line: Line<'a>, ranges: &[(usize, usize)], style: Style) -> Line<'a> {
+    if ranges.is_empty() {
+        return line;
+    }
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in &line.spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+        let mut cuts = vec![0, content.len()];
+        for (start, stop) in ranges {
+            for cut in [*start, *stop] {
+                if cut > offset && cut < end {
+                    cuts.push(cut - offset);
+                }
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+        for piece in cuts.windows(2) {
+            let at = offset + piece[0];
+            let hit = ranges
+                .iter()
+                .any(|(start, stop)| at >= *start && at < *stop);
+            let piece_style = if hit {
+                span.style.patch(style)
+            } else {
+                span.style
+            };
+            spans.push(Span::styled(
+                content[piece[0]..piece[1]].to_string(),
+                piece_style,
+            ));
+        }
+        offset = end;
+    }
+    Line { spans, ..line }
+}
+
+/// Columns of the results in first-seen order, hiding internal fields other
+/// than `_time`.
+fn result_columns(results: &[Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in results {
+        for key in row.as_object().map(|o| o.keys()).into_iter().flatten() {
+            if (key == "_time" || !key.starts_with('_')) && !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
     }
+    columns
+}
 
-    s
+/// Plain results table: every column (internal fields other than `_time`
+/// hidden), or `_time` and the first line of `_raw` for events.
+fn render_results_table(
+    f: &mut Frame,
+    theme: &AppTheme,
+    results: &[Value],
+    events: bool,
+    area: Rect,
+) {
+    let columns: Vec<String> = if events {
+        vec!["_time".to_string(), "_raw".to_string()]
+    } else {
+        result_columns(results)
+    };
+    let rows: Vec<Row> = results
+        .iter()
+        .map(|row| {
+            Row::new(columns.iter().map(|col| match row.get(col) {
+                Some(Value::String(s)) => s.lines().next().unwrap_or_default().to_string(),
+                Some(Value::Null) | None => String::new(),
+                Some(v) => v.to_string(),
+            }))
+            .style(Style::default().fg(theme.text))
+        })
+        .collect();
+    let widths = if events {
+        vec![Constraint::Length(24), Constraint::Fill(1)]
+    } else {
+        vec![Constraint::Fill(1); columns.len()]
+    };
+    let header = Row::new(columns).style(
+        Style::default()
+            .fg(theme.title_secondary)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_widget(Table::new(rows, widths).header(header), area);
 }
 
-fn render_yaml_detail(
+/// Highlights `text` with the syntect syntax for `extension`.
+fn render_highlighted(
     syntax_set: &SyntaxSet,
     theme: &Theme,
-    value: &Value,
+    text: &str,
+    extension: &str,
 ) -> ratatui::text::Text<'static> {
-    // 1. Recursive Parse
-    let parsed_value = recursive_json_parse(value.clone());
-
-    // 2. Convert to YAML
-    let yaml_str = serde_yaml::to_string(&parsed_value)
-        .unwrap_or_else(|e| format!("Error converting to YAML: {}", e));
-
-    // 3. Highlight
     let syntax = syntax_set
-        .find_syntax_by_extension("yaml")
+        .find_syntax_by_extension(extension)
         .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
     let mut h = HighlightLines::new(syntax, theme);
 
     let mut lines = Vec::new();
-    for line in yaml_str.lines() {
+    for line in text.lines() {
         let ranges: Vec<(syntect::highlighting::Style, &str)> =
             h.highlight_line(line, syntax_set).unwrap_or_default();
         let spans: Vec<Span> = ranges
@@ -1717,16 +4927,113 @@ fn render_yaml_detail(
     ratatui::text::Text::from(lines)
 }
 
+/// One line per tree row: a fold marker on containers, the key, and the
+/// scalar value or the child count.
+fn tree_lines(theme: &AppTheme, value: &Value, rows: &[json_tree::Row]) -> Vec<Line<'static>> {
+    rows.iter()
+        .map(|row| {
+            let (marker, summary) = match (row.children, json_tree::get(value, &row.path)) {
+                (Some(n), Some(Value::Array(_))) => (row.folded, format!("[{}]", n)),
+                (Some(n), _) => (row.folded, format!("{{{}}}", n)),
+                (None, Some(v)) => (false, v.to_string()),
+                (None, None) => (false, String::new()),
+            };
+            let marker = match (row.children, marker) {
+                (None, _) => "  ",
+                (Some(_), true) => "▸ ",
+                (Some(_), false) => "▾ ",
+            };
+            let summary_style = if row.children.is_some() {
+                Style::default().fg(theme.date_label)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            Line::from(vec![
+                Span::raw(format!("{}{}", "  ".repeat(row.depth), marker)),
+                Span::styled(row.label(), Style::default().fg(theme.title_secondary)),
+                Span::raw(": "),
+                Span::styled(summary, summary_style),
+            ])
+        })
+        .collect()
+}
+
+/// Colours SPL by token kind using the active theme.
+/// Splits editor text into lines, styling the byte range `selection`.
+fn selection_lines(text: &str, selection: Option<(usize, usize)>, style: Style) -> Vec<Line<'_>> {
+    let mut offset = 0;
+    text.split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            let Some((sel_start, sel_end)) = selection else {
+                return Line::raw(line);
+            };
+            let a = sel_start.clamp(start, start + line.len()) - start;
+            let b = sel_end.clamp(start, start + line.len()) - start;
+            Line::from(vec![
+                Span::raw(&line[..a]),
+                Span::styled(&line[a..b], style),
+                Span::raw(&line[b..]),
+            ])
+        })
+        .collect()
+}
+
+fn highlight_spl(query: &str, theme: &AppTheme) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::default()];
+    for token in tokenize(query) {
+        let style = match token.kind {
+            TokenKind::Command => Style::default()
+                .fg(theme.title_main)
+                .add_modifier(Modifier::BOLD),
+            TokenKind::Pipe | TokenKind::LBracket | TokenKind::RBracket => {
+                Style::default().fg(theme.summary_highlight)
+            }
+            TokenKind::Keyword | TokenKind::Boolean => Style::default()
+                .fg(theme.title_secondary)
+                .add_modifier(Modifier::BOLD),
+            TokenKind::Field => Style::default().fg(theme.owner_label),
+            TokenKind::Operator => Style::default().fg(theme.separator),
+            TokenKind::String => Style::default().fg(theme.input_edit),
+            TokenKind::Number => Style::default().fg(theme.summary_highlight),
+            TokenKind::Macro => Style::default()
+                .fg(theme.title_secondary)
+                .add_modifier(Modifier::ITALIC),
+            TokenKind::Comment => Style::default()
+                .fg(theme.date_label)
+                .add_modifier(Modifier::ITALIC),
+            _ => Style::default().fg(theme.text),
+        };
+        // Tokens (whitespace, strings, comments) may span lines.
+        for (i, part) in token.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+            if !part.is_empty() {
+                if let Some(line) = lines.last_mut() {
+                    line.push_span(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+    }
+    lines
+}
+
 fn ui(f: &mut Frame, app: &mut App) {
     let header_height = 5; // Fixed height: 5 cells total = 3 content lines + 2 borders
+    let editing_pipeline = matches!(app.input_mode, InputMode::LocalPipeline);
+    let pipeline_shown = editing_pipeline || !app.local_pipeline.is_empty();
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints(
             [
-                Constraint::Length(header_height), // Header: Search + Sparkline
+                Constraint::Length(header_height), // Header: Search
                 Constraint::Length(1),             // Job Status (no block borders)
+                Constraint::Length(if app.timeline.is_empty() { 0 } else { 7 }), // Timeline
+                Constraint::Length(if pipeline_shown { 3 } else { 0 }), // Local pipeline
                 Constraint::Min(10),               // Content
                 Constraint::Length(1),             // Footer (Navigation, centered, one line)
             ]
@@ -1735,13 +5042,10 @@ fn ui(f: &mut Frame, app: &mut App) {
         .split(f.area());
 
     // --- Header ---
-    let header_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
-        .split(chunks[0]);
+    let search_area = chunks[0];
 
     // Store search area for mouse interaction
-    app.search_area = header_chunks[0];
+    app.search_area = search_area;
 
     // 1. Search Input
     let input_style = match app.input_mode {
@@ -1752,7 +5056,7 @@ fn ui(f: &mut Frame, app: &mut App) {
     };
 
     // Input Area calculations
-    let input_area_width = header_chunks[0].width.saturating_sub(2); // Minus borders
+    let input_area_width = search_area.width.saturating_sub(2); // Minus borders
     let input_display_height = header_height.saturating_sub(2); // 1 line visible
 
     // Auto-scroll logic: Ensure cursor is visible
@@ -1760,13 +5064,9 @@ fn ui(f: &mut Frame, app: &mut App) {
     let text_before = &app.input[..cursor_byte_idx.min(app.input.len())];
     let cursor_line_idx = text_before.matches('\n').count() as u16;
 
-    // Calculate cursor column (visual width check)
-    let last_nl_idx = text_before.rfind('\n');
-    let cursor_col_idx = if let Some(nl) = last_nl_idx {
-        cursor_byte_idx - (nl + 1)
-    } else {
-        cursor_byte_idx
-    } as u16;
+    // Cursor column in cells, so wide and multi-byte characters line up
+    let line_start = text_before.rfind('\n').map_or(0, |nl| nl + 1);
+    let cursor_col_idx = Span::raw(&text_before[line_start..]).width() as u16;
 
     // Vertical Scroll
     if cursor_line_idx >= app.input_scroll + input_display_height {
@@ -1782,72 +5082,48 @@ fn ui(f: &mut Frame, app: &mut App) {
         app.input_scroll_x = cursor_col_idx;
     }
 
-    let title = if let Some(name) = &app.current_saved_search_name {
+    let mut title = if let Some(name) = &app.current_saved_search_name {
         format!("SPL Search [{}]", name)
     } else {
         "SPL Search".to_string()
     };
+    if let Some(ns) = app.namespace.as_ref() {
+        title.push_str(&format!(" ({}/{})", ns.owner, ns.app));
+    }
+    title.push_str(&format!(" [{}]", app.time_range.label()));
+
+    let mut title_spans = Vec::new();
+    if app.read_only {
+        title_spans.push(Span::styled(
+            " READ-ONLY ",
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+        title_spans.push(Span::raw(" "));
+    }
+    title_spans.push(Span::raw(title));
 
-    let input = Paragraph::new(app.input.as_str())
+    let mut input_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(Line::from(title_spans))
+        .border_style(Style::default().fg(app.theme.title_main))
+        .padding(Padding::horizontal(0)); // Remove padding to simplify scroll math
+    let mut selection = None;
+    if let (InputMode::Editing, EditorMode::Vim(state)) = (&app.input_mode, app.editor_mode) {
+        input_block = input_block.title_bottom(format!(" {} {}", state.label(), app.vim.pending()));
+        selection = app.vim.selection(&app.input, app.cursor_position, state);
+    } else if let (InputMode::Editing, EditorMode::Standard) = (&app.input_mode, app.editor_mode) {
+        selection = app.standard.selection(app.cursor_position);
+    }
+    let selection_style = Style::default().add_modifier(Modifier::REVERSED);
+    let input = Paragraph::new(selection_lines(&app.input, selection, selection_style))
         .style(input_style)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .title(title)
-                .border_style(Style::default().fg(app.theme.title_main))
-                .padding(Padding::horizontal(0)), // Remove padding to simplify scroll math
-        )
+        .block(input_block)
         .scroll((app.input_scroll, app.input_scroll_x)); // Use both scroll offsets
-    f.render_widget(input, header_chunks[0]);
-
-    // 2. Sparkline
-    let mut spark_data = vec![];
-    if !app.search_results.is_empty() {
-        // Simple bucketing of time
-        // Note: parsing _time string requires chrono
-        // format: 2023-10-27T10:00:00.000+00:00
-        let timestamps: Vec<i64> = app
-            .search_results
-            .iter()
-            .filter_map(|v| {
-                v.get("_time").and_then(|t| t.as_str()).and_then(|s| {
-                    chrono::DateTime::parse_from_rfc3339(s)
-                        .ok()
-                        .map(|dt| dt.timestamp())
-                })
-            })
-            .collect();
-
-        if !timestamps.is_empty() {
-            let min = *timestamps.iter().min().unwrap_or(&0);
-            let max = *timestamps.iter().max().unwrap_or(&0);
-            if max > min {
-                let range = max - min;
-                let buckets = 40; // Approx width
-                let mut counts = vec![0u64; buckets];
-                for t in timestamps {
-                    let idx = ((t - min) as f64 / range as f64 * (buckets - 1) as f64) as usize;
-                    if idx < buckets {
-                        counts[idx] += 1;
-                    }
-                }
-                spark_data = counts;
-            }
-        }
-    }
-
-    let sparkline = Sparkline::default()
-        .block(
-            Block::default()
-                .title("Activity")
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(app.theme.border)),
-        )
-        .data(&spark_data)
-        .style(Style::default().fg(app.theme.summary_highlight));
-    f.render_widget(sparkline, header_chunks[1]);
+    f.render_widget(input, search_area);
 
     // --- Job Status (Middle 1) ---
     let mut stats_text = vec![];
@@ -1893,7 +5169,7 @@ fn ui(f: &mut Frame, app: &mut App) {
         ];
 
         if let Some(sid) = &app.current_job_sid {
-            let url = app.client.get_shareable_url(sid);
+            let url = shareable_url(&app.base_url, app.namespace.as_ref(), sid);
             line_vec.push(Span::styled(
                 " | URL: ",
                 Style::default().fg(app.theme.title_secondary),
@@ -1928,13 +5204,75 @@ fn ui(f: &mut Frame, app: &mut App) {
     f.render_widget(stats_paragraph, chunks[1]);
 
     // --- Results (Middle 2) ---
-    let results_area = chunks[2];
+    app.timeline_area = chunks[2];
+    if !app.timeline.is_empty() {
+        render_timeline(f, app, chunks[2]);
+    }
+
+    if pipeline_shown {
+        let text = if editing_pipeline {
+            &app.pipeline_input
+        } else {
+            &app.local_pipeline
+        };
+        let mut spans = vec![Span::styled("| ", Style::default().fg(app.theme.separator))];
+        if let Some(line) = highlight_spl(text, &app.theme).into_iter().next() {
+            spans.extend(line.spans);
+        }
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title("Local Pipeline")
+            .border_style(Style::default().fg(if editing_pipeline {
+                app.theme.title_main
+            } else {
+                app.theme.border
+            }));
+        if editing_pipeline {
+            block = block.title_bottom(Line::styled(
+                "Enter apply | Ctrl+P add to query | Esc close",
+                Style::default().fg(app.theme.separator),
+            ));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)).block(block), chunks[3]);
+        if editing_pipeline {
+            f.set_cursor_position(ratatui::layout::Position::new(
+                chunks[3].x + 3 + text.chars().count() as u16,
+                chunks[3].y + 1,
+            ));
+        }
+    }
+
+    let results_area = chunks[4];
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .title(match app.view_mode {
-            ViewMode::RawEvents => "Search Results (Raw)",
-            ViewMode::Table => "Search Results (Table)",
+        .title({
+            let mut title = match app.view_mode {
+                ViewMode::RawEvents => "Search Results (Raw)".to_string(),
+                ViewMode::Table => "Search Results (Table)".to_string(),
+                ViewMode::Chart => format!("Search Results (Chart: {})", app.chart_kind.label()),
+                ViewMode::Stat => "Search Results (Single Value)".to_string(),
+            };
+            if let Some(all) = &app.unfiltered_results {
+                let mut filters = Vec::new();
+                if app.timeline_filter.is_some() {
+                    filters.push("timeline filter");
+                }
+                if !app.local_pipeline.is_empty() {
+                    filters.push("local pipeline");
+                }
+                if app.local_search_filter && app.local_search.is_some() {
+                    filters.push("local search");
+                }
+                title.push_str(&format!(
+                    " [{}: {} of {}]",
+                    filters.join(" + "),
+                    app.search_results.len(),
+                    all.len()
+                ));
+            }
+            title
         })
         .border_style(Style::default().fg(app.theme.border))
         .padding(Padding::new(2, 2, 1, 1));
@@ -1943,7 +5281,10 @@ fn ui(f: &mut Frame, app: &mut App) {
     app.main_area = results_area;
     app.detail_area = Rect::default();
 
-    if app.search_results.is_empty() {
+    if let Some(view) = &app.dashboard {
+        let focused = matches!(app.input_mode, InputMode::Dashboard);
+        render_dashboard(f, &app.theme, view, focused, results_area);
+    } else if app.search_results.is_empty() {
         let text = Paragraph::new("No results available.")
             .alignment(Alignment::Center)
             .style(Style::default().fg(app.theme.text))
@@ -1970,22 +5311,33 @@ fn ui(f: &mut Frame, app: &mut App) {
                             } else {
                                 v.to_string()
                             };
-                            content.push(Line::from(vec![
-                                Span::styled(
-                                    format!("{}: ", k),
-                                    Style::default().fg(app.theme.summary_highlight),
-                                ),
-                                Span::styled(val_str, Style::default().fg(app.theme.text)),
-                            ]));
+                            let ranges = app
+                                .local_search
+                                .as_ref()
+                                .map(|search| search.highlights(Some(k), &val_str))
+                                .unwrap_or_default();
+                            let value = highlight_line(
+                                Line::styled(val_str, Style::default().fg(app.theme.text)),
+                                &ranges,
+                                match_style(&app.theme),
+                            );
+                            let mut spans = vec![Span::styled(
+                                format!("{}: ", k),
+                                Style::default().fg(app.theme.summary_highlight),
+                            )];
+                            spans.extend(value.spans);
+                            content.push(Line::from(spans));
                         }
                     } else {
                         content.push(Line::from(format!("{:?}", result)));
                     }
                 }
-                let paragraph = Paragraph::new(content)
+                let mut paragraph = Paragraph::new(content)
                     .block(block)
-                    .wrap(Wrap { trim: true })
                     .scroll((app.scroll_offset, 0));
+                if app.wrap_lines {
+                    paragraph = paragraph.wrap(Wrap { trim: true });
+                }
                 f.render_widget(paragraph, results_area);
             }
             ViewMode::Table => {
@@ -2004,7 +5356,13 @@ fn ui(f: &mut Frame, app: &mut App) {
                 // --- Left Pane: Table ---
                 // "Time Sourcetype Host Message should not have a highlighted background. Instead, underline the table headers."
                 // "In the Table View: Don't show Hosts."
-                let header = Row::new(vec!["Time", "Sourcetype", "Message"])
+                let columns = app.shown_columns();
+                let header_cells: Vec<String> = if columns.is_empty() {
+                    vec!["Time".into(), "Sourcetype".into(), "Message".into()]
+                } else {
+                    columns.clone()
+                };
+                let header = Row::new(header_cells)
                     .style(
                         Style::default()
                             .fg(app.theme.title_secondary)
@@ -2012,10 +5370,27 @@ fn ui(f: &mut Frame, app: &mut App) {
                     )
                     .bottom_margin(1);
 
+                // Highlights local search matches in a cell showing `field`.
+                let highlight = match_style(&app.theme);
+                let cell = |field: &str, text: String| -> Line {
+                    let ranges = app
+                        .local_search
+                        .as_ref()
+                        .map(|search| search.highlights(Some(field), &text))
+                        .unwrap_or_default();
+                    highlight_line(Line::raw(text), &ranges, highlight)
+                };
                 let rows: Vec<Row> = app
                     .search_results
                     .iter()
                     .map(|item| {
+                        if !columns.is_empty() {
+                            let cells: Vec<Line> = columns
+                                .iter()
+                                .map(|col| cell(col, clipboard::cell_text(item, col)))
+                                .collect();
+                            return Row::new(cells).style(Style::default().fg(app.theme.text));
+                        }
                         let time = item
                             .get("_time")
                             .and_then(|v| v.as_str())
@@ -2036,8 +5411,12 @@ fn ui(f: &mut Frame, app: &mut App) {
                             .unwrap_or("")
                             .to_string();
 
-                        Row::new(vec![time, sourcetype, msg])
-                            .style(Style::default().fg(app.theme.text))
+                        Row::new(vec![
+                            cell("_time", time),
+                            cell("sourcetype", sourcetype),
+                            cell("_raw", msg),
+                        ])
+                        .style(Style::default().fg(app.theme.text))
                     })
                     .collect();
 
@@ -2056,21 +5435,23 @@ fn ui(f: &mut Frame, app: &mut App) {
                 app.detail_area = inner_chunks[1];
 
                 // --- Left Pane: Table ---
-                let table = Table::new(
-                    rows,
-                    [
+                let widths = if columns.is_empty() {
+                    vec![
                         Constraint::Length(24), // Time
                         Constraint::Length(20), // Sourcetype
                         Constraint::Min(20),    // Message (Host removed)
-                    ],
-                )
-                .header(header)
-                .row_highlight_style(
-                    Style::default()
-                        .bg(app.theme.summary_highlight)
-                        .fg(Color::White),
-                )
-                .highlight_symbol(">> ");
+                    ]
+                } else {
+                    vec![Constraint::Fill(1); columns.len()]
+                };
+                let table = Table::new(rows, widths)
+                    .header(header)
+                    .row_highlight_style(
+                        Style::default()
+                            .bg(app.theme.summary_highlight)
+                            .fg(Color::White),
+                    )
+                    .highlight_symbol(">> ");
 
                 // Render table directly into chunk, but we need to handle borders if we want distinct colors.
                 // Since we render the outer block, inner widgets shouldn't necessarily have borders unless we want to override the middle separator?
@@ -2091,18 +5472,79 @@ fn ui(f: &mut Frame, app: &mut App) {
                 );
 
                 // --- Right Pane: Detail ---
-                // Use cached detail text
-                let detail_paragraph = Paragraph::new(app.cached_detail.clone())
-                    .block(
-                        Block::default()
-                            .borders(Borders::NONE) // Remove left border to avoid double
-                            .border_style(detail_border_style)
-                            .padding(Padding::new(1, 0, 0, 0)),
-                    )
-                    .wrap(Wrap { trim: false })
-                    .scroll((app.detail_scroll, 0))
-                    .style(Style::default().fg(app.theme.text));
-                f.render_widget(detail_paragraph, inner_chunks[1]);
+                // A breadcrumb with the format and the tree cursor's path,
+                // then the cached detail text.
+                let detail_block = Block::default()
+                    .borders(Borders::NONE) // Remove left border to avoid double
+                    .border_style(detail_border_style)
+                    .padding(Padding::new(1, 0, 0, 0));
+                let detail_area = detail_block.inner(inner_chunks[1]);
+                let detail_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(0)])
+                    .split(detail_area);
+                let mut breadcrumb = vec![Span::styled(
+                    format!("[{}] ", app.detail_format.label()),
+                    Style::default().fg(detail_border_style.fg.unwrap_or(app.theme.border)),
+                )];
+                if app.detail_format == DetailFormat::Tree {
+                    if let Some(row) = app.detail_row() {
+                        breadcrumb.push(Span::styled(
+                            json_tree::json_path(&row.path),
+                            Style::default().fg(app.theme.date_label),
+                        ));
+                    }
+                }
+                f.render_widget(Paragraph::new(Line::from(breadcrumb)), detail_chunks[0]);
+
+                if app.detail_format == DetailFormat::Tree && app.detail_value.is_some() {
+                    let items: Vec<ListItem> = app
+                        .cached_detail
+                        .lines
+                        .iter()
+                        .cloned()
+                        .map(ListItem::new)
+                        .collect();
+                    let tree = List::new(items)
+                        .style(Style::default().fg(app.theme.text))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                    f.render_stateful_widget(tree, detail_chunks[1], &mut app.detail_tree_state);
+                } else {
+                    let mut detail_paragraph = Paragraph::new(app.cached_detail.clone())
+                        .scroll((app.detail_scroll, 0))
+                        .style(Style::default().fg(app.theme.text));
+                    if app.wrap_lines {
+                        detail_paragraph = detail_paragraph.wrap(Wrap { trim: false });
+                    }
+                    f.render_widget(detail_paragraph, detail_chunks[1]);
+                }
+            }
+            ViewMode::Chart => {
+                let inner_area = block.inner(results_area);
+                f.render_widget(block, results_area);
+                app.main_area = inner_area;
+                let view = ChartView {
+                    results: &app.search_results,
+                    kind: app.chart_kind,
+                    hidden: &app.chart_hidden,
+                    cursor: app.chart_cursor,
+                    legend: true,
+                };
+                render_chart(f, &app.theme, &view, inner_area);
+            }
+            ViewMode::Stat => {
+                let inner_area = block.inner(results_area);
+                f.render_widget(block, results_area);
+                app.main_area = inner_area;
+                let config = app.stat_config.clone().unwrap_or_default();
+                render_stat(
+                    f,
+                    &app.theme,
+                    &app.search_results,
+                    &config,
+                    app.stat_compare.as_deref(),
+                    inner_area,
+                );
             }
         }
     }
@@ -2114,43 +5556,48 @@ fn ui(f: &mut Frame, app: &mut App) {
     // "lets remove ' ^J NewLine' from the nav bar."
     // "Remove the status line at the bottom"
 
-    let mut footer_spans = vec![
-        Span::styled(" e ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Search  |  ", Style::default().fg(app.theme.text)),
+    let job_running = app
+        .current_job_status
+        .as_ref()
+        .is_some_and(|status| !status.is_done);
+    let hints = [
+        (keymap::Action::EditQuery, "Search"),
+        (keymap::Action::KillJob, "Kill Job"),
+        (keymap::Action::MoveDown, "Scroll"),
+        (keymap::Action::CycleView, "View Mode"),
+        (keymap::Action::OpenInEditor, "Open in Editor"),
+        (keymap::Action::LoadSearch, "Load"),
+        (keymap::Action::SaveSearch, "Save"),
+        (keymap::Action::Quit, "Quit"),
     ];
-    if let Some(status) = &app.current_job_status {
-        if !status.is_done {
-            footer_spans.push(Span::styled(
-                " x ",
-                Style::default().fg(app.theme.title_main),
-            ));
-            footer_spans.push(Span::styled(
-                "Kill Job  |  ",
-                Style::default().fg(app.theme.text),
-            ));
-        }
+    let footer_spans: Vec<Span> = hints
+        .iter()
+        .filter(|(action, _)| *action != keymap::Action::KillJob || job_running)
+        .filter_map(|(action, label)| {
+            let key = app.keymap.first(keymap::Mode::Normal, *action)?;
+            Some([
+                Span::styled(
+                    format!(" {} ", key),
+                    Style::default().fg(app.theme.title_main),
+                ),
+                Span::styled(
+                    format!("{}  |  ", label),
+                    Style::default().fg(app.theme.text),
+                ),
+            ])
+        })
+        .flatten()
+        .collect();
+    let mut footer_spans = footer_spans;
+    if let Some(last) = footer_spans.last_mut() {
+        last.content = last.content.trim_end_matches(['|', ' ']).to_string().into();
     }
-    footer_spans.extend(vec![
-        Span::styled(" ↑/↓ ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Scroll  |  ", Style::default().fg(app.theme.text)),
-        Span::styled(" ^V ", Style::default().fg(app.theme.title_main)),
-        Span::styled("View Mode  |  ", Style::default().fg(app.theme.text)),
-        Span::styled(" ^X ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Open in Editor  |  ", Style::default().fg(app.theme.text)),
-        // Removed ^J NewLine
-        Span::styled(" ^l ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Load  |  ", Style::default().fg(app.theme.text)),
-        Span::styled(" ^s ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Save  |  ", Style::default().fg(app.theme.text)),
-        Span::styled(" q ", Style::default().fg(app.theme.title_main)),
-        Span::styled("Quit", Style::default().fg(app.theme.text)),
-    ]);
 
     let footer = Paragraph::new(Line::from(footer_spans))
         .alignment(Alignment::Center)
         .style(Style::default().fg(app.theme.text));
 
-    f.render_widget(footer, chunks[3]);
+    f.render_widget(footer, chunks[5]);
 
     // --- Modals ---
     if let InputMode::LocalSearch = app.input_mode {
@@ -2162,33 +5609,366 @@ fn ui(f: &mut Frame, app: &mut App) {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Local Search (Regex)")
-                    .border_style(Style::default().fg(app.theme.title_main)),
+                    .title(format!(
+                        "Local Search (Regex) [{}{}{}{}]",
+                        app.local_search_scope.label(),
+                        if app.local_search_case { ", case" } else { "" },
+                        if app.local_search_invert {
+                            ", invert"
+                        } else {
+                            ""
+                        },
+                        if app.local_search_filter {
+                            ", only matching"
+                        } else {
+                            ""
+                        }
+                    ))
+                    .title_bottom(Line::styled(
+                        "Tab scope | ^T case | ^V invert | ^F only matching",
+                        Style::default().fg(app.theme.separator),
+                    ))
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            );
+        f.render_widget(input_block, area);
+
+        // Cursor for Local Search
+        f.set_cursor_position(ratatui::layout::Position::new(
+            area.x + 1 + app.local_search_query.len() as u16,
+            area.y + 1,
+        ));
+    }
+
+    if let InputMode::Palette = app.input_mode {
+        let area = centered_rect(70, 60, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        let input = Paragraph::new(format!(":{}", app.palette_input))
+            .style(Style::default().fg(app.theme.input_edit))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Command Palette (Enter run, Esc close)")
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            );
+        f.render_widget(input, chunks[0]);
+
+        // Actions with their keys, or the commands while one is being typed.
+        let items: Vec<ListItem> = if app.palette_matches.is_empty() {
+            let typed = app.palette_input.split_whitespace().next().unwrap_or("");
+            palette::COMMANDS
+                .iter()
+                .filter(|(usage, _)| usage.starts_with(typed))
+                .map(|(usage, description)| {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{:<34}", usage),
+                            Style::default().fg(app.theme.input_edit),
+                        ),
+                        Span::styled(*description, Style::default().fg(app.theme.text)),
+                    ]))
+                })
+                .collect()
+        } else {
+            app.palette_matches
+                .iter()
+                .map(|(mode, action)| {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{:<42}", action.description()),
+                            Style::default().fg(app.theme.text),
+                        ),
+                        Span::styled(
+                            format!("{:<16}", app.keymap.label(*mode, *action)),
+                            Style::default().fg(app.theme.input_edit),
+                        ),
+                        Span::styled(action.name(), Style::default().fg(app.theme.date_label)),
+                    ]))
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(app.theme.summary_highlight)
+                    .fg(Color::White),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[1], &mut app.palette_state);
+    }
+
+    if let InputMode::Yank = app.input_mode {
+        let area = centered_rect(50, 50, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let row = app.selected_row();
+        let items: Vec<ListItem> = app
+            .yank_targets
+            .iter()
+            .map(|target| {
+                let key = target.key().map(String::from).unwrap_or_default();
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:<2}", key),
+                        Style::default().fg(app.theme.input_edit),
+                    ),
+                    Span::styled(target.label(), Style::default().fg(app.theme.text)),
+                ];
+                if let (YankTarget::Field(name), Some(row)) = (target, row) {
+                    let value: String = clipboard::cell_text(row, name).chars().take(60).collect();
+                    spans.push(Span::styled(
+                        format!(" = {}", value),
+                        Style::default().fg(app.theme.date_label),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Copy to Clipboard (Enter or key, Esc cancel)")
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(app.theme.summary_highlight)
+                    .fg(Color::White),
+            )
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(list, area, &mut app.yank_list_state);
+    }
+
+    if let InputMode::ThemeSelect = app.input_mode {
+        let area = centered_rect(40, 40, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let items: Vec<ListItem> = app
+            .themes
+            .iter()
+            .map(|t| {
+                let mut spans = vec![Span::styled(
+                    t.name.clone(),
+                    Style::default().fg(app.theme.text),
+                )];
+                if let Some(path) = &t.path {
+                    spans.push(Span::styled(
+                        format!(
+                            "  {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ),
+                        Style::default().fg(app.theme.date_label),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Select Theme")
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(app.theme.summary_highlight)
+                    .fg(Color::White),
+            )
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(list, area, &mut app.theme_list_state);
+    }
+
+    if let InputMode::AppSelect = app.input_mode {
+        let area = centered_rect(50, 50, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let items: Vec<ListItem> = app
+            .app_options
+            .iter()
+            .map(|a| {
+                let style = if a.visible {
+                    Style::default().fg(app.theme.text)
+                } else {
+                    Style::default().fg(app.theme.date_label)
+                };
+                ListItem::new(format!("{} ({})", a.name, a.label)).style(style)
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Select App [profile: {}]", app.profile))
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(app.theme.summary_highlight)
+                    .fg(Color::White),
+            )
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(list, area, &mut app.app_list_state);
+    }
+
+    if let InputMode::ServerSearches = app.input_mode {
+        let area = centered_rect(90, 70, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let header = Row::new(vec!["Name", "App", "Owner", "Schedule", "Last Run"])
+            .style(
+                Style::default()
+                    .fg(app.theme.title_secondary)
+                    .add_modifier(Modifier::UNDERLINED),
+            )
+            .bottom_margin(1);
+
+        let rows: Vec<Row> = app
+            .server_searches
+            .iter()
+            .map(|s| {
+                let schedule = if s.is_scheduled {
+                    s.cron_schedule.clone()
+                } else {
+                    String::from("-")
+                };
+                let style = if s.disabled {
+                    Style::default().fg(app.theme.date_label)
+                } else {
+                    Style::default().fg(app.theme.text)
+                };
+                Row::new(vec![
+                    s.name.clone(),
+                    s.app.clone(),
+                    s.owner.clone(),
+                    schedule,
+                    s.last_run.clone().unwrap_or_default(),
+                ])
+                .style(style)
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(30),
+                Constraint::Length(18),
+                Constraint::Length(14),
+                Constraint::Length(16),
+                Constraint::Length(26),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Server Saved Searches (Enter copy | d dispatch | h last run | s schedule)")
+                .border_style(Style::default().fg(app.theme.title_main)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(app.theme.summary_highlight)
+                .fg(Color::White),
+        )
+        .highlight_symbol(">> ");
+
+        let modal_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(5)])
+            .split(area);
+        f.render_stateful_widget(table, modal_chunks[0], &mut app.server_search_state);
+
+        if let Some(saved) = app.selected_server_search() {
+            let time_range = format!(
+                "{} to {}",
+                if saved.earliest_time.is_empty() {
+                    "default"
+                } else {
+                    &saved.earliest_time
+                },
+                if saved.latest_time.is_empty() {
+                    "now"
+                } else {
+                    &saved.latest_time
+                },
+            );
+            let preview = Paragraph::new(vec![
+                Line::from(vec![
+                    Span::styled("Range: ", Style::default().fg(app.theme.title_secondary)),
+                    Span::styled(time_range, Style::default().fg(app.theme.text)),
+                    Span::styled("  ", Style::default()),
+                    Span::styled(saved.description, Style::default().fg(app.theme.date_label)),
+                ]),
+                Line::from(Span::styled(
+                    saved.search,
+                    Style::default().fg(app.theme.text),
+                )),
+            ])
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(app.theme.border)),
             );
-        f.render_widget(input_block, area);
-
-        // Cursor for Local Search
-        f.set_cursor_position(ratatui::layout::Position::new(
-            area.x + 1 + app.local_search_query.len() as u16,
-            area.y + 1,
-        ));
+            f.render_widget(preview, modal_chunks[1]);
+        }
     }
 
-    if let InputMode::ThemeSelect = app.input_mode {
-        let area = centered_rect(40, 40, f.area());
+    if let InputMode::Sync = app.input_mode {
+        let area = centered_rect(90, 70, f.area());
         f.render_widget(ratatui::widgets::Clear, area);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(area);
 
         let items: Vec<ListItem> = app
-            .theme_options
+            .sync_entries
             .iter()
-            .map(|i| ListItem::new(*i).style(Style::default().fg(app.theme.text)))
+            .map(|e| {
+                let mark = if app.sync_marked.contains(&e.name) {
+                    "*"
+                } else {
+                    " "
+                };
+                let color = match e.status {
+                    SyncStatus::LocalOnly => app.theme.active_label,
+                    SyncStatus::ServerOnly => app.theme.error,
+                    SyncStatus::Modified => app.theme.input_edit,
+                    SyncStatus::Unchanged => app.theme.date_label,
+                    SyncStatus::Conflict => app.theme.error,
+                };
+                ListItem::new(format!("{}{} {}", mark, e.status.marker(), e.name))
+                    .style(Style::default().fg(color))
+            })
             .collect();
 
+        let app_name = app
+            .namespace
+            .as_ref()
+            .map(|ns| ns.app.clone())
+            .unwrap_or_default();
         let list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Select Theme")
+                    .title(format!("Local vs {} (Space/p/g)", app_name))
                     .border_style(Style::default().fg(app.theme.title_main)),
             )
             .highlight_style(
@@ -2197,45 +5977,218 @@ fn ui(f: &mut Frame, app: &mut App) {
                     .fg(Color::White),
             )
             .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut app.sync_list_state);
+
+        let diff_lines: Vec<Line> = app
+            .sync_list_state
+            .selected()
+            .and_then(|idx| app.sync_entries.get(idx))
+            .map(|e| {
+                let server = e.server.as_ref().map(|s| s.search.as_str()).unwrap_or("");
+                let local = e.local.as_deref().unwrap_or("");
+                search_sync::line_diff(server, local)
+                    .into_iter()
+                    .map(|(tag, line)| match tag {
+                        DiffTag::Same => Line::from(Span::styled(
+                            format!("  {}", line),
+                            Style::default().fg(app.theme.text),
+                        )),
+                        DiffTag::Added => Line::from(Span::styled(
+                            format!("+ {}", line),
+                            Style::default().fg(app.theme.active_label),
+                        )),
+                        DiffTag::Removed => Line::from(Span::styled(
+                            format!("- {}", line),
+                            Style::default().fg(app.theme.error),
+                        )),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        f.render_stateful_widget(list, area, &mut app.theme_list_state);
+        let diff_view = Paragraph::new(diff_lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Diff (- server / + local)")
+                .border_style(Style::default().fg(app.theme.border)),
+        );
+        f.render_widget(diff_view, panes[1]);
+    }
+
+    if let InputMode::HistorySearch = app.input_mode {
+        let area = search_area;
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let found = app
+            .history_search_match
+            .and_then(|i| app.history.get(i))
+            .map(|e| e.query.replace('\n', " "))
+            .unwrap_or_default();
+        let prompt = format!("`{}': ", app.history_search_query);
+        let line = Line::from(vec![
+            Span::styled(
+                prompt.clone(),
+                Style::default().fg(app.theme.title_secondary),
+            ),
+            Span::styled(found, Style::default().fg(app.theme.input_edit)),
+        ]);
+        let widget = Paragraph::new(line).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("(reverse-i-search)")
+                .border_style(Style::default().fg(app.theme.title_main)),
+        );
+        f.render_widget(widget, area);
+        f.set_cursor_position(ratatui::layout::Position::new(
+            area.x + 1 + prompt.chars().count().saturating_sub(3) as u16,
+            area.y + 1,
+        ));
+    }
+
+    if let InputMode::HistoryBrowser = app.input_mode {
+        let area = centered_rect(90, 70, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let header = Row::new(vec![
+            "When", "Profile", "Range", "Results", "Time", "SID", "Query",
+        ])
+        .style(
+            Style::default()
+                .fg(app.theme.title_secondary)
+                .add_modifier(Modifier::UNDERLINED),
+        )
+        .bottom_margin(1);
+
+        let rows: Vec<Row> = app
+            .history
+            .iter()
+            .rev()
+            .map(|e| {
+                let range = TimeRange {
+                    earliest: e.earliest.clone(),
+                    latest: e.latest.clone(),
+                };
+                Row::new(vec![
+                    timeline::format_at(e.timestamp, app.utc_offset, "%Y-%m-%d %H:%M"),
+                    e.profile.clone(),
+                    range.label(),
+                    e.result_count.map(|c| c.to_string()).unwrap_or_default(),
+                    e.duration.map(|d| format!("{:.2}s", d)).unwrap_or_default(),
+                    e.sid.clone(),
+                    e.query.replace('\n', " "),
+                ])
+                .style(Style::default().fg(app.theme.text))
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(16),
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(18),
+                Constraint::Min(20),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Search History (Enter load | r re-run | a re-attach)")
+                .border_style(Style::default().fg(app.theme.title_main)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(app.theme.summary_highlight)
+                .fg(Color::White),
+        )
+        .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut app.history_list_state);
     }
 
     if let InputMode::Help = app.input_mode {
         let area = centered_rect(60, 80, f.area());
         f.render_widget(ratatui::widgets::Clear, area);
 
-        let shortcuts = vec![
-            ("General", ""),
-            ("Ctrl+/", "Show this Help"),
-            ("q", "Quit"),
-            ("e", "Enter Search Input Mode"),
-            ("Ctrl+t", "Toggle Theme"),
-            ("", ""),
-            ("Search Input", ""),
-            ("Enter", "Run Search"),
-            ("Shift+Enter", "Newline (Standard Mode)"),
-            ("Ctrl+x", "Edit Query in External Editor"),
-            ("Ctrl+v", "Toggle Vim/Standard Mode"),
-            ("Ctrl+s", "Save Search"),
+        // Bindable actions come from the live keymap; the editors' own keys
+        // and the keys inside modals are fixed.
+        let mut shortcuts: Vec<(String, String)> = Vec::new();
+        for mode in keymap::Mode::ALL {
+            shortcuts.push((mode.title().to_string(), String::new()));
+            for action in app.keymap.bound(mode) {
+                shortcuts.push((
+                    app.keymap.label(mode, action),
+                    action.description().to_string(),
+                ));
+            }
+            match mode {
+                keymap::Mode::Chart => {
+                    shortcuts.push(("1-9".into(), "Toggle Series".into()));
+                }
+                keymap::Mode::Editing => {
+                    shortcuts.push(("Shift+Enter".into(), "Newline (Standard Mode)".into()));
+                    shortcuts.push(("Up / Down".into(), "Recall History (empty buffer)".into()));
+                }
+                _ => {}
+            }
+            shortcuts.push((String::new(), String::new()));
+        }
+        let fixed = [
+            ("Standard Mode", ""),
+            ("Ctrl+a / Ctrl+e", "Line Start / End"),
+            (
+                "Ctrl+w / Alt+Backspace",
+                "Delete Word (Ctrl+w cuts selection)",
+            ),
+            ("Ctrl+k / Ctrl+u", "Delete to Line End / Start"),
+            ("Shift+Arrows", "Select Text"),
+            ("Ctrl+c / Ctrl+y", "Copy / Paste"),
+            ("Ctrl+z / Alt+z", "Undo / Redo"),
             ("", ""),
-            ("Results & Navigation", ""),
-            ("j / k / Down / Up", "Scroll / Navigate"),
-            ("Ctrl+j / Ctrl+k", "Fast Scroll"),
-            ("Ctrl+r", "Clear Results"),
-            ("Ctrl+l", "Load Saved Search"),
-            ("Shift+E", "Open Job in Browser"),
-            ("Ctrl+v / Ctrl+m", "Toggle Raw/Table View"),
-            ("Ctrl+x", "Open Results in External Editor"),
-            ("/ / n / N", "Local Regex Search / Next / Prev"),
+            ("Vim Mode", ""),
+            ("w b e / 0 ^ $ / gg G", "Word, Line and Buffer Motions"),
+            ("f t F T ; ,", "Find Char in Line"),
+            ("d c y + motion/object", "Operators (iw i\" i( i[ i|)"),
+            ("v / V", "Visual / Visual Line"),
+            (". / u / Ctrl+r", "Repeat / Undo / Redo"),
             ("", ""),
-            ("Pane Navigation", ""),
-            ("Tab", "Cycle Focus (Search > List > Detail)"),
-            ("h / l / Left / Right", "Focus Panes"),
+            ("Modal Views (fixed, not in [keys])", ""),
+            ("Enter / Esc", "Confirm / Cancel in Every Modal"),
+            ("j k / Up Down", "Move in Lists (^n ^p in Finders)"),
+            (
+                "^R / ^D / ^X",
+                "Rename / Duplicate / Delete (in Load finder)",
+            ),
+            ("Tab", "Group Load Finder by Folder / Tag"),
+            (
+                "Tab / ^T / ^V / ^F",
+                "Local Search Scope / Case / Invert / Only Matching",
+            ),
+            ("^P", "Add Local Pipeline to Query"),
+            ("d / h / s", "Dispatch / Last Run / Schedule (Server)"),
+            ("Space / p / g", "Mark / Push / Pull (Sync)"),
+            ("r / a", "Re-run / Re-attach (History)"),
+            ("^R / ^G", "Older Match / Cancel (History Search)"),
+            ("Space z c", "Mark / Zoom / Clear (Timeline)"),
+            ("r / i", "Refresh / Edit Inputs (Dashboard)"),
+            ("x", "Clear App Namespace (App Select)"),
+            ("y / n / r", "Overwrite / Cancel / Rename (Save)"),
         ];
+        shortcuts.extend(fixed.iter().map(|(k, d)| (k.to_string(), d.to_string())));
 
+        // Keep the last page in view when scrolled past the end.
+        let visible = area.height.saturating_sub(2) as usize;
+        app.help_scroll = app
+            .help_scroll
+            .min(shortcuts.len().saturating_sub(visible) as u16);
         let rows: Vec<Row> = shortcuts
             .iter()
+            .skip(app.help_scroll as usize)
             .map(|(k, d)| {
                 let style = if d.is_empty() {
                     Style::default()
@@ -2251,7 +6204,7 @@ fn ui(f: &mut Frame, app: &mut App) {
         let table = Table::new(rows, [Constraint::Length(25), Constraint::Min(30)]).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Keyboard Shortcuts")
+                .title("Keyboard Shortcuts (j/k scroll, Esc close)")
                 .border_style(Style::default().fg(app.theme.title_main)),
         );
 
@@ -2273,6 +6226,142 @@ fn ui(f: &mut Frame, app: &mut App) {
         f.render_widget(input_block, area);
     }
 
+    if let InputMode::DashboardOpen = app.input_mode {
+        let area = centered_rect(60, 20, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let input_block = Paragraph::new(app.dashboard_source.as_str())
+            .style(Style::default().fg(app.theme.input_edit))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Open Dashboard (name or file)")
+                    .border_style(Style::default().fg(app.theme.title_main)),
+            );
+        f.render_widget(input_block, area);
+    }
+
+    if let (InputMode::DashboardInput, Some(view)) = (&app.input_mode, &app.dashboard) {
+        let area = centered_rect(60, 40, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, (input, value)) in view
+            .dashboard
+            .inputs
+            .iter()
+            .zip(&view.input_edits)
+            .enumerate()
+        {
+            let selected = i == view.input_selected;
+            let marker = if selected { "> " } else { "  " };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{}{} ", marker, input.label),
+                    Style::default().fg(app.theme.title_secondary),
+                ),
+                Span::styled(
+                    value.clone(),
+                    if selected {
+                        Style::default().fg(app.theme.input_edit)
+                    } else {
+                        Style::default().fg(app.theme.text)
+                    },
+                ),
+            ]));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "Tab/Up/Down field | Enter apply | Esc cancel",
+            Style::default().fg(app.theme.title_secondary),
+        ));
+
+        let form = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Dashboard Inputs")
+                .border_style(Style::default().fg(app.theme.title_main)),
+        );
+        f.render_widget(form, area);
+    }
+
+    if let InputMode::TokenForm = app.input_mode {
+        let area = centered_rect(60, 40, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, field) in app.token_fields.iter().enumerate() {
+            let selected = i == app.token_selected;
+            let marker = if selected { "> " } else { "  " };
+            let mut spans = vec![
+                Span::styled(
+                    format!("{}${}$ ", marker, field.name),
+                    Style::default().fg(app.theme.title_secondary),
+                ),
+                Span::styled(
+                    field.value.clone(),
+                    if selected {
+                        Style::default().fg(app.theme.input_edit)
+                    } else {
+                        Style::default().fg(app.theme.text)
+                    },
+                ),
+            ];
+            if let Some(pattern) = &field.pattern {
+                spans.push(Span::styled(
+                    format!("  /{}/", pattern),
+                    Style::default().fg(app.theme.separator),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "Tab/Up/Down field | Ctrl+N/Ctrl+P recent values | Enter run | Esc cancel",
+            Style::default().fg(app.theme.title_secondary),
+        ));
+
+        let form = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Search Tokens")
+                .border_style(Style::default().fg(app.theme.title_main)),
+        );
+        f.render_widget(form, area);
+    }
+
+    if let InputMode::LintConfirm = app.input_mode {
+        let area = centered_rect(70, 40, f.area());
+        f.render_widget(ratatui::widgets::Clear, area);
+
+        let mut lines: Vec<Line> = app
+            .lint_findings
+            .iter()
+            .map(|finding| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("[{}] ", finding.rule.id()),
+                        Style::default().fg(app.theme.error),
+                    ),
+                    Span::styled(finding.message.clone(), Style::default().fg(app.theme.text)),
+                ])
+            })
+            .collect();
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "Enter/y run anyway | Esc/n back to editor (disable rules with lint_disable in config.toml)",
+            Style::default().fg(app.theme.title_secondary),
+        ));
+
+        let msg = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Lint Warnings")
+                .border_style(Style::default().fg(app.theme.error)),
+        );
+        f.render_widget(msg, area);
+    }
+
     if let InputMode::ConfirmOverwrite = app.input_mode {
         let area = centered_rect(60, 10, f.area());
         f.render_widget(ratatui::widgets::Clear, area);
@@ -2283,26 +6372,91 @@ fn ui(f: &mut Frame, app: &mut App) {
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Confirm Overwrite")
-                    .border_style(Style::default().fg(app.theme.evilness_label)),
+                    .border_style(Style::default().fg(app.theme.error)),
             ); // Use red for warning
         f.render_widget(msg, area);
     }
 
     if let InputMode::LoadSearch = app.input_mode {
-        let area = centered_rect(60, 40, f.area());
+        let area = centered_rect(80, 60, f.area());
         f.render_widget(ratatui::widgets::Clear, area);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3)])
+            .split(area);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(rows[1]);
+
+        let (prompt_title, prompt_text) = match &app.load_action {
+            Some(LoadAction::Rename(buf)) => ("Rename To", buf.as_str()),
+            Some(LoadAction::Duplicate(buf)) => ("Duplicate As", buf.as_str()),
+            Some(LoadAction::ConfirmDelete) => ("Delete? (y/n)", ""),
+            None => ("Find (name, tags, SPL)", app.load_query.as_str()),
+        };
+        let prompt = Paragraph::new(prompt_text)
+            .style(Style::default().fg(app.theme.input_edit))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(prompt_title)
+                    .border_style(Style::default().fg(match app.load_action {
+                        Some(LoadAction::ConfirmDelete) => app.theme.error,
+                        _ => app.theme.title_main,
+                    })),
+            );
+        f.render_widget(prompt, rows[0]);
+        f.set_cursor_position(ratatui::layout::Position::new(
+            rows[0].x + 1 + prompt_text.chars().count() as u16,
+            rows[0].y + 1,
+        ));
 
         let items: Vec<ListItem> = app
-            .saved_searches
+            .load_rows
             .iter()
-            .map(|i| ListItem::new(i.as_str()).style(Style::default().fg(app.theme.text)))
+            .map(|row| match row {
+                LoadRow::Header(group) => ListItem::new(group.as_str()).style(
+                    Style::default()
+                        .fg(app.theme.title_secondary)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                LoadRow::Search(idx) => {
+                    let indent = if app.load_group_by == LoadGroupBy::None
+                        || !app.load_query.trim().is_empty()
+                    {
+                        ""
+                    } else {
+                        "  "
+                    };
+                    let name = &app.saved_searches[*idx].name;
+                    let matched = fuzzy_match(&app.load_query, name)
+                        .map(|(_, positions)| positions)
+                        .unwrap_or_default();
+                    let mut spans = vec![Span::raw(indent)];
+                    spans.extend(name.chars().enumerate().map(|(i, c)| {
+                        let style = if matched.contains(&i) {
+                            Style::default()
+                                .fg(app.theme.input_edit)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(app.theme.text)
+                        };
+                        Span::styled(c.to_string(), style)
+                    }));
+                    ListItem::new(Line::from(spans))
+                }
+            })
             .collect();
 
         let list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Saved Searches")
+                    .title(format!(
+                        "Saved Searches (Tab: group by {:?})",
+                        app.load_group_by
+                    ))
                     .border_style(Style::default().fg(app.theme.title_main)),
             )
             .highlight_style(
@@ -2312,7 +6466,54 @@ fn ui(f: &mut Frame, app: &mut App) {
             )
             .highlight_symbol(">> ");
 
-        f.render_stateful_widget(list, area, &mut app.saved_search_list_state);
+        f.render_stateful_widget(list, panes[0], &mut app.saved_search_list_state);
+
+        let mut preview: Vec<Line> = vec![];
+        if let Some(search) = app.selected_saved_search() {
+            let label = |text: &str| {
+                Span::styled(
+                    text.to_string(),
+                    Style::default().fg(app.theme.title_secondary),
+                )
+            };
+            if !search.meta.description.is_empty() {
+                preview.push(Line::from(Span::styled(
+                    search.meta.description.clone(),
+                    Style::default().fg(app.theme.text),
+                )));
+                preview.push(Line::from(""));
+            }
+            let range = TimeRange {
+                earliest: search.meta.earliest.clone(),
+                latest: search.meta.latest.clone(),
+            };
+            preview.push(Line::from(vec![label("Range: "), Span::raw(range.label())]));
+            if !search.meta.tags.is_empty() {
+                preview.push(Line::from(vec![
+                    label("Tags: "),
+                    Span::raw(search.meta.tags.join(", ")),
+                ]));
+            }
+            if !search.meta.author.is_empty() {
+                preview.push(Line::from(vec![
+                    label("Author: "),
+                    Span::raw(search.meta.author.clone()),
+                ]));
+            }
+            preview.push(Line::from(""));
+            preview.extend(highlight_spl(&search.query, &app.theme));
+        }
+
+        let preview = Paragraph::new(preview)
+            .wrap(Wrap { trim: false })
+            .style(Style::default().fg(app.theme.text))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Preview")
+                    .border_style(Style::default().fg(app.theme.border)),
+            );
+        f.render_widget(preview, panes[1]);
     }
 
     // Set cursor
@@ -2324,14 +6525,14 @@ fn ui(f: &mut Frame, app: &mut App) {
         // Ensure cursor is within displayed area
         if displayed_y < input_display_height && displayed_x < input_area_width {
             f.set_cursor_position(ratatui::layout::Position::new(
-                header_chunks[0].x + 1 + displayed_x, // +1 for border
-                header_chunks[0].y + 1 + displayed_y, // +1 for border
+                search_area.x + 1 + displayed_x, // +1 for border
+                search_area.y + 1 + displayed_y, // +1 for border
             ));
         }
     } else if let InputMode::SaveSearch = app.input_mode {
         let area = centered_rect(60, 20, f.area());
         f.set_cursor_position(ratatui::layout::Position::new(
-            area.x + 1 + app.save_search_name.len() as u16,
+            area.x + 1 + Span::raw(&app.save_search_name).width() as u16,
             area.y + 1,
         ));
     }
@@ -2365,3 +6566,627 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
     vertical_layout[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::saved_searches::SavedSearchManager;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseEvent};
+    use ratatui::backend::TestBackend;
+    use ratatui::layout::Position;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn press(app: &mut App, code: KeyCode) -> Vec<Effect> {
+        app.update(Action::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+    }
+
+    /// Drives an `App` the way `run_loop` does, with the executor's effects
+    /// answered by a fake server, and renders it into a `TestBackend`.
+    struct Harness {
+        app: App,
+        terminal: Terminal<TestBackend>,
+        /// Rows every search job returns.
+        rows: Vec<Value>,
+        timeline: Vec<TimelineBucket>,
+        /// Effects the fake server doesn't answer (`Quit`, `OpenEditor`, ...).
+        unanswered: Vec<Effect>,
+    }
+
+    impl Harness {
+        fn new() -> Harness {
+            let client = SplunkClient::new("https://localhost:8089".into(), "token".into(), false);
+            // Nothing from the user's config, history or theme files.
+            let startup = Startup {
+                config: Config {
+                    profile: crate::config::DEFAULT_PROFILE.to_string(),
+                    ..Config::default()
+                },
+                history: Vec::new(),
+                token_values: TokenValues::default(),
+                themes: theme::builtins(),
+                theme_errors: Vec::new(),
+                utc_offset: chrono::FixedOffset::east_opt(0),
+            };
+            let app = App::new(&client, startup);
+            Harness {
+                app,
+                terminal: Terminal::new(TestBackend::new(100, 30)).unwrap(),
+                rows: Vec::new(),
+                timeline: Vec::new(),
+                unanswered: Vec::new(),
+            }
+        }
+
+        /// Types `script`: `<...>` is a key as written in `[keys]` (`<enter>`,
+        /// `<ctrl+s>`, `<shift+enter>`), anything else is typed as is.
+        fn keys(&mut self, script: &str) {
+            let mut rest = script;
+            while let Some(c) = rest.chars().next() {
+                let key = match rest.strip_prefix('<').and_then(|r| r.split_once('>')) {
+                    Some((name, after)) => {
+                        rest = after;
+                        keymap::Key::parse(name).unwrap().into()
+                    }
+                    None => {
+                        rest = &rest[c.len_utf8()..];
+                        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+                    }
+                };
+                self.send(Action::Key(key));
+            }
+        }
+
+        /// Mouse events hit the areas of the last frame, so one is drawn first.
+        fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) {
+            self.terminal.draw(|f| ui(f, &mut self.app)).unwrap();
+            self.send(Action::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }));
+        }
+
+        /// Runs `query` over the last day to completion from the normal mode.
+        fn search(&mut self, query: &str) {
+            self.app.replace_input(query.into());
+            self.app.time_range.earliest = Some("-24h".into());
+            self.keys("<enter>");
+            self.send(Action::Tick);
+        }
+
+        /// Applies `action` and every action its effects lead to.
+        fn send(&mut self, action: Action) {
+            let mut pending = VecDeque::from([action]);
+            while let Some(action) = pending.pop_front() {
+                let effects = self.app.update(action);
+                pending.extend(effects.into_iter().filter_map(|e| self.answer(e)));
+            }
+        }
+
+        fn answer(&mut self, effect: Effect) -> Option<Action> {
+            let action = match effect {
+                Effect::CreateSearch { query, range } => Action::SearchCreated {
+                    query,
+                    range,
+                    result: Ok("1700000000.1".into()),
+                },
+                Effect::PollJob { sid } => Action::JobStatus {
+                    sid,
+                    result: Ok(JobStatus {
+                        is_done: true,
+                        dispatch_state: "DONE".into(),
+                        result_count: self.rows.len() as u64,
+                        run_duration: 0.42,
+                        scan_count: 1200,
+                        event_count: self.rows.len() as u64,
+                    }),
+                },
+                Effect::FetchResults { sid } => Action::ResultsLoaded {
+                    sid,
+                    result: Ok(self.rows.clone()),
+                },
+                Effect::FetchTimeline { sid } => Action::TimelineLoaded {
+                    sid,
+                    buckets: self.timeline.clone(),
+                },
+                Effect::RunPanel {
+                    index, generation, ..
+                } => Action::PanelStarted {
+                    index,
+                    generation,
+                    result: Ok(format!("panel{}", index)),
+                },
+                Effect::WaitForPanel {
+                    index, generation, ..
+                } => Action::PanelLoaded {
+                    index,
+                    generation,
+                    result: Ok(self.rows.clone()),
+                },
+                Effect::ListApps => Action::AppsLoaded(Ok(["search", "security", "web"]
+                    .iter()
+                    .map(|name| SplunkApp {
+                        name: name.to_string(),
+                        label: name.to_string(),
+                        visible: true,
+                        disabled: false,
+                    })
+                    .collect())),
+                Effect::ListServerSearches => {
+                    Action::ServerSearchesLoaded(Ok(vec![server_search("Errors by host")]))
+                }
+                Effect::LoadSync { app } => Action::SyncLoaded {
+                    app,
+                    result: Ok(search_sync::diff(
+                        &[
+                            ("Errors by host".into(), "index=web status>=500".into()),
+                            ("Local only".into(), "index=main".into()),
+                        ],
+                        &[server_search("Errors by host")],
+                    )),
+                },
+                Effect::LoadDashboard { source } => Action::DashboardLoaded {
+                    source,
+                    result: Ok(DASHBOARD.into()),
+                },
+                Effect::Copy { what, text, .. } => Action::Copied {
+                    what,
+                    bytes: text.len(),
+                    result: Ok("OSC 52".into()),
+                },
+                // Saved searches live in the test storage directory; config
+                // and history effects are left alone.
+                effect @ (Effect::SaveSearch { .. }
+                | Effect::LoadSavedSearches
+                | Effect::ChangeSavedSearch { .. }) => return crate::effect::store(effect),
+                Effect::Connect(_) | Effect::SetNamespace(_) | Effect::OpenUrl(_) => return None,
+                effect => {
+                    self.unanswered.push(effect);
+                    return None;
+                }
+            };
+            Some(action)
+        }
+
+        /// Renders the app and compares the screen and cursor with
+        /// `src/snapshots/<name>.snap`. With `UPDATE_SNAPSHOTS=1` it is
+        /// written instead; otherwise a missing snapshot fails the test.
+        fn snapshot(&mut self, name: &str) {
+            // Off screen, so it stays put when `ui` hides the cursor.
+            let hidden = Position::new(u16::MAX, u16::MAX);
+            self.terminal.set_cursor_position(hidden).unwrap();
+            self.terminal.draw(|f| ui(f, &mut self.app)).unwrap();
+            let cursor = match self.terminal.get_cursor_position().unwrap() {
+                p if p == hidden => "hidden".to_string(),
+                p => format!("{}, {}", p.x, p.y),
+            };
+            let actual = format!("{}cursor: {}\n", self.terminal.backend(), cursor);
+
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("src/snapshots")
+                .join(format!("{}.snap", name));
+            if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, actual).unwrap();
+                return;
+            }
+            let Ok(expected) = std::fs::read_to_string(&path) else {
+                panic!(
+                    "snapshot {} is missing, rerun with UPDATE_SNAPSHOTS=1 to write it:\n{}",
+                    name, actual
+                );
+            };
+            assert!(
+                expected == actual,
+                "snapshot {} differs, rerun with UPDATE_SNAPSHOTS=1 to accept:\n{}",
+                name,
+                actual
+            );
+        }
+    }
+
+    const DASHBOARD: &str = r#"<form>
+  <label>Web Overview</label>
+  <fieldset>
+    <input type="text" token="host"><label>Host</label><default>web*</default></input>
+  </fieldset>
+  <row>
+    <panel><title>Requests</title><table><search><query>index=web host=$host$ | stats count by host</query></search></table></panel>
+  </row>
+</form>"#;
+
+    fn server_search(name: &str) -> ServerSavedSearch {
+        ServerSavedSearch {
+            name: name.into(),
+            app: "search".into(),
+            owner: "admin".into(),
+            search: "index=web status>=500 | stats count by host".into(),
+            cron_schedule: "*/15 * * * *".into(),
+            is_scheduled: true,
+            earliest_time: "-24h".into(),
+            latest_time: "now".into(),
+            can_write: true,
+            ..Default::default()
+        }
+    }
+
+    fn events() -> Vec<Value> {
+        vec![
+            json!({"_time": "2024-05-01T12:00:03.000+00:00", "host": "web-1", "status": "500", "_raw": "GET /api/orders 500"}),
+            json!({"_time": "2024-05-01T12:00:02.000+00:00", "host": "web-2", "status": "200", "_raw": "GET /index.html 200"}),
+            json!({"_time": "2024-05-01T12:00:01.000+00:00", "host": "web-1", "status": "404", "_raw": "GET /favicon.ico 404"}),
+        ]
+    }
+
+    fn counts() -> Vec<Value> {
+        vec![
+            json!({"host": "web-1", "count": "42"}),
+            json!({"host": "web-2", "count": "17"}),
+            json!({"host": "web-3", "count": "5"}),
+        ]
+    }
+
+    #[test]
+    fn test_update_runs_a_search_job() {
+        let mut app = Harness::new().app;
+        app.replace_input("index=main sourcetype=syslog | head 2".into());
+        app.time_range.earliest = Some("-15m".into());
+
+        let effects = press(&mut app, KeyCode::Enter);
+        let [Effect::CreateSearch { query, range }] = effects.as_slice() else {
+            panic!("expected CreateSearch, status: {}", app.status_message);
+        };
+        let (query, range) = (query.clone(), range.clone());
+        // History is written by the executor, not by `update`.
+        let effects = app.update(Action::SearchCreated {
+            query,
+            range,
+            result: Ok("1700000000.1".into()),
+        });
+        assert!(matches!(
+            effects.as_slice(),
+            [Effect::AppendHistory(entry)] if entry.sid == "1700000000.1"
+        ));
+        assert_eq!(app.current_job_sid.as_deref(), Some("1700000000.1"));
+
+        // One poll at a time.
+        assert!(matches!(
+            app.update(Action::Tick).as_slice(),
+            [Effect::PollJob { sid }] if sid == "1700000000.1"
+        ));
+        assert!(app.update(Action::Tick).is_empty());
+        let status = JobStatus {
+            is_done: true,
+            dispatch_state: "DONE".into(),
+            result_count: 2,
+            run_duration: 0.5,
+            scan_count: 2,
+            event_count: 2,
+        };
+        let effects = app.update(Action::JobStatus {
+            sid: "1700000000.1".into(),
+            result: Ok(status),
+        });
+        assert!(matches!(effects.as_slice(), [Effect::FetchResults { .. }]));
+
+        // Replies for another job are dropped.
+        let rows = vec![
+            serde_json::json!({"_raw": "a"}),
+            serde_json::json!({"_raw": "b"}),
+        ];
+        app.update(Action::ResultsLoaded {
+            sid: "older".into(),
+            result: Ok(rows.clone()),
+        });
+        assert!(app.search_results.is_empty());
+        let effects = app.update(Action::ResultsLoaded {
+            sid: "1700000000.1".into(),
+            result: Ok(rows),
+        });
+        assert_eq!(app.search_results.len(), 2);
+        assert!(app.results_fetched);
+        assert!(matches!(
+            effects.as_slice(),
+            [
+                Effect::FetchTimeline { .. },
+                Effect::RecordCompletion {
+                    result_count: 2,
+                    ..
+                }
+            ]
+        ));
+        assert!(app.update(Action::Tick).is_empty());
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('x')).as_slice(),
+            [Effect::KillJob { .. }]
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('q')).as_slice(),
+            [Effect::Quit]
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_startup() {
+        let mut h = Harness::new();
+        h.snapshot("normal_empty");
+        h.keys("q");
+        assert!(matches!(h.unanswered.as_slice(), [Effect::Quit]));
+    }
+
+    #[test]
+    fn test_snapshot_editor_cursor() {
+        let mut h = Harness::new();
+        h.keys("e");
+        h.snapshot("editing_empty");
+
+        // The fourth line scrolls the three-line box.
+        h.keys("index=web<shift+enter>| where status>=500<shift+enter>| stats count by host");
+        h.keys("<shift+enter>| sort -count");
+        h.snapshot("editing_multiline_end");
+        h.keys("<up><up><end>");
+        h.snapshot("editing_multiline_up");
+
+        // Multi-byte and double-width characters before the cursor.
+        let mut h = Harness::new();
+        h.keys("e");
+        h.keys("city=\"Zürich\" msg=\"日本語\" | head 1<left><left><left><left><left><left><left><left><left><left>");
+        h.snapshot("editing_unicode");
+
+        // Clicking a line puts the cursor under the pointer.
+        let mut h = Harness::new();
+        h.app
+            .replace_input("index=web\nhost=\"Zürich-1\"\n| stats count".into());
+        h.mouse(MouseEventKind::Down(MouseButton::Left), 11, 3);
+        assert!(matches!(h.app.input_mode, InputMode::Editing));
+        h.snapshot("editing_click");
+    }
+
+    #[test]
+    fn test_snapshot_views() {
+        let mut h = Harness::new();
+        h.rows = events();
+        h.search("index=web");
+        assert!(h.app.results_fetched);
+        h.snapshot("view_table");
+        h.keys("<tab><tab>");
+        h.snapshot("view_table_detail");
+        h.keys("<tab>");
+
+        let mut h = Harness::new();
+        h.rows = counts();
+        h.search("index=web | stats count by host");
+        h.keys("<ctrl+v>");
+        assert_eq!(h.app.view_mode, ViewMode::Chart);
+        h.snapshot("view_chart");
+        h.keys("c");
+        h.snapshot("view_chart_bar");
+
+        h.rows = vec![json!({"count": "1234", "errors": "56"})];
+        h.search("index=web | stats count, count(eval(status>=500)) as errors");
+        h.keys("<ctrl+v>");
+        assert_eq!(h.app.view_mode, ViewMode::Stat);
+        h.snapshot("view_stat");
+        h.keys("<ctrl+v>");
+        assert_eq!(h.app.view_mode, ViewMode::RawEvents);
+        h.rows = events();
+        h.search("index=web");
+        h.snapshot("view_raw_events");
+    }
+
+    #[test]
+    fn test_snapshot_result_modes() {
+        let mut h = Harness::new();
+        h.rows = events();
+        h.timeline = (0..24)
+            .map(|i| TimelineBucket {
+                earliest_time: 1_714_521_600.0 + i as f64 * 3600.0,
+                duration: 3600.0,
+                total_count: (i * 7 % 11) as u64,
+            })
+            .collect();
+        h.search("index=web");
+
+        h.keys("/");
+        h.keys("status:5");
+        h.snapshot("local_search");
+        h.keys("<enter>");
+
+        h.keys("|where host=\"web-1\"");
+        h.snapshot("local_pipeline");
+        h.keys("<esc>");
+
+        h.keys("T<left><left><shift+left>");
+        assert!(matches!(h.app.input_mode, InputMode::Timeline));
+        h.snapshot("timeline");
+        h.keys("<esc>");
+
+        h.keys("<tab>y");
+        assert!(matches!(h.app.input_mode, InputMode::Yank));
+        h.snapshot("yank");
+        h.keys("s");
+        assert!(h.app.status_message.contains("via OSC 52"));
+    }
+
+    #[test]
+    fn test_snapshot_modals() {
+        let mut h = Harness::new();
+        h.keys("<f1>");
+        assert!(matches!(h.app.input_mode, InputMode::Help));
+        h.snapshot("help");
+        h.keys("<esc>");
+
+        h.keys(":them");
+        h.snapshot("palette");
+        h.keys("<esc>t");
+        assert!(matches!(h.app.input_mode, InputMode::ThemeSelect));
+        h.snapshot("theme_select");
+        h.keys("<esc>");
+
+        h.keys("a");
+        assert!(matches!(h.app.input_mode, InputMode::AppSelect));
+        h.snapshot("app_select");
+        h.keys("<esc>r");
+        assert!(matches!(h.app.input_mode, InputMode::ServerSearches));
+        h.snapshot("server_searches");
+        h.keys("<esc>");
+
+        h.app.namespace = Some(Namespace {
+            owner: "admin".into(),
+            app: "search".into(),
+        });
+        h.keys("S");
+        assert!(matches!(h.app.input_mode, InputMode::Sync));
+        h.snapshot("sync");
+    }
+
+    #[test]
+    fn test_snapshot_search_prompts() {
+        let mut h = Harness::new();
+        h.keys("eindex=web host=$host$<enter>");
+        assert!(matches!(h.app.input_mode, InputMode::TokenForm));
+        h.snapshot("token_form");
+
+        // All time with no time range in the query.
+        let mut h = Harness::new();
+        h.keys("eindex=web<enter>");
+        assert!(matches!(h.app.input_mode, InputMode::LintConfirm));
+        h.snapshot("lint_confirm");
+        h.keys("<esc><esc>");
+
+        let when = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().into();
+        h.app.history = vec![
+            HistoryEntry {
+                timestamp: when("2024-05-01T09:30:00Z"),
+                profile: "default".into(),
+                query: "index=web | stats count by host".into(),
+                earliest: Some("-24h".into()),
+                latest: None,
+                sid: "1714555800.7".into(),
+                result_count: Some(3),
+                duration: Some(1.25),
+            },
+            HistoryEntry {
+                timestamp: when("2024-05-01T10:05:00Z"),
+                profile: "default".into(),
+                query: "index=web status>=500\n| top uri".into(),
+                earliest: Some("-15m".into()),
+                latest: None,
+                sid: "1714557900.9".into(),
+                result_count: None,
+                duration: None,
+            },
+        ];
+        h.keys("H");
+        assert!(matches!(h.app.input_mode, InputMode::HistoryBrowser));
+        h.snapshot("history_browser");
+        h.keys("<esc>e<ctrl+r>stats");
+        assert!(matches!(h.app.input_mode, InputMode::HistorySearch));
+        h.snapshot("history_search");
+    }
+
+    #[test]
+    fn test_snapshot_save_and_load() {
+        let fixtures = [
+            ("snapshot-errors", "index=web status>=500", Some("web")),
+            ("snapshot-logins", "index=auth action=failure", None),
+        ];
+        for (name, query, folder) in fixtures {
+            let search = SavedSearch {
+                name: name.into(),
+                query: query.into(),
+                meta: SearchMetadata {
+                    description: format!("{} fixture", name),
+                    folder: folder.map(str::to_string),
+                    earliest: Some("-24h".into()),
+                    ..Default::default()
+                },
+            };
+            SavedSearchManager::save(&search).unwrap();
+        }
+
+        let mut h = Harness::new();
+        h.keys("eindex=web<esc><ctrl+s>");
+        assert!(matches!(h.app.input_mode, InputMode::SaveSearch));
+        h.keys("Zürich errors");
+        h.snapshot("save_search");
+        h.keys("<esc><ctrl+l>");
+        assert!(matches!(h.app.input_mode, InputMode::LoadSearch));
+        h.snapshot("load_search");
+        h.keys("<tab>");
+        h.snapshot("load_search_by_folder");
+
+        h.keys("<tab><tab>logins<enter>");
+        assert_eq!(
+            h.app.current_saved_search_name.as_deref(),
+            Some("snapshot-logins")
+        );
+        h.keys("<ctrl+s>");
+        assert!(matches!(h.app.input_mode, InputMode::ConfirmOverwrite));
+        h.snapshot("confirm_overwrite");
+    }
+
+    #[test]
+    fn test_snapshot_dashboard() {
+        let mut h = Harness::new();
+        h.rows = counts();
+        h.keys("Dweb.xml");
+        assert!(matches!(h.app.input_mode, InputMode::DashboardOpen));
+        h.snapshot("dashboard_open");
+        h.keys("<enter>");
+        h.send(Action::Tick);
+        assert!(matches!(h.app.input_mode, InputMode::Dashboard));
+        h.snapshot("dashboard");
+        h.keys("i");
+        assert!(matches!(h.app.input_mode, InputMode::DashboardInput));
+        h.snapshot("dashboard_input");
+    }
+
+    #[test]
+    fn test_dashboard_refresh_cancels_running_panels() {
+        let mut h = Harness::new();
+        h.keys("Dweb.xml<enter>");
+        let mut started = Vec::new();
+        for effect in h.app.update(Action::Tick) {
+            if let Effect::RunPanel {
+                index, generation, ..
+            } = effect
+            {
+                let sid = format!("panel{}", index);
+                let effects = h.app.update(Action::PanelStarted {
+                    index,
+                    generation,
+                    result: Ok(sid.clone()),
+                });
+                assert!(matches!(effects.as_slice(), [Effect::WaitForPanel { .. }]));
+                started.push((index, generation, sid));
+            }
+        }
+        assert!(!started.is_empty());
+
+        let cancelled = |effects: Vec<Effect>| -> Vec<String> {
+            effects
+                .into_iter()
+                .filter_map(|e| match e {
+                    Effect::CancelJob { sid } => Some(sid),
+                    _ => None,
+                })
+                .collect()
+        };
+        let refresh = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        let sids: Vec<String> = started.iter().map(|(_, _, sid)| sid.clone()).collect();
+        assert_eq!(cancelled(h.app.update(Action::Key(refresh))), sids);
+
+        // A job of the old run that starts after the refresh is cancelled too.
+        let (index, generation, _) = started[0];
+        let late = h.app.update(Action::PanelStarted {
+            index,
+            generation,
+            result: Ok("late".into()),
+        });
+        assert_eq!(cancelled(late), vec!["late"]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Copying to the system clipboard: an OSC 52 escape sequence, which the
+//! terminal handles even over SSH, or an external command such as `wl-copy`
+//! or `xclip -selection clipboard`. Also the text forms of a table that are
+//! copied or exported.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The OSC 52 sequence setting the clipboard to `text`, wrapped in a DCS
+/// passthrough inside tmux.
+pub fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// How long `clipboard_command` may take before it is killed.
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pipes `text` into `command`. Stdin is written from its own thread and the
+/// command is killed after `timeout`, so one that hangs or stops reading
+/// can't block the caller for long.
+pub fn run_command(
+    command: &str,
+    text: &str,
+    timeout: Duration,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("empty clipboard_command")?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().ok_or("no stdin")?;
+    let text = text.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(text.as_bytes()));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{} timed out after {:?}", program, timeout).into());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+    // A command that succeeds without reading everything chose to stop.
+    match writer.join().map_err(|_| "stdin writer panicked")? {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Sets the clipboard with an OSC 52 sequence on stdout.
+pub fn copy_osc52(text: &str) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52(text, std::env::var_os("TMUX").is_some()).as_bytes())?;
+    stdout.flush()
+}
+
+/// A table cell as displayed: strings unquoted, null and missing empty.
+pub fn cell_text(row: &Value, column: &str) -> String {
+    match row.get(column) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Tab-separated values with a header line; tabs and newlines in cells are
+/// escaped.
+pub fn tsv(columns: &[String], rows: &[Value]) -> String {
+    let escape = |s: &str| {
+        s.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+    };
+    let mut lines = vec![columns
+        .iter()
+        .map(|c| escape(c))
+        .collect::<Vec<_>>()
+        .join("\t")];
+    for row in rows {
+        let cells: Vec<String> = columns.iter().map(|c| escape(&cell_text(row, c))).collect();
+        lines.push(cells.join("\t"));
+    }
+    lines.join("\n")
+}
+
+/// RFC 4180 CSV: cells with commas, quotes or newlines are quoted.
+pub fn csv(columns: &[String], rows: &[Value]) -> String {
+    let escape = |s: &str| {
+        if s.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    };
+    let mut lines = vec![columns
+        .iter()
+        .map(|c| escape(c))
+        .collect::<Vec<_>>()
+        .join(",")];
+    for row in rows {
+        let cells: Vec<String> = columns.iter().map(|c| escape(&cell_text(row, c))).collect();
+        lines.push(cells.join(","));
+    }
+    lines.join("\r\n")
+}
+
+/// A Markdown (GitHub) table.
+pub fn markdown(columns: &[String], rows: &[Value]) -> String {
+    let escape = |s: &str| s.replace('|', "\\|").replace('\n', "<br>");
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let mut lines = vec![
+        line(columns.iter().map(|c| escape(c)).collect()),
+        line(columns.iter().map(|_| "---".to_string()).collect()),
+    ];
+    for row in rows {
+        lines.push(line(
+            columns.iter().map(|c| escape(&cell_text(row, c))).collect(),
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_osc52_and_table_formats() {
+        assert_eq!(osc52("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52("hi", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+
+        let columns = vec!["host".to_string(), "msg".to_string()];
+        let rows = vec![
+            json!({"host": "web", "msg": "a|b\nc"}),
+            json!({"host": "db", "msg": "x\ty", "count": 3}),
+        ];
+        assert_eq!(tsv(&columns, &rows), "host\tmsg\nweb\ta|b\\nc\ndb\tx\\ty");
+        assert_eq!(
+            csv(
+                &columns,
+                &[json!({"host": "web", "msg": "say \"hi\", bye"})]
+            ),
+            "host,msg\r\nweb,\"say \"\"hi\"\", bye\""
+        );
+        assert_eq!(
+            markdown(&columns, &rows),
+            "| host | msg |\n| --- | --- |\n| web | a\\|b<br>c |\n| db | x\ty |"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command() {
+        let timeout = Duration::from_secs(5);
+        // More than a pipe buffer holds.
+        assert!(run_command("cat", &"x".repeat(1 << 20), timeout).is_ok());
+        assert!(run_command("false", "hi", timeout).is_err());
+        // Never reads stdin and never exits.
+        let err =
+            run_command("sleep 30", &"x".repeat(1 << 20), Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.to_string(), "sleep timed out after 100ms");
+    }
+}
@@ -0,0 +1,61 @@
+/// Scores `pattern` as a case-insensitive subsequence of `text`.
+///
+/// Returns `None` when not every pattern character is found in order. Higher
+/// scores are better: consecutive runs and matches at word starts are
+/// rewarded, gaps are penalised. The second value holds the char indices of
+/// the matched characters for highlighting.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0i64;
+    let mut p = 0;
+    let mut last: Option<usize> = None;
+
+    for (i, c) in chars.iter().enumerate() {
+        if p == pattern.len() {
+            break;
+        }
+        if c.to_lowercase().eq(std::iter::once(pattern[p])) {
+            score += 1;
+            match last {
+                Some(l) if l + 1 == i => score += 5,
+                Some(l) => score -= (i - l - 1).min(5) as i64,
+                None => score -= i.min(10) as i64,
+            }
+            let at_word_start = i == 0 || !chars[i - 1].is_alphanumeric();
+            if at_word_start {
+                score += 3;
+            }
+            positions.push(i);
+            last = Some(i);
+            p += 1;
+        }
+    }
+
+    (p == pattern.len()).then_some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("flg", "failed_logins").is_some());
+        assert!(fuzzy_match("xyz", "failed_logins").is_none());
+        assert_eq!(fuzzy_match("FL", "failed_logins").unwrap().1, vec![0, 3]);
+
+        let (contiguous, _) = fuzzy_match("log", "logins").unwrap();
+        let (scattered, _) = fuzzy_match("log", "lateral_movement_go").unwrap();
+        assert!(contiguous > scattered);
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+#[cfg(not(test))]
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Oldest entries are dropped once the history grows past this.
+const MAX_ENTRIES: usize = 1000;
+
+/// One dispatched search. Count and duration are filled in once the job finishes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub profile: String,
+    pub query: String,
+    #[serde(default)]
+    pub earliest: Option<String>,
+    #[serde(default)]
+    pub latest: Option<String>,
+    pub sid: String,
+    #[serde(default)]
+    pub result_count: Option<u64>,
+    #[serde(default)]
+    pub duration: Option<f64>,
+}
+
+/// Query history stored as JSON lines in `history.jsonl`, oldest first.
+pub struct HistoryManager;
+
+impl HistoryManager {
+    #[cfg(not(test))]
+    fn get_history_path() -> PathBuf {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "spelunktui") {
+            let dir = proj_dirs.config_dir().to_path_buf();
+            if fs::create_dir_all(&dir).is_ok() {
+                return dir.join("history.jsonl");
+            }
+        }
+        PathBuf::from("history.jsonl")
+    }
+
+    #[cfg(test)]
+    fn get_history_path() -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push("spelunktui-tests");
+        let _ = fs::create_dir_all(&path);
+        path.push("history.jsonl");
+        path
+    }
+
+    /// Loads all entries, skipping lines that fail to parse.
+    pub fn load() -> io::Result<Vec<HistoryEntry>> {
+        let path = Self::get_history_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    pub fn append(entry: &HistoryEntry) -> io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::get_history_path())?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+    }
+
+    /// Records the outcome of the job `sid` and trims the file to `MAX_ENTRIES`.
+    pub fn record_completion(sid: &str, result_count: u64, duration: f64) -> io::Result<()> {
+        let mut entries = Self::load()?;
+        if let Some(entry) = entries.iter_mut().rev().find(|e| e.sid == sid) {
+            entry.result_count = Some(result_count);
+            entry.duration = Some(duration);
+        }
+        let skip = entries.len().saturating_sub(MAX_ENTRIES);
+        Self::write_all(&entries[skip..])
+    }
+
+    fn write_all(entries: &[HistoryEntry]) -> io::Result<()> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(Self::get_history_path(), content)
+    }
+}
+
+/// Index of the newest entry before `before` whose query contains `needle`
+/// (case-insensitive), for shell-style reverse incremental search.
+pub fn reverse_find(entries: &[HistoryEntry], needle: &str, before: usize) -> Option<usize> {
+    let needle = needle.to_lowercase();
+    entries[..before.min(entries.len())]
+        .iter()
+        .rposition(|e| e.query.to_lowercase().contains(&needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str, sid: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Utc::now(),
+            profile: "default".to_string(),
+            query: query.to_string(),
+            earliest: Some("-24h".to_string()),
+            latest: None,
+            sid: sid.to_string(),
+            result_count: None,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn test_reverse_find() {
+        let entries = vec![
+            entry("index=main", "1"),
+            entry("index=auth | stats count", "2"),
+            entry("index=MAIN error", "3"),
+        ];
+        assert_eq!(reverse_find(&entries, "main", entries.len()), Some(2));
+        assert_eq!(reverse_find(&entries, "main", 2), Some(0));
+        assert_eq!(reverse_find(&entries, "main", 0), None);
+        assert_eq!(reverse_find(&entries, "nope", 3), None);
+    }
+
+    #[test]
+    fn test_append_and_complete() {
+        let _ = fs::remove_file(HistoryManager::get_history_path());
+        HistoryManager::append(&entry("index=edr", "sid-a")).unwrap();
+        HistoryManager::append(&entry("index=edr | head", "sid-b")).unwrap();
+        HistoryManager::record_completion("sid-a", 42, 1.5).unwrap();
+
+        let entries = HistoryManager::load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].result_count, Some(42));
+        assert_eq!(entries[0].duration, Some(1.5));
+        assert_eq!(entries[1].result_count, None);
+    }
+}
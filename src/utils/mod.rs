@@ -1 +1,6 @@
+pub mod clipboard;
+pub mod fuzzy;
+pub mod history;
+pub mod result_search;
 pub mod saved_searches;
+pub mod token_values;
@@ -0,0 +1,163 @@
+//! The `/` search over loaded results: a regex matched against `_raw`, every
+//! field or the whole event as JSON, or scoped to one field with
+//! `field:regex`.
+
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scope {
+    Raw,
+    AllFields,
+    Json,
+}
+
+impl Scope {
+    pub fn next(self) -> Self {
+        match self {
+            Scope::Raw => Scope::AllFields,
+            Scope::AllFields => Scope::Json,
+            Scope::Json => Scope::Raw,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Raw => "_raw",
+            Scope::AllFields => "all fields",
+            Scope::Json => "JSON",
+        }
+    }
+}
+
+pub struct ResultSearch {
+    regex: Regex,
+    field: Option<String>,
+    scope: Scope,
+    invert: bool,
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+impl ResultSearch {
+    /// `name:` at the start of the query scopes the search to that field, but
+    /// only if `known_field(name)`, so `http://` still searches for URLs.
+    pub fn new(
+        query: &str,
+        scope: Scope,
+        case_sensitive: bool,
+        invert: bool,
+        known_field: impl Fn(&str) -> bool,
+    ) -> Result<ResultSearch, regex::Error> {
+        let (field, pattern) = match query.split_once(':') {
+            Some((name, rest))
+                if !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+                    && known_field(name) =>
+            {
+                (Some(name.to_string()), rest)
+            }
+            _ => (None, query),
+        };
+        Ok(ResultSearch {
+            regex: RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .build()?,
+            field,
+            scope,
+            invert,
+        })
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    pub fn matches(&self, row: &Value) -> bool {
+        let found = match (&self.field, self.scope) {
+            (Some(field), _) => row
+                .get(field)
+                .is_some_and(|v| self.regex.is_match(&text(v))),
+            (None, Scope::Raw) => row
+                .get("_raw")
+                .is_some_and(|v| self.regex.is_match(&text(v))),
+            (None, Scope::AllFields) => row
+                .as_object()
+                .is_some_and(|o| o.values().any(|v| self.regex.is_match(&text(v)))),
+            (None, Scope::Json) => self.regex.is_match(&row.to_string()),
+        };
+        found != self.invert
+    }
+
+    /// Byte ranges of the matches in `text`, a displayed value of `field`.
+    /// Text that isn't one field's value (`None`) is always searched. Inverted
+    /// searches highlight nothing.
+    pub fn highlights(&self, field: Option<&str>, text: &str) -> Vec<(usize, usize)> {
+        let applies = match (field, &self.field) {
+            (None, _) => true,
+            (Some(shown), Some(scoped)) => shown == scoped,
+            (Some(shown), None) => self.scope != Scope::Raw || shown == "_raw",
+        };
+        if self.invert || !applies {
+            return Vec::new();
+        }
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scopes_and_highlights() {
+        let row = json!({"_raw": "GET http://a/login", "user": "Alice", "bytes": 512});
+        let known = |name: &str| name == "user";
+
+        let search = ResultSearch::new("user:^ali", Scope::Raw, false, false, known).unwrap();
+        assert_eq!(search.field(), Some("user"));
+        assert!(search.matches(&row));
+        assert_eq!(search.highlights(Some("user"), "Alice"), vec![(0, 3)]);
+        assert!(search.highlights(Some("_raw"), "Alice").is_empty());
+
+        // Not a known field, so the colon is part of the regex.
+        let search = ResultSearch::new("http://a", Scope::Raw, false, false, known).unwrap();
+        assert!(search.field().is_none());
+        assert!(search.matches(&row));
+
+        assert!(!ResultSearch::new("alice", Scope::Raw, false, false, known)
+            .unwrap()
+            .matches(&row));
+        assert!(
+            ResultSearch::new("alice", Scope::AllFields, false, false, known)
+                .unwrap()
+                .matches(&row)
+        );
+        assert!(
+            !ResultSearch::new("alice", Scope::AllFields, true, false, known)
+                .unwrap()
+                .matches(&row)
+        );
+        assert!(
+            ResultSearch::new(r#""bytes":512"#, Scope::Json, false, false, known)
+                .unwrap()
+                .matches(&row)
+        );
+
+        let inverted = ResultSearch::new("login", Scope::Raw, false, true, known).unwrap();
+        assert!(!inverted.matches(&row));
+        assert!(inverted.highlights(None, "login").is_empty());
+    }
+}
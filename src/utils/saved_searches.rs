@@ -1,9 +1,70 @@
 #[cfg(not(test))]
 use directories::ProjectDirs;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
 
+/// Optional metadata stored in a `<name>.toml` sidecar next to `<name>.spl`.
+///
+/// Searches saved before sidecars existed simply have no `.toml` file and load
+/// with default metadata; the sidecar is written the next time they are saved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchMetadata {
+    pub description: String,
+    pub author: String,
+    pub tags: Vec<String>,
+    pub folder: Option<String>,
+    pub earliest: Option<String>,
+    pub latest: Option<String>,
+    /// "table", "chart", "stat" or "raw".
+    pub view_mode: Option<String>,
+    pub columns: Vec<String>,
+    /// Defaults and validation for `$token$` placeholders in the query.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokens: BTreeMap<String, TokenSpec>,
+    /// Single-value panel settings for KPI searches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stat: Option<StatConfig>,
+}
+
+/// A `[tokens.<name>]` table in the sidecar.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenSpec {
+    pub default: Option<String>,
+    /// Regex the whole value must match.
+    pub pattern: Option<String>,
+}
+
+/// The `[stat]` table in the sidecar.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatConfig {
+    /// Time range to run the search over again for the delta, e.g. the
+    /// previous day with `compare_earliest = "-48h"`, `compare_latest = "-24h"`.
+    pub compare_earliest: Option<String>,
+    pub compare_latest: Option<String>,
+    pub thresholds: Vec<Threshold>,
+}
+
+/// Values at or above `value` are drawn in `color` (a name, index or `#rrggbb`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Threshold {
+    pub value: f64,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    pub meta: SearchMetadata,
+}
+
 pub struct SavedSearchManager;
 
 impl SavedSearchManager {
@@ -24,15 +85,69 @@ impl SavedSearchManager {
         path
     }
 
+    /// A fresh directory per test thread, removed when the test ends, so
+    /// tests never see each other's searches.
     #[cfg(test)]
     fn get_storage_dir() -> PathBuf {
-        let mut path = std::env::temp_dir();
-        path.push("spelunktui-tests");
-        path.push("saved_searches");
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct TestDir(PathBuf);
+        impl Drop for TestDir {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+        thread_local! {
+            static DIR: TestDir = {
+                static NEXT: AtomicUsize = AtomicUsize::new(0);
+                let path = std::env::temp_dir().join("spelunktui-tests").join(format!(
+                    "saved_searches-{}-{}",
+                    std::process::id(),
+                    NEXT.fetch_add(1, Ordering::Relaxed)
+                ));
+                let _ = fs::remove_dir_all(&path);
+                TestDir(path)
+            };
+        }
+        let path = DIR.with(|dir| dir.0.clone());
         let _ = fs::create_dir_all(&path);
         path
     }
 
+    /// The file name stem for `name`. `%`, path separators, control
+    /// characters and a leading `.` are percent-encoded, so names such as
+    /// `Errors / min` or `..` from the server stay inside the directory.
+    fn file_stem(name: &str) -> String {
+        let mut stem = String::with_capacity(name.len());
+        for (i, c) in name.chars().enumerate() {
+            if matches!(c, '%' | '/' | '\\') || c.is_control() || (i == 0 && c == '.') {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    stem.push_str(&format!("%{:02X}", b));
+                }
+            } else {
+                stem.push(c);
+            }
+        }
+        stem
+    }
+
+    fn check_name(name: &str) -> io::Result<()> {
+        if name.trim().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a saved search name cannot be empty",
+            ));
+        }
+        Ok(())
+    }
+
+    fn metadata_path(name: &str) -> PathBuf {
+        let mut path = Self::get_storage_dir();
+        path.push(format!("{}.toml", Self::file_stem(name)));
+        path
+    }
+
     pub fn list_searches() -> io::Result<Vec<String>> {
         let dir = Self::get_storage_dir();
         let mut searches = Vec::new();
@@ -42,8 +157,11 @@ impl SavedSearchManager {
                 let entry = entry?;
                 let path = entry.path();
                 if path.extension().and_then(|s| s.to_str()) == Some("spl") {
-                    if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                        searches.push(name.to_string());
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        let name = urlencoding::decode(stem)
+                            .map(|name| name.into_owned())
+                            .unwrap_or_else(|_| stem.to_string());
+                        searches.push(name);
                     }
                 }
             }
@@ -52,15 +170,211 @@ impl SavedSearchManager {
         Ok(searches)
     }
 
+    /// Writes the query only, leaving any existing sidecar untouched.
     pub fn save_search(name: &str, query: &str) -> io::Result<()> {
-        let mut path = Self::get_storage_dir();
-        path.push(format!("{}.spl", name));
-        fs::write(path, query)
+        Self::check_name(name)?;
+        fs::write(Self::query_path(name), query)
     }
 
     pub fn load_search(name: &str) -> io::Result<String> {
+        fs::read_to_string(Self::query_path(name))
+    }
+
+    /// Reads the sidecar for `name`, falling back to defaults when it is missing.
+    pub fn load_metadata(name: &str) -> io::Result<SearchMetadata> {
+        let path = Self::metadata_path(name);
+        if !path.exists() {
+            return Ok(SearchMetadata::default());
+        }
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save_metadata(name: &str, meta: &SearchMetadata) -> io::Result<()> {
+        let content =
+            toml::to_string(meta).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(Self::metadata_path(name), content)
+    }
+
+    /// Loads a search together with its metadata.
+    pub fn load(name: &str) -> io::Result<SavedSearch> {
+        Ok(SavedSearch {
+            name: name.to_string(),
+            query: Self::load_search(name)?,
+            // A broken sidecar shouldn't make the query itself unusable.
+            meta: Self::load_metadata(name).unwrap_or_else(|e| {
+                warn!("Ignoring invalid metadata for '{}': {}", name, e);
+                SearchMetadata::default()
+            }),
+        })
+    }
+
+    /// Saves a search and its metadata sidecar.
+    pub fn save(search: &SavedSearch) -> io::Result<()> {
+        Self::save_search(&search.name, &search.query)?;
+        Self::save_metadata(&search.name, &search.meta)
+    }
+
+    /// Saves the query and view settings (time range, view mode, columns) of
+    /// `search`, keeping the description, tags, folder, tokens and stat
+    /// config already stored under its name. Returns what was written.
+    pub fn save_view(search: SavedSearch) -> io::Result<SavedSearch> {
+        let mut meta = Self::load_metadata(&search.name).unwrap_or_default();
+        if meta.author.is_empty() {
+            meta.author = std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_default();
+        }
+        meta.earliest = search.meta.earliest;
+        meta.latest = search.meta.latest;
+        meta.view_mode = search.meta.view_mode;
+        meta.columns = search.meta.columns;
+        let search = SavedSearch { meta, ..search };
+        Self::save(&search)?;
+        Ok(search)
+    }
+
+    fn query_path(name: &str) -> PathBuf {
         let mut path = Self::get_storage_dir();
-        path.push(format!("{}.spl", name));
-        fs::read_to_string(path)
+        path.push(format!("{}.spl", Self::file_stem(name)));
+        path
+    }
+
+    /// Deletes a search and its sidecar.
+    pub fn delete_search(name: &str) -> io::Result<()> {
+        fs::remove_file(Self::query_path(name))?;
+        match fs::remove_file(Self::metadata_path(name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Renames a search and its sidecar, refusing to clobber an existing one.
+    pub fn rename_search(old: &str, new: &str) -> io::Result<()> {
+        Self::check_name(new)?;
+        if Self::query_path(new).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("a saved search named '{}' already exists", new),
+            ));
+        }
+        fs::rename(Self::query_path(old), Self::query_path(new))?;
+        let old_meta = Self::metadata_path(old);
+        if old_meta.exists() {
+            fs::rename(old_meta, Self::metadata_path(new))?;
+        }
+        Ok(())
+    }
+
+    /// Copies a search and its metadata to a new name.
+    pub fn duplicate_search(name: &str, new: &str) -> io::Result<()> {
+        Self::check_name(new)?;
+        if Self::query_path(new).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("a saved search named '{}' already exists", new),
+            ));
+        }
+        let mut search = Self::load(name)?;
+        search.name = new.to_string();
+        Self::save(&search)
+    }
+
+    /// Loads every saved search with metadata, sorted by name.
+    pub fn load_all() -> io::Result<Vec<SavedSearch>> {
+        Self::list_searches()?
+            .iter()
+            .map(|name| Self::load(name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_search_without_sidecar() {
+        let name = "legacy-no-sidecar";
+        let _ = fs::remove_file(SavedSearchManager::metadata_path(name));
+        SavedSearchManager::save_search(name, "index=main").unwrap();
+
+        let search = SavedSearchManager::load(name).unwrap();
+        assert_eq!(search.query, "index=main");
+        assert_eq!(search.meta, SearchMetadata::default());
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let search = SavedSearch {
+            name: "with-sidecar".to_string(),
+            query: "index=auth | stats count by user".to_string(),
+            meta: SearchMetadata {
+                description: "Failed logins per user".to_string(),
+                tags: vec!["auth".to_string(), "triage".to_string()],
+                folder: Some("identity".to_string()),
+                earliest: Some("-24h".to_string()),
+                view_mode: Some("table".to_string()),
+                columns: vec!["user".to_string(), "count".to_string()],
+                ..Default::default()
+            },
+        };
+        SavedSearchManager::save(&search).unwrap();
+
+        let loaded = SavedSearchManager::load("with-sidecar").unwrap();
+        assert_eq!(loaded.query, search.query);
+        assert_eq!(loaded.meta, search.meta);
+    }
+
+    #[test]
+    fn test_rename_duplicate_delete() {
+        for name in ["rename-src", "rename-dst", "rename-copy"] {
+            let _ = SavedSearchManager::delete_search(name);
+        }
+        let search = SavedSearch {
+            name: "rename-src".to_string(),
+            query: "index=edr".to_string(),
+            meta: SearchMetadata {
+                tags: vec!["edr".to_string()],
+                ..Default::default()
+            },
+        };
+        SavedSearchManager::save(&search).unwrap();
+
+        SavedSearchManager::rename_search("rename-src", "rename-dst").unwrap();
+        assert!(SavedSearchManager::load("rename-src").is_err());
+        assert_eq!(
+            SavedSearchManager::load("rename-dst").unwrap().meta.tags,
+            vec!["edr".to_string()]
+        );
+
+        SavedSearchManager::duplicate_search("rename-dst", "rename-copy").unwrap();
+        assert!(SavedSearchManager::duplicate_search("rename-dst", "rename-copy").is_err());
+        assert_eq!(
+            SavedSearchManager::load("rename-copy").unwrap().query,
+            "index=edr"
+        );
+
+        SavedSearchManager::delete_search("rename-dst").unwrap();
+        SavedSearchManager::delete_search("rename-copy").unwrap();
+        assert!(SavedSearchManager::load("rename-copy").is_err());
+    }
+
+    #[test]
+    fn test_names_stay_in_the_directory() {
+        let dir = SavedSearchManager::get_storage_dir();
+        for name in ["../escape", "Errors / min", "..", "50% of \\hosts"] {
+            SavedSearchManager::save_search(name, "index=main").unwrap();
+            let path = SavedSearchManager::query_path(name);
+            assert_eq!(path.parent(), Some(dir.as_path()), "{}", name);
+            assert!(SavedSearchManager::list_searches()
+                .unwrap()
+                .contains(&name.to_string()));
+            assert_eq!(SavedSearchManager::load(name).unwrap().query, "index=main");
+            SavedSearchManager::delete_search(name).unwrap();
+        }
+        assert_eq!(SavedSearchManager::file_stem("../a/b"), "%2E.%2Fa%2Fb");
+        assert!(SavedSearchManager::save_search(" ", "index=main").is_err());
+        assert!(SavedSearchManager::rename_search("missing", "").is_err());
     }
 }
@@ -0,0 +1,78 @@
+#[cfg(not(test))]
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Recent values kept per token.
+const MAX_VALUES: usize = 10;
+
+/// Recently used values of `$token$` placeholders, most recent first, stored
+/// in `token_values.json`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TokenValues(BTreeMap<String, Vec<String>>);
+
+impl TokenValues {
+    #[cfg(not(test))]
+    fn get_path() -> PathBuf {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "spelunktui") {
+            let dir = proj_dirs.config_dir().to_path_buf();
+            if fs::create_dir_all(&dir).is_ok() {
+                return dir.join("token_values.json");
+            }
+        }
+        PathBuf::from("token_values.json")
+    }
+
+    #[cfg(test)]
+    fn get_path() -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push("spelunktui-tests");
+        let _ = fs::create_dir_all(&path);
+        path.push("token_values.json");
+        path
+    }
+
+    /// Loads stored values; a missing or unreadable file gives an empty set.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::get_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(Self::get_path(), serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn recent(&self, name: &str) -> &[String] {
+        self.0.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn remember(&mut self, name: &str, value: &str) {
+        let values = self.0.entry(name.to_string()).or_default();
+        values.retain(|v| v != value);
+        values.insert(0, value.to_string());
+        values.truncate(MAX_VALUES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_orders_and_persists() {
+        let mut values = TokenValues::default();
+        values.remember("host", "a");
+        values.remember("host", "b");
+        values.remember("host", "a");
+        assert_eq!(values.recent("host"), ["a", "b"]);
+        assert!(values.recent("user").is_empty());
+
+        values.save().unwrap();
+        assert_eq!(TokenValues::load().recent("host"), ["a", "b"]);
+    }
+}